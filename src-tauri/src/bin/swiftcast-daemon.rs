@@ -0,0 +1,1402 @@
+//! Headless SwiftCast: runs the proxy without Tauri and manages the same SQLite DB from the shell.
+
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use std::sync::Arc;
+use swiftcast_lib::claude_settings;
+use swiftcast_lib::models::webhook_sink::DEFAULT_SIGNATURE_HEADER;
+use swiftcast_lib::models::{
+    project_name, Account, AlertChannel, AlertMetric, AlertRule, AlertScope, AlertWindow, SessionState, StepConfig, WebhookSink,
+};
+use swiftcast_lib::proxy::hooks::CustomTaskHook;
+use swiftcast_lib::proxy::question_detector::{questions_from_tool_input, QuestionRules};
+use swiftcast_lib::proxy::ProxyServer;
+use swiftcast_lib::proxy::RequestKind;
+use swiftcast_lib::proxy::search;
+use swiftcast_lib::proxy::webhook::{WebhookClient, WebhookPayload};
+use swiftcast_lib::storage::database::{KindRoute, LatencyGroup, PhaseStats, SearchQuery, SubagentRouting, ToolStatsFilter};
+use swiftcast_lib::storage::usage_export::{self, ExportFormat, ExportOptions, GroupKey, Period};
+use swiftcast_lib::storage::Database;
+
+#[derive(Parser)]
+#[command(name = "swiftcast-daemon", version, about = "Headless SwiftCast proxy and CLI")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the proxy in the foreground until Ctrl-C
+    Serve {
+        /// Port to listen on (defaults to the configured proxy port)
+        #[arg(long)]
+        port: Option<u16>,
+        /// Do not point ~/.claude/settings.json at the proxy
+        #[arg(long)]
+        no_claude_settings: bool,
+    },
+    /// Manage accounts
+    #[command(subcommand)]
+    Accounts(AccountsCommand),
+    /// Manage per-session routing
+    #[command(subcommand)]
+    Sessions(SessionsCommand),
+    /// Usage reports
+    #[command(subcommand)]
+    Usage(UsageCommand),
+    /// Request kinds (main turn, subagent, title generation, ...) and their routing
+    #[command(subcommand)]
+    Kinds(KindsCommand),
+    /// Custom tasks (>>swiftcast <name>)
+    #[command(subcommand)]
+    Tasks(TasksCommand),
+    /// Tool call statistics
+    #[command(subcommand)]
+    Tools(ToolsCommand),
+    /// Upstream and transport errors
+    #[command(subcommand)]
+    Errors(ErrorsCommand),
+    /// Threshold alerts on cost, tokens, requests and error rate
+    #[command(subcommand)]
+    Alerts(AlertsCommand),
+    /// Webhook sinks that receive usage, question, step and session events
+    #[command(subcommand)]
+    Webhooks(WebhooksCommand),
+    /// Tool-to-phase mapping and time spent per phase (analysis, design, implementation, verification)
+    #[command(subcommand)]
+    Steps(StepsCommand),
+    /// AI question detection rules (~/.sessioncast/question_rules.toml)
+    #[command(subcommand)]
+    Questions(QuestionsCommand),
+    /// Full-text search over conversation transcripts (prompts, assistant text, tool inputs)
+    Search {
+        /// Words (matched literally), "phrases", prefix*, tool:<name>, OR, NOT
+        #[arg(required = true)]
+        query: Vec<String>,
+        /// Session id (includes its subagents)
+        #[arg(long)]
+        session: Option<String>,
+        /// Account id or name
+        #[arg(long)]
+        account: Option<String>,
+        #[arg(long)]
+        model: Option<String>,
+        /// Request kind (see `kinds list`)
+        #[arg(long)]
+        kind: Option<String>,
+        /// First day to include (YYYY-MM-DD, local time)
+        #[arg(long)]
+        since: Option<String>,
+        /// Last day to include (YYYY-MM-DD, local time)
+        #[arg(long)]
+        until: Option<String>,
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+    },
+}
+
+#[derive(Subcommand)]
+enum AccountsCommand {
+    /// Add an account (the first account becomes active)
+    Add {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        base_url: String,
+        /// API key (falls back to $SWIFTCAST_API_KEY)
+        #[arg(long, env = "SWIFTCAST_API_KEY", hide_env_values = true)]
+        api_key: String,
+    },
+    /// List accounts
+    List,
+    /// Switch the active account by id or name
+    Switch { account: String },
+    /// Set upstream timeouts in seconds (omit a flag to use the default)
+    Timeouts {
+        account: String,
+        #[arg(long)]
+        connect: Option<u32>,
+        #[arg(long)]
+        first_byte: Option<u32>,
+        #[arg(long)]
+        idle: Option<u32>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SessionsCommand {
+    /// List sessions active in the last 24 hours
+    List,
+    /// Route a session to an account (and optionally override the model)
+    Set {
+        session_id: String,
+        /// Account id or name
+        #[arg(long)]
+        account: String,
+        #[arg(long)]
+        model: Option<String>,
+    },
+    /// List subagent sessions spawned by a session
+    Subagents { session_id: String },
+    /// Show a session's state, wall/active time and turns
+    Turns { session_id: String },
+    /// Mark a session ended
+    End { session_id: String },
+    /// Show what happened in a session: messages, thinking, tool calls and results
+    Timeline {
+        session_id: String,
+        /// Interleave the session's subagents
+        #[arg(long)]
+        subagents: bool,
+        /// Print full content instead of one line per entry
+        #[arg(long)]
+        full: bool,
+        #[arg(long, default_value_t = 500)]
+        limit: i64,
+    },
+    /// Route new subagent sessions (no flags: inherit the parent's account and keep the model)
+    SubagentRoute {
+        /// Account id or name
+        #[arg(long)]
+        account: Option<String>,
+        #[arg(long)]
+        model: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum UsageCommand {
+    /// Print totals plus per-account, per-model and daily breakdowns
+    Report {
+        #[arg(long, default_value_t = 7)]
+        days: i64,
+        /// Only usage from this project (working directory path or directory name)
+        #[arg(long)]
+        project: Option<String>,
+    },
+    /// Export usage as CSV or JSON Lines (one line per request, or grouped with --by / --group-by)
+    Export {
+        /// Output file ("-" for stdout)
+        #[arg(long, short, default_value = "-")]
+        output: String,
+        /// csv or jsonl (defaults to the output file extension, then csv)
+        #[arg(long)]
+        format: Option<String>,
+        /// First day to include (YYYY-MM-DD, local time)
+        #[arg(long)]
+        since: Option<String>,
+        /// Last day to include (YYYY-MM-DD, local time)
+        #[arg(long)]
+        until: Option<String>,
+        /// Aggregate per day or month
+        #[arg(long)]
+        by: Option<String>,
+        /// Comma-separated group keys: origin, account, model, session, kind, project, branch
+        #[arg(long)]
+        group_by: Option<String>,
+        /// Label for this machine's rows (defaults to $USER)
+        #[arg(long)]
+        origin: Option<String>,
+    },
+    /// Time to first token, throughput and time spent in the proxy
+    Latency {
+        /// account, model or hour (local hour of day)
+        #[arg(long, default_value = "model")]
+        by: String,
+        #[arg(long, default_value_t = 7)]
+        days: i64,
+        #[arg(long)]
+        project: Option<String>,
+    },
+    /// Merge a per-request export from another machine (rows already present are skipped)
+    Import {
+        path: String,
+        /// Account (id or name) for rows whose account is not known here
+        #[arg(long)]
+        account: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum KindsCommand {
+    /// List request kinds and their routes
+    List,
+    /// Route every request of a kind to an account (omit --account to remove the route)
+    Route {
+        kind: String,
+        /// Account id or name
+        #[arg(long)]
+        account: Option<String>,
+        #[arg(long)]
+        model: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ToolsCommand {
+    /// Most used tools, error rates, tool use per model, Bash commands and edits per file
+    Report {
+        /// Session id (includes its subagents)
+        #[arg(long)]
+        session: Option<String>,
+        #[arg(long)]
+        model: Option<String>,
+        /// Only the last N days
+        #[arg(long)]
+        days: Option<i64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ErrorsCommand {
+    /// Most recent errors
+    List {
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+        /// Account id or name
+        #[arg(long)]
+        account: Option<String>,
+    },
+    /// Error rate over time, by account and by error type
+    Report {
+        #[arg(long, default_value_t = 7)]
+        days: i64,
+        /// Timeline per hour instead of per day
+        #[arg(long)]
+        hourly: bool,
+        /// Account id or name
+        #[arg(long)]
+        account: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AlertsCommand {
+    /// List alert rules
+    List,
+    /// Add an alert rule
+    Add {
+        #[arg(long)]
+        name: String,
+        /// cost_usd, tokens, requests, errors or error_rate
+        #[arg(long)]
+        metric: String,
+        /// error_rate is a fraction (0.2 = 20%)
+        #[arg(long)]
+        threshold: f64,
+        /// 10m, 2h, day or total
+        #[arg(long)]
+        window: String,
+        /// global, account, model or session
+        #[arg(long, default_value = "global")]
+        scope: String,
+        /// Only count this account (id or name)
+        #[arg(long)]
+        account: Option<String>,
+        /// Only count this model
+        #[arg(long)]
+        model: Option<String>,
+        /// Only count this error type (errors / error_rate), e.g. authentication_error
+        #[arg(long)]
+        error_type: Option<String>,
+        /// Minimum requests in the window before error_rate fires (default 5)
+        #[arg(long)]
+        min_requests: Option<i64>,
+        /// event, webhook:<url> or command:<shell command> (repeatable, default event)
+        #[arg(long = "channel")]
+        channels: Vec<String>,
+    },
+    /// Remove an alert rule
+    Remove {
+        /// Rule id or name
+        rule: String,
+    },
+    /// Alerts that fired
+    History {
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+    },
+}
+
+#[derive(Subcommand)]
+enum WebhooksCommand {
+    /// List webhook sinks
+    List,
+    /// Add a webhook sink
+    Add {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        url: String,
+        /// Only these events (repeatable): usage_logged, ai_question_detected, step_update, session_complete
+        #[arg(long = "event")]
+        events: Vec<String>,
+        /// HMAC-SHA256 signing key
+        #[arg(long)]
+        secret: Option<String>,
+        /// Header carrying the signature
+        #[arg(long, default_value = DEFAULT_SIGNATURE_HEADER)]
+        signature_header: String,
+        /// Extra header as Name:value (repeatable)
+        #[arg(long = "header")]
+        headers: Vec<String>,
+        /// JSON body template with {{path}} placeholders, or @file
+        #[arg(long)]
+        template: Option<String>,
+    },
+    /// Remove a webhook sink
+    Remove {
+        /// Sink id or name
+        sink: String,
+    },
+    /// Send a test event to a sink
+    Test {
+        /// Sink id or name
+        sink: String,
+    },
+    /// Events waiting to be delivered (including retries)
+    Pending {
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+    },
+    /// Deliveries that failed too many times
+    Failed {
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+        /// Sink id or name
+        #[arg(long)]
+        sink: Option<String>,
+    },
+    /// Queue failed deliveries again (picked up by the running proxy within 30s)
+    Replay {
+        /// Dead letter ids (see `webhooks failed`)
+        ids: Vec<i64>,
+        /// Replay all failed deliveries (of --sink)
+        #[arg(long)]
+        all: bool,
+        #[arg(long)]
+        sink: Option<String>,
+    },
+    /// Delete failed deliveries
+    Purge {
+        ids: Vec<i64>,
+        /// Delete all failed deliveries (of --sink)
+        #[arg(long)]
+        all: bool,
+        #[arg(long)]
+        sink: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum StepsCommand {
+    /// Show the tool-to-phase mapping
+    Rules {
+        /// Print as JSON (input for `steps set`)
+        #[arg(long)]
+        json: bool,
+    },
+    /// Replace the mapping with a JSON file ({"phases": [...], "rules": [...], "idle_timeout_secs": ...})
+    Set { path: String },
+    /// Restore the built-in mapping
+    Reset,
+    /// Phase spans of a session
+    Timeline { session_id: String },
+    /// Time per phase across sessions
+    Phases {
+        #[arg(long, default_value_t = 7)]
+        days: i64,
+    },
+}
+
+#[derive(Subcommand)]
+enum QuestionsCommand {
+    /// List active rules in evaluation order
+    Rules,
+    /// Run sample text through the rules
+    Test {
+        text: Vec<String>,
+        /// Read the sample from a file
+        #[arg(long, conflicts_with = "text")]
+        file: Option<String>,
+        /// Parse an AskUserQuestion tool input (JSON) instead of text
+        #[arg(long, conflicts_with_all = ["text", "file"])]
+        tool_input: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TasksCommand {
+    /// List configured custom tasks
+    List,
+    /// Show a task's usage and parameters
+    Show { name: String },
+}
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .with_target(false)
+        .init();
+
+    let cli = Cli::parse();
+    if let Err(e) = run(cli).await {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let db = Arc::new(Database::init().await?);
+
+    match cli.command {
+        Command::Serve { port, no_claude_settings } => serve(db, port, !no_claude_settings).await,
+        Command::Accounts(cmd) => accounts(&db, cmd).await,
+        Command::Sessions(cmd) => sessions(&db, cmd).await,
+        Command::Usage(UsageCommand::Report { days, project }) => usage_report(&db, days, project.as_deref()).await,
+        Command::Usage(UsageCommand::Export { output, format, since, until, by, group_by, origin }) => {
+            let format = match format {
+                Some(f) => ExportFormat::parse(&f).ok_or_else(|| anyhow!("unknown format: {}", f))?,
+                None => ExportFormat::from_path(std::path::Path::new(&output)).unwrap_or_default(),
+            };
+            let period = match by {
+                Some(p) => Some(Period::parse(&p).ok_or_else(|| anyhow!("--by must be day or month"))?),
+                None => None,
+            };
+            let options = ExportOptions {
+                format,
+                since: since.as_deref().map(local_day_start).transpose()?,
+                until: until.as_deref().map(|d| local_day_start(d).map(|t| t + 24 * 60 * 60)).transpose()?,
+                origin,
+                period,
+                group_by: GroupKey::parse_list(group_by.as_deref().unwrap_or_default())?,
+            };
+            let (content, count) = usage_export::export_usage(&db, &options).await?;
+            if output == "-" {
+                print!("{}", content);
+            } else {
+                std::fs::write(&output, content)?;
+                eprintln!("Wrote {} records to {}", count, output);
+            }
+            Ok(())
+        }
+        Command::Usage(UsageCommand::Latency { by, days, project }) => {
+            let by = LatencyGroup::parse(&by).ok_or_else(|| anyhow!("--by must be account, model or hour"))?;
+            println!("Last {} days by {}:", days, by.as_str());
+            print_latency(&db, by, days, project.as_deref()).await
+        }
+        Command::Usage(UsageCommand::Import { path, account }) => {
+            let fallback = match account {
+                Some(key) => Some(find_account(&db, &key).await?.id),
+                None => None,
+            };
+            let content = std::fs::read_to_string(&path)?;
+            let format = ExportFormat::from_path(std::path::Path::new(&path));
+            let report = usage_export::import_usage(&db, &content, format, fallback.as_deref()).await?;
+            println!(
+                "Imported {} rows ({} already present, {} skipped: unknown account)",
+                report.imported, report.duplicates, report.skipped_unknown_account
+            );
+            Ok(())
+        }
+        Command::Kinds(cmd) => kinds(&db, cmd).await,
+        Command::Tasks(TasksCommand::List) => list_tasks().await,
+        Command::Tasks(TasksCommand::Show { name }) => show_task(&name).await,
+        Command::Errors(cmd) => errors(&db, cmd).await,
+        Command::Alerts(cmd) => alerts(&db, cmd).await,
+        Command::Webhooks(cmd) => webhooks(&db, cmd).await,
+        Command::Steps(cmd) => steps(&db, cmd).await,
+        Command::Questions(cmd) => questions(cmd),
+        Command::Tools(ToolsCommand::Report { session, model, days }) => {
+            tools_report(&db, &ToolStatsFilter { session_id: session, model, days }).await
+        }
+        Command::Search { query, session, account, model, kind, since, until, limit } => {
+            let account_id = match account {
+                Some(key) => Some(find_account(&db, &key).await?.id),
+                None => None,
+            };
+            if let Some(kind) = &kind {
+                RequestKind::parse(kind).ok_or_else(|| anyhow!("unknown request kind: {}", kind))?;
+            }
+            let query = SearchQuery {
+                query: query.join(" "),
+                session_id: session,
+                account_id,
+                model,
+                request_kind: kind,
+                since: since.as_deref().map(local_day_start).transpose()?,
+                until: until.as_deref().map(|d| local_day_start(d).map(|t| t + 24 * 60 * 60)).transpose()?,
+                limit: Some(limit),
+            };
+            search_transcripts(&db, query).await
+        }
+    }
+}
+
+async fn serve(db: Arc<Database>, port: Option<u16>, manage_settings: bool) -> Result<()> {
+    let port = match port {
+        Some(port) => port,
+        None => db.get_proxy_port().await?,
+    };
+
+    let mut server = ProxyServer::new(db.clone());
+    server.start(port).await?;
+    tracing::info!("swiftcast-daemon listening on 127.0.0.1:{}", port);
+
+    if manage_settings {
+        if let Some(account) = db.get_active_account().await? {
+            claude_settings::init_claude_settings(&account.base_url, port).map_err(|e| anyhow!(e))?;
+            tracing::info!("Claude settings.json points at the proxy (account: {})", account.name);
+        }
+    }
+
+    tokio::signal::ctrl_c().await?;
+
+    server.stop().await?;
+    if manage_settings {
+        // GUI의 stop_proxy와 동일하게 settings.json 정리
+        claude_settings::clear_claude_settings().map_err(|e| anyhow!(e))?;
+    }
+    Ok(())
+}
+
+/// Resolve an account by exact id or (case-insensitive) name
+async fn find_account(db: &Database, key: &str) -> Result<Account> {
+    db.get_accounts()
+        .await?
+        .into_iter()
+        .find(|a| a.id == key || a.name.eq_ignore_ascii_case(key))
+        .ok_or_else(|| anyhow!("account not found: {}", key))
+}
+
+async fn accounts(db: &Database, cmd: AccountsCommand) -> Result<()> {
+    match cmd {
+        AccountsCommand::Add { name, base_url, api_key } => {
+            let account = Account::new(name, base_url);
+            db.create_account(account.clone(), api_key).await?;
+            if db.get_accounts().await?.len() == 1 {
+                db.switch_account(&account.id).await?;
+            }
+            println!("{}", account.id);
+        }
+        AccountsCommand::List => {
+            for a in db.get_accounts().await? {
+                println!("{} {:<36} {:<20} {}", if a.is_active { "*" } else { " " }, a.id, a.name, a.base_url);
+            }
+        }
+        AccountsCommand::Switch { account } => {
+            let account = find_account(db, &account).await?;
+            db.switch_account(&account.id).await?;
+            let port = db.get_proxy_port().await?;
+            claude_settings::update_claude_settings(&account.base_url, port).map_err(|e| anyhow!(e))?;
+            println!("Switched to {}", account.name);
+        }
+        AccountsCommand::Timeouts { account, connect, first_byte, idle } => {
+            let account = find_account(db, &account).await?;
+            db.set_account_timeouts(&account.id, connect, first_byte, idle).await?;
+            let account = find_account(db, &account.id).await?;
+            let t = account.timeouts();
+            println!(
+                "{}: connect={}s first_byte={}s idle={}s",
+                account.name,
+                t.connect.as_secs(),
+                t.first_byte.as_secs(),
+                t.idle.as_secs()
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn sessions(db: &Database, cmd: SessionsCommand) -> Result<()> {
+    let time = |ts: i64| {
+        chrono::DateTime::from_timestamp(ts, 0)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default()
+    };
+    match cmd {
+        SessionsCommand::List => {
+            for s in db.get_active_sessions().await? {
+                println!(
+                    "{:<40} {:<16} {:<20} {:<28} {:<20} {}",
+                    s.session_id,
+                    s.state.map(|st| st.as_str()).unwrap_or("-"),
+                    s.account_name,
+                    s.model_override.as_deref().unwrap_or("-"),
+                    s.project_path.as_deref().map(project_name).unwrap_or("-"),
+                    s.last_message.as_deref().unwrap_or("").chars().take(60).collect::<String>()
+                );
+            }
+        }
+        SessionsCommand::Set { session_id, account, model } => {
+            let account = find_account(db, &account).await?;
+            db.upsert_session_config(&session_id, &account.id, model.as_deref()).await?;
+            println!("Session {} -> {}", session_id, account.name);
+        }
+        SessionsCommand::Subagents { session_id } => {
+            for s in db.get_subagent_sessions(&session_id).await? {
+                println!(
+                    "{:<52} {:<16} {:>6} req {:>10} in {:>10} out ${:.4}  {}",
+                    s.child_session_id,
+                    s.agent_type.as_deref().unwrap_or("-"),
+                    s.request_count,
+                    s.total_input_tokens,
+                    s.total_output_tokens,
+                    s.total_cost_usd,
+                    s.description.as_deref().unwrap_or("")
+                );
+            }
+        }
+        SessionsCommand::Turns { session_id } => {
+            let lifecycle = db
+                .get_session_lifecycle(&session_id)
+                .await?
+                .ok_or_else(|| anyhow!("No recorded activity for session {}", session_id))?;
+            println!(
+                "{} ({}{}), {} turns, wall {}s, active {:.1}s",
+                lifecycle.state,
+                time(lifecycle.started_at),
+                lifecycle.ended_at.map(|t| format!(" - {}", time(t))).unwrap_or_default(),
+                lifecycle.turn_count,
+                lifecycle.wall_secs(),
+                lifecycle.active_ms as f64 / 1000.0
+            );
+            for t in db.get_session_turns(&session_id).await? {
+                println!(
+                    "  #{:<4} {} {:>6}s {:>4} req active {:>8.1}s  {}",
+                    t.turn_number,
+                    time(t.started_at),
+                    t.duration_secs,
+                    t.request_count,
+                    t.active_ms as f64 / 1000.0,
+                    t.stop_reason.as_deref().unwrap_or(if t.ended_at.is_some() { "-" } else { "in progress" })
+                );
+            }
+        }
+        SessionsCommand::End { session_id } => {
+            let ended = db
+                .set_session_state(&session_id, SessionState::Ended, Some("explicit"), chrono::Utc::now().timestamp())
+                .await?;
+            if !ended {
+                return Err(anyhow!("No recorded activity for session {}", session_id));
+            }
+            println!("Session {} ended", session_id);
+        }
+        SessionsCommand::Timeline { session_id, subagents, full, limit } => {
+            for e in db.get_session_timeline(&session_id, subagents, 0, limit).await? {
+                let time = chrono::DateTime::from_timestamp(e.created_at, 0)
+                    .map(|t| t.with_timezone(&chrono::Local).format("%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_default();
+                let label = match (e.entry_type.as_str(), &e.tool_name) {
+                    ("tool_use", Some(tool)) => format!("tool_use:{}", tool),
+                    ("usage", _) => format!(
+                        "usage in={} out={}",
+                        e.input_tokens.unwrap_or(0),
+                        e.output_tokens.unwrap_or(0)
+                    ),
+                    (entry_type, _) => entry_type.to_string(),
+                };
+                let who = if e.session_id == session_id { e.role.clone() } else { format!("{}@{}", e.role, e.session_id) };
+                if full {
+                    println!("[{}] {} {}\n{}\n", time, who, label, e.content);
+                } else {
+                    let line: String = e.content.replace('\n', " ").chars().take(120).collect();
+                    println!("[{}] {:<10} {:<24} {}", time, who, label, line);
+                }
+            }
+        }
+        SessionsCommand::SubagentRoute { account, model } => {
+            let account = match account {
+                Some(key) => Some(find_account(db, &key).await?),
+                None => None,
+            };
+            let routing = SubagentRouting {
+                account_id: account.as_ref().map(|a| a.id.clone()),
+                model_override: model,
+            };
+            db.set_subagent_routing(&routing).await?;
+            println!(
+                "Subagents -> {} ({})",
+                account.as_ref().map(|a| a.name.as_str()).unwrap_or("parent account"),
+                routing.model_override.as_deref().unwrap_or("original model")
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn usage_report(db: &Database, days: i64, project: Option<&str>) -> Result<()> {
+    let (requests, input, output) = db.get_usage_stats(project).await?;
+    println!("Total: {} requests, {} input / {} output tokens", requests, input, output);
+
+    let accounts = db.get_accounts().await?;
+    println!("\nBy account:");
+    for row in db.get_usage_by_account(project).await? {
+        let name = accounts
+            .iter()
+            .find(|a| a.id == row.account_id)
+            .map(|a| a.name.as_str())
+            .unwrap_or(&row.account_id);
+        println!("  {:<24} {:>8} req {:>12} in {:>12} out", name, row.request_count, row.total_input_tokens, row.total_output_tokens);
+    }
+
+    println!("\nBy model:");
+    for row in db.get_usage_by_model(project).await? {
+        println!("  {:<32} {:>8} req {:>12} in {:>12} out", row.model, row.request_count, row.total_input_tokens, row.total_output_tokens);
+    }
+
+    println!("\nBy kind:");
+    for row in db.get_usage_by_kind(project).await? {
+        println!(
+            "  {:<32} {:>8} req {:>12} in {:>12} out ${:.4}",
+            row.request_kind, row.request_count, row.total_input_tokens, row.total_output_tokens, row.total_cost_usd
+        );
+    }
+
+    println!("\nBy session (subagents rolled up):");
+    for row in db.get_usage_by_session(project).await?.iter().take(10) {
+        println!(
+            "  {:<40} {:>8} req ${:>9.4}  subagents: {} / {} req / ${:.4}",
+            row.session_id,
+            row.request_count,
+            row.total_cost_usd,
+            row.subagent_count,
+            row.subagent_request_count,
+            row.subagent_cost_usd
+        );
+    }
+
+    if project.is_none() {
+        println!("\nBy project:");
+        for row in db.get_usage_by_project().await? {
+            println!(
+                "  {:<40} {:>8} req {:>4} sessions ${:>9.4}  {}",
+                row.project_path,
+                row.request_count,
+                row.session_count,
+                row.total_cost_usd,
+                row.git_branches.as_deref().unwrap_or("")
+            );
+        }
+    }
+
+    println!("\nLatency by model (last {} days):", days);
+    print_latency(db, LatencyGroup::Model, days, project).await?;
+
+    println!("\nLast {} days:", days);
+    for row in db.get_daily_usage(days, project).await? {
+        println!("  {} {:>8} req {:>12} in {:>12} out", row.date, row.request_count, row.total_input_tokens, row.total_output_tokens);
+    }
+    Ok(())
+}
+
+async fn tools_report(db: &Database, filter: &ToolStatsFilter) -> Result<()> {
+    let report = db.get_tool_usage_report(filter).await?;
+    if report.tools.is_empty() {
+        println!("No tool calls recorded");
+        return Ok(());
+    }
+
+    println!("By tool:");
+    for row in &report.tools {
+        println!(
+            "  {:<24} {:>8} calls {:>6} errors ({:>5.1}%) {:>8.0} B avg input {:>5} sessions",
+            row.tool_name,
+            row.call_count,
+            row.error_count,
+            row.error_rate * 100.0,
+            row.avg_input_size,
+            row.session_count
+        );
+    }
+
+    println!("\nBy model:");
+    for row in &report.models {
+        println!(
+            "  {:<32} {:>8} calls {:>7.1} per session {:>5.1}% errors {:>3} tools",
+            row.model,
+            row.call_count,
+            row.calls_per_session,
+            row.error_rate * 100.0,
+            row.distinct_tools
+        );
+    }
+
+    println!("\nBash commands:");
+    for row in report.bash_commands.iter().take(20) {
+        println!("  {:<32} {:>8} calls {:>6} errors", row.command_head, row.call_count, row.error_count);
+    }
+
+    println!("\nEdits per file:");
+    for row in report.file_edits.iter().take(20) {
+        println!("  {:<60} {:>6} edits {:>6} errors {:>4} sessions", row.file_path, row.edit_count, row.error_count, row.session_count);
+    }
+    Ok(())
+}
+
+async fn errors(db: &Database, cmd: ErrorsCommand) -> Result<()> {
+    let time = |ts: i64| {
+        chrono::DateTime::from_timestamp(ts, 0)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default()
+    };
+    match cmd {
+        ErrorsCommand::List { limit, account } => {
+            let account_id = match account {
+                Some(key) => Some(find_account(db, &key).await?.id),
+                None => None,
+            };
+            let errors = db.get_recent_errors(limit, account_id.as_deref()).await?;
+            if errors.is_empty() {
+                println!("No errors recorded");
+            }
+            for e in errors {
+                let retry = e.retry_after_secs.map(|s| format!(" retry-after {}s", s)).unwrap_or_default();
+                println!(
+                    "{}  {} {:<22} {:<7} {} / {}{}  {}",
+                    time(e.timestamp),
+                    e.status_code,
+                    e.error_type,
+                    e.phase,
+                    e.account_name.as_deref().unwrap_or(&e.account_id),
+                    e.model,
+                    retry,
+                    e.session_id.as_deref().unwrap_or("-")
+                );
+                println!("    {}", e.message.chars().take(200).collect::<String>().replace('\n', " "));
+            }
+        }
+        ErrorsCommand::Report { days, hourly, account } => {
+            let account_id = match account {
+                Some(key) => Some(find_account(db, &key).await?.id),
+                None => None,
+            };
+
+            println!("By account (last {} days):", days);
+            for row in db.get_errors_by_account(days).await? {
+                if account_id.as_ref().is_some_and(|id| *id != row.account_id) {
+                    continue;
+                }
+                println!(
+                    "  {:<24} {:>8} req {:>6} errors ({:>5.1}%)  last: {}",
+                    row.account_name.as_deref().unwrap_or(&row.account_id),
+                    row.request_count,
+                    row.error_count,
+                    row.error_rate * 100.0,
+                    row.last_error_at.map(time).unwrap_or_else(|| "-".to_string())
+                );
+            }
+
+            println!("\nBy type:");
+            for row in db.get_errors_by_type(days, account_id.as_deref()).await? {
+                println!(
+                    "  {:<24} {:>6} errors  status {:<12} {} accounts  last: {}  {}",
+                    row.error_type,
+                    row.error_count,
+                    row.status_codes,
+                    row.account_count,
+                    time(row.last_error_at),
+                    row.last_message.unwrap_or_default().chars().take(80).collect::<String>().replace('\n', " ")
+                );
+            }
+
+            println!("\nError rate over time:");
+            for row in db.get_error_timeline(days, hourly, account_id.as_deref()).await? {
+                println!(
+                    "  {:<16} {:>8} req {:>6} errors ({:>5.1}%)",
+                    row.bucket,
+                    row.request_count,
+                    row.error_count,
+                    row.error_rate * 100.0
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn alerts(db: &Database, cmd: AlertsCommand) -> Result<()> {
+    match cmd {
+        AlertsCommand::List => {
+            let rules = db.get_alert_rules().await?;
+            if rules.is_empty() {
+                println!("No alert rules");
+            }
+            for rule in rules {
+                let mut filters = Vec::new();
+                if rule.scope != AlertScope::Global {
+                    filters.push(format!("per {}", rule.scope.as_str()));
+                }
+                filters.extend(rule.account_id.map(|a| format!("account={}", a)));
+                filters.extend(rule.model.map(|m| format!("model={}", m)));
+                filters.extend(rule.error_type.map(|t| format!("type={}", t)));
+                let channels: Vec<String> = rule
+                    .channels
+                    .iter()
+                    .map(|c| match c {
+                        AlertChannel::Event => "event".to_string(),
+                        AlertChannel::Webhook { url } => format!("webhook:{}", url),
+                        AlertChannel::Command { command } => format!("command:{}", command),
+                    })
+                    .collect();
+                println!(
+                    "{}  {}{}: {} >= {} over {} {}  -> {}",
+                    rule.id,
+                    rule.name,
+                    if rule.enabled { "" } else { " (disabled)" },
+                    rule.metric.as_str(),
+                    rule.metric.format(rule.threshold),
+                    rule.window.label(),
+                    filters.join(" "),
+                    if channels.is_empty() { "event".to_string() } else { channels.join(", ") }
+                );
+            }
+        }
+        AlertsCommand::Add { name, metric, threshold, window, scope, account, model, error_type, min_requests, channels } => {
+            let account_id = match account {
+                Some(key) => Some(find_account(db, &key).await?.id),
+                None => None,
+            };
+            let rule = AlertRule {
+                id: String::new(),
+                name,
+                enabled: true,
+                metric: AlertMetric::parse(&metric)
+                    .ok_or_else(|| anyhow!("--metric must be cost_usd, tokens, requests, errors or error_rate"))?,
+                threshold,
+                window: AlertWindow::parse(&window).ok_or_else(|| anyhow!("--window must be like 10m, 2h, day or total"))?,
+                scope: AlertScope::parse(&scope).ok_or_else(|| anyhow!("--scope must be global, account, model or session"))?,
+                account_id,
+                model,
+                error_type,
+                min_requests,
+                channels: channels
+                    .iter()
+                    .map(|c| AlertChannel::parse(c).ok_or_else(|| anyhow!("invalid channel: {}", c)))
+                    .collect::<Result<_>>()?,
+            };
+            rule.validate().map_err(|e| anyhow!(e))?;
+            let mut rules = db.get_alert_rules().await?;
+            rules.push(rule);
+            let saved = db.set_alert_rules(&rules).await?;
+            if let Some(rule) = saved.last() {
+                println!("Added alert {} ({})", rule.name, rule.id);
+            }
+        }
+        AlertsCommand::Remove { rule } => {
+            let mut rules = db.get_alert_rules().await?;
+            let before = rules.len();
+            rules.retain(|r| r.id != rule && !r.name.eq_ignore_ascii_case(&rule));
+            if rules.len() == before {
+                return Err(anyhow!("alert rule not found: {}", rule));
+            }
+            db.set_alert_rules(&rules).await?;
+            println!("Removed {}", rule);
+        }
+        AlertsCommand::History { limit } => {
+            let firings = db.get_alert_firings(limit).await?;
+            if firings.is_empty() {
+                println!("No alerts fired");
+            }
+            for f in firings {
+                let time = chrono::DateTime::from_timestamp(f.fired_at, 0)
+                    .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default();
+                println!("{}  {}", time, f.message);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn webhooks(db: &Database, cmd: WebhooksCommand) -> Result<()> {
+    let time = |ts: i64| {
+        chrono::DateTime::from_timestamp(ts, 0)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default()
+    };
+    let find = |sinks: &[WebhookSink], key: &str| {
+        sinks
+            .iter()
+            .find(|s| s.id == key || s.name.eq_ignore_ascii_case(key))
+            .cloned()
+            .ok_or_else(|| anyhow!("webhook not found: {}", key))
+    };
+    match cmd {
+        WebhooksCommand::List => {
+            let sinks = db.get_webhook_sinks().await?;
+            if sinks.is_empty() {
+                println!("No webhook sinks");
+            }
+            for sink in sinks {
+                println!(
+                    "{}  {}{}  {}",
+                    sink.id,
+                    sink.name,
+                    if sink.enabled { "" } else { " (disabled)" },
+                    sink.url
+                );
+                let events = if sink.events.is_empty() { "all".to_string() } else { sink.events.join(", ") };
+                let signed = if sink.secret.is_some() { format!(", signed ({})", sink.signature_header) } else { String::new() };
+                let template = if sink.template.is_some() { ", template" } else { "" };
+                println!("    events: {}{}{}", events, signed, template);
+            }
+        }
+        WebhooksCommand::Add { name, url, events, secret, signature_header, headers, template } => {
+            let mut sink = WebhookSink::new(name, url);
+            sink.events = events;
+            sink.secret = secret;
+            sink.signature_header = signature_header;
+            for header in headers {
+                let (name, value) = header.split_once(':').ok_or_else(|| anyhow!("--header must be Name:value"))?;
+                sink.headers.insert(name.trim().to_string(), value.trim().to_string());
+            }
+            if let Some(template) = template {
+                let json = match template.strip_prefix('@') {
+                    Some(path) => std::fs::read_to_string(path)?,
+                    None => template,
+                };
+                sink.template = Some(serde_json::from_str(&json).map_err(|e| anyhow!("invalid template JSON: {}", e))?);
+            }
+            sink.validate().map_err(|e| anyhow!(e))?;
+            let mut sinks = db.get_webhook_sinks().await?;
+            sinks.push(sink);
+            let saved = db.set_webhook_sinks(&sinks).await?;
+            if let Some(sink) = saved.last() {
+                println!("Added webhook {} ({}); applies on next proxy start", sink.name, sink.id);
+            }
+        }
+        WebhooksCommand::Remove { sink } => {
+            let mut sinks = db.get_webhook_sinks().await?;
+            let removed = find(&sinks, &sink)?;
+            sinks.retain(|s| s.id != removed.id);
+            db.set_webhook_sinks(&sinks).await?;
+            println!("Removed {}", removed.name);
+        }
+        WebhooksCommand::Test { sink } => {
+            let sink = find(&db.get_webhook_sinks().await?, &sink)?;
+            let status = WebhookClient::new().deliver(&sink, &WebhookPayload::test()).await?;
+            println!("{} responded {}", sink.name, status);
+        }
+        WebhooksCommand::Pending { limit } => {
+            let sinks = db.get_webhook_sinks().await?;
+            let entries = db.get_webhook_outbox(limit).await?;
+            if entries.is_empty() {
+                println!("Outbox is empty");
+            }
+            for e in entries {
+                let sink = sinks.iter().find(|s| s.id == e.sink_id).map(|s| s.name.as_str()).unwrap_or(&e.sink_id);
+                let retry = match e.attempts {
+                    0 => String::new(),
+                    n => format!("  {} failed, next at {}: {}", n, time(e.next_attempt_at), e.last_error.unwrap_or_default()),
+                };
+                println!("#{:<6} {}  {:<12} {:<20} {}{}", e.id, time(e.created_at), sink, e.event, e.session_id, retry);
+            }
+        }
+        WebhooksCommand::Failed { limit, sink } => {
+            let sinks = db.get_webhook_sinks().await?;
+            let sink_id = match sink {
+                Some(key) => Some(find(&sinks, &key)?.id),
+                None => None,
+            };
+            let letters = db.get_webhook_dead_letters(limit, sink_id.as_deref()).await?;
+            if letters.is_empty() {
+                println!("No failed deliveries");
+            }
+            for l in letters {
+                let sink = sinks.iter().find(|s| s.id == l.sink_id).map(|s| s.name.as_str()).unwrap_or(&l.sink_id);
+                println!(
+                    "{:<6} {}  {:<12} {:<20} {}  {} attempts: {}",
+                    l.id,
+                    time(l.failed_at),
+                    sink,
+                    l.event,
+                    l.session_id,
+                    l.attempts,
+                    l.last_error.unwrap_or_default()
+                );
+            }
+        }
+        WebhooksCommand::Replay { ids, all, sink } => {
+            if ids.is_empty() && !all {
+                return Err(anyhow!("pass dead letter ids or --all"));
+            }
+            let sink_id = match sink {
+                Some(key) => Some(find(&db.get_webhook_sinks().await?, &key)?.id),
+                None => None,
+            };
+            let replayed = db.replay_webhook_dead_letters(&ids, sink_id.as_deref()).await?;
+            println!("Queued {} deliveries again", replayed);
+        }
+        WebhooksCommand::Purge { ids, all, sink } => {
+            if ids.is_empty() && !all {
+                return Err(anyhow!("pass dead letter ids or --all"));
+            }
+            let sink_id = match sink {
+                Some(key) => Some(find(&db.get_webhook_sinks().await?, &key)?.id),
+                None => None,
+            };
+            let purged = db.purge_webhook_dead_letters(&ids, sink_id.as_deref()).await?;
+            println!("Deleted {} failed deliveries", purged);
+        }
+    }
+    Ok(())
+}
+
+async fn print_latency(db: &Database, by: LatencyGroup, days: i64, project: Option<&str>) -> Result<()> {
+    let ms = |v: Option<f64>| v.map(|v| format!("{:.0}ms", v)).unwrap_or_else(|| "-".to_string());
+    let stats = db.get_latency_stats(by, days, project).await?;
+    if stats.is_empty() {
+        println!("  No requests with latency recorded");
+    }
+    for row in stats {
+        println!(
+            "  {:<32} {:>6} req  ttft avg {:>7} p50 {:>7} p95 {:>7}  {:>6} tok/s  total {:>8}  headers {:>7}  queue {:>6}",
+            row.key,
+            row.request_count,
+            ms(row.avg_ttft_ms),
+            ms(row.p50_ttft_ms.map(|v| v as f64)),
+            ms(row.p95_ttft_ms.map(|v| v as f64)),
+            row.output_tokens_per_sec.map(|v| format!("{:.1}", v)).unwrap_or_else(|| "-".to_string()),
+            ms(row.avg_duration_ms),
+            ms(row.avg_headers_ms),
+            ms(row.avg_queue_ms)
+        );
+    }
+    Ok(())
+}
+
+async fn kinds(db: &Database, cmd: KindsCommand) -> Result<()> {
+    let mut routes = db.get_request_kind_routes().await?;
+    match cmd {
+        KindsCommand::List => {
+            let accounts = db.get_accounts().await?;
+            for kind in RequestKind::ALL {
+                let route = routes.get(kind.as_str()).map(|r| {
+                    let name = accounts
+                        .iter()
+                        .find(|a| a.id == r.account_id)
+                        .map(|a| a.name.as_str())
+                        .unwrap_or(&r.account_id);
+                    format!("-> {} ({})", name, r.model_override.as_deref().unwrap_or("original model"))
+                });
+                println!("{:<20} {}", kind.as_str(), route.unwrap_or_else(|| "-".to_string()));
+            }
+        }
+        KindsCommand::Route { kind, account, model } => {
+            let kind = RequestKind::parse(&kind).ok_or_else(|| anyhow!("unknown request kind: {}", kind))?;
+            match account {
+                Some(key) => {
+                    let account = find_account(db, &key).await?;
+                    routes.insert(
+                        kind.as_str().to_string(),
+                        KindRoute { account_id: account.id.clone(), model_override: model },
+                    );
+                    println!("{} -> {} (applies on the next proxy start)", kind.as_str(), account.name);
+                }
+                None => {
+                    routes.remove(kind.as_str());
+                    println!("{} route removed (applies on the next proxy start)", kind.as_str());
+                }
+            }
+            db.set_request_kind_routes(&routes).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Unix timestamp of local midnight for a `YYYY-MM-DD` date
+fn local_day_start(date: &str) -> Result<i64> {
+    let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| anyhow!("invalid date {}: {}", date, e))?;
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|t| t.and_local_timezone(chrono::Local).earliest())
+        .map(|t| t.timestamp())
+        .ok_or_else(|| anyhow!("invalid local date: {}", date))
+}
+
+async fn search_transcripts(db: &Database, query: SearchQuery) -> Result<()> {
+    let hits = search::search(db, query).await?;
+    if hits.is_empty() {
+        println!("No matches");
+    }
+    for hit in hits {
+        let time = chrono::DateTime::from_timestamp(hit.created_at, 0)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        let what = match &hit.tool_name {
+            Some(tool) if hit.entry_type == "tool_use" => format!("tool_use:{}", tool),
+            _ => format!("{} {}", hit.role, hit.entry_type),
+        };
+        println!(
+            "{}  {}  [{} / {} / {}]  {}",
+            time,
+            hit.session_id,
+            hit.account_name.as_deref().unwrap_or("-"),
+            hit.model.as_deref().unwrap_or("-"),
+            hit.request_kind.as_deref().unwrap_or("-"),
+            what
+        );
+        println!("    {}", hit.snippet.replace('\n', " "));
+        match &hit.log_file {
+            Some(path) => println!("    log: {}", path),
+            None => println!("    entry: #{} (sessions timeline {})", hit.entry_id, hit.session_id),
+        }
+    }
+    Ok(())
+}
+
+async fn steps(db: &Database, cmd: StepsCommand) -> Result<()> {
+    let duration = |secs: i64| match secs {
+        s if s >= 3600 => format!("{}h {:02}m", s / 3600, s % 3600 / 60),
+        s if s >= 60 => format!("{}m {:02}s", s / 60, s % 60),
+        s => format!("{}s", s),
+    };
+    match cmd {
+        StepsCommand::Rules { json } => {
+            let config = db.get_step_config().await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&config)?);
+                return Ok(());
+            }
+            println!("Phases: {}  (idle timeout {})", config.phases.join(", "), duration(config.idle_timeout_secs as i64));
+            for rule in config.rules {
+                let condition = match (&rule.pattern, &rule.field) {
+                    (Some(pattern), field) => format!(" {} =~ {}", field.as_deref().unwrap_or("command"), pattern),
+                    (None, _) => String::new(),
+                };
+                println!("  {}{} -> {}", rule.tool, condition, rule.phase.as_deref().unwrap_or("(ignored)"));
+            }
+        }
+        StepsCommand::Set { path } => {
+            let config: StepConfig = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+            config.validate().map_err(|e| anyhow!(e))?;
+            db.set_step_config(&config).await?;
+            println!("Saved {} rules (applies on the next proxy start)", config.rules.len());
+        }
+        StepsCommand::Reset => {
+            db.set_step_config(&StepConfig::default()).await?;
+            println!("Restored the built-in mapping (applies on the next proxy start)");
+        }
+        StepsCommand::Timeline { session_id } => {
+            let timeline = db.get_step_timeline(&session_id).await?;
+            if timeline.spans.is_empty() {
+                println!("No steps recorded for {}", session_id);
+                return Ok(());
+            }
+            for span in &timeline.spans {
+                let started = chrono::DateTime::from_timestamp(span.started_at, 0)
+                    .map(|t| t.with_timezone(&chrono::Local).format("%H:%M:%S").to_string())
+                    .unwrap_or_default();
+                println!(
+                    "  {}  {:<16} {:>9}  {:>3} tools  (from {}){}",
+                    started,
+                    span.phase,
+                    duration(span.duration_secs),
+                    span.tool_calls,
+                    span.first_tool,
+                    if span.ended_at.is_none() { "  open" } else { "" }
+                );
+            }
+            print_phase_stats(&timeline.phases, duration);
+        }
+        StepsCommand::Phases { days } => {
+            let stats = db.get_phase_stats(days).await?;
+            if stats.is_empty() {
+                println!("No steps recorded in the last {} days", days);
+                return Ok(());
+            }
+            print_phase_stats(&stats, duration);
+        }
+    }
+    Ok(())
+}
+
+fn print_phase_stats(stats: &[PhaseStats], duration: impl Fn(i64) -> String) {
+    let total: i64 = stats.iter().map(|p| p.duration_secs).sum();
+    println!("Time per phase:");
+    for p in stats {
+        println!(
+            "  {:<16} {:>9}  {:>5.1}%  {:>4} spans  {:>5} tools  {:>4} sessions",
+            p.phase,
+            duration(p.duration_secs),
+            p.duration_secs as f64 * 100.0 / total.max(1) as f64,
+            p.span_count,
+            p.tool_calls,
+            p.session_count
+        );
+    }
+}
+
+fn questions(cmd: QuestionsCommand) -> Result<()> {
+    let path = QuestionRules::default_config_path();
+    let rules = QuestionRules::load(&path).map_err(|e| anyhow!(e))?;
+    let detected = match cmd {
+        QuestionsCommand::Rules => {
+            let source = if path.exists() { path.display().to_string() } else { "built-in".to_string() };
+            println!("Rules ({}):", source);
+            for (name, language) in rules.rule_names() {
+                println!("  {:<20} {}", name, language);
+            }
+            return Ok(());
+        }
+        QuestionsCommand::Test { tool_input: Some(json), .. } => {
+            let input: serde_json::Value = serde_json::from_str(&json)?;
+            questions_from_tool_input(&input)
+        }
+        QuestionsCommand::Test { text, file, .. } => {
+            let text = match file {
+                Some(file) => std::fs::read_to_string(file)?,
+                None => text.join(" "),
+            };
+            rules.detect(&text).into_iter().collect()
+        }
+    };
+    if detected.is_empty() {
+        println!("No question detected");
+    }
+    for q in detected {
+        println!("{} ({}{})", q.question, q.rule, if q.language.is_empty() { String::new() } else { format!(", {}", q.language) });
+        println!("    options: {}", q.options.join(" | "));
+    }
+    Ok(())
+}
+
+async fn show_task(name: &str) -> Result<()> {
+    let hook = CustomTaskHook::new(CustomTaskHook::default_config_path());
+    let task = hook
+        .list_tasks()
+        .await
+        .into_iter()
+        .find(|t| t.name == name)
+        .ok_or_else(|| anyhow!("Unknown task: {}", name))?;
+    println!("{}", CustomTaskHook::task_help(&task));
+    Ok(())
+}
+
+async fn list_tasks() -> Result<()> {
+    let path = CustomTaskHook::default_config_path();
+    let hook = CustomTaskHook::new(path.clone());
+    let mut tasks = hook.list_tasks().await;
+    tasks.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if tasks.is_empty() {
+        println!("No tasks configured in {:?}", path);
+    }
+    for task in tasks {
+        println!("{:<20} {:<10?} {}", task.name, task.task_type, task.description);
+        for (i, step) in task.steps.iter().flatten().enumerate() {
+            let target = match (&step.task, &step.task_type) {
+                (Some(name), _) => name.clone(),
+                (None, Some(task_type)) => format!("{:?}", task_type),
+                (None, None) => "-".to_string(),
+            };
+            println!("    {}. {:<14} {}", i + 1, step.name.as_deref().unwrap_or(""), target);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,119 @@
+//! Claude Code settings.json 관리 (GUI 커맨드와 Admin API 공용)
+
+use std::fs;
+use std::path::PathBuf;
+
+// 앱 시작 시 Claude settings.json 초기화 (main.rs에서 호출)
+pub fn init_claude_settings(base_url: &str, proxy_port: u16) -> Result<(), String> {
+    update_claude_settings(base_url, proxy_port)
+}
+
+// 프록시 중지 시 settings.json에서 프록시 설정 제거
+pub fn clear_claude_settings() -> Result<(), String> {
+    let settings_path = get_claude_settings_path()?;
+
+    if !settings_path.exists() {
+        return Ok(());
+    }
+
+    // 기존 설정 읽기
+    let content = fs::read_to_string(&settings_path)
+        .map_err(|e| format!("Failed to read settings.json: {}", e))?;
+
+    if let Ok(mut settings) = serde_json::from_str::<serde_json::Value>(&content) {
+        if let Some(obj) = settings.as_object_mut() {
+            // env에서 ANTHROPIC_BASE_URL 제거
+            if let Some(env) = obj.get_mut("env").and_then(|e| e.as_object_mut()) {
+                env.remove("ANTHROPIC_BASE_URL");
+
+                // env가 비어있으면 env 자체 제거
+                if env.is_empty() {
+                    obj.remove("env");
+                }
+            }
+
+            // 파일이 비어있으면 삭제, 아니면 업데이트
+            if obj.is_empty() {
+                fs::remove_file(&settings_path)
+                    .map_err(|e| format!("Failed to delete settings.json: {}", e))?;
+            } else {
+                let settings_json = serde_json::to_string_pretty(&settings)
+                    .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+                fs::write(&settings_path, settings_json)
+                    .map_err(|e| format!("Failed to write settings.json: {}", e))?;
+            }
+        }
+    } else {
+        // 파싱 실패시 파일 삭제
+        fs::remove_file(&settings_path)
+            .map_err(|e| format!("Failed to delete settings.json: {}", e))?;
+    }
+
+    Ok(())
+}
+
+// Claude settings.json 업데이트 - 항상 프록시 URL 설정 (사용량 추적을 위해)
+pub fn update_claude_settings(_base_url: &str, proxy_port: u16) -> Result<(), String> {
+    let settings_path = get_claude_settings_path()?;
+
+    // 프록시 URL 설정 (Anthropic이든 GLM이든 모두 프록시를 통해 사용량 추적)
+    let proxy_url = format!("http://localhost:{}", proxy_port);
+
+    let mut settings: serde_json::Value = if settings_path.exists() {
+        let content = fs::read_to_string(&settings_path)
+            .map_err(|e| format!("Failed to read settings.json: {}", e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse settings.json: {}", e))?
+    } else {
+        serde_json::json!({})
+    };
+
+    // env 객체가 없으면 생성
+    if !settings.as_object().map(|o| o.contains_key("env")).unwrap_or(false) {
+        settings["env"] = serde_json::json!({});
+    }
+
+    // BASE_URL을 로컬 프록시로 설정 (프록시에서 실제 API로 전달)
+    settings["env"]["ANTHROPIC_BASE_URL"] = serde_json::Value::String(proxy_url.clone());
+
+    // settings.json 쓰기
+    let settings_json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+    // 디렉토리가 없으면 생성
+    if let Some(parent) = settings_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+
+    fs::write(&settings_path, settings_json)
+        .map_err(|e| format!("Failed to write settings.json: {}", e))?;
+
+    tracing::info!("Updated Claude settings.json with proxy_url: {}", proxy_url);
+
+    Ok(())
+}
+
+// Claude 설정 경로 가져오기
+// Claude Code는 모든 플랫폼에서 ~/.claude/settings.json 사용
+pub fn get_claude_settings_path() -> Result<PathBuf, String> {
+    let home = get_home_dir()?;
+    Ok(PathBuf::from(home).join(".claude").join("settings.json"))
+}
+
+// 크로스 플랫폼 홈 디렉토리 가져오기
+pub fn get_home_dir() -> Result<String, String> {
+    // Windows: USERPROFILE 환경변수 사용
+    // macOS/Linux: HOME 환경변수 사용
+    #[cfg(target_os = "windows")]
+    {
+        std::env::var("USERPROFILE")
+            .map_err(|e| format!("Failed to get USERPROFILE: {}", e))
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        std::env::var("HOME")
+            .map_err(|e| format!("Failed to get HOME: {}", e))
+    }
+}
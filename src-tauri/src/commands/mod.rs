@@ -1,4 +1,13 @@
-use crate::{models::{Account, SessionDetail}, proxy::ProxyServer, AppState};
+use crate::{
+    claude_settings::{clear_claude_settings, get_claude_settings_path, init_claude_settings, update_claude_settings},
+    models::{available_models, Account, AlertFiring, AlertRule, ModelInfo, SessionDetail, SessionState, StepConfig, TranscriptEntry, WebhookSink},
+    proxy::{
+        question_detector::{DetectedQuestion, QuestionRules},
+        webhook::{WebhookClient, WebhookPayload},
+        ProxyServer,
+    },
+    AppState,
+};
 use tauri::State;
 use std::path::PathBuf;
 use std::fs;
@@ -80,101 +89,6 @@ pub async fn switch_account(
     Ok(())
 }
 
-// 앱 시작 시 Claude settings.json 초기화 (main.rs에서 호출)
-pub fn init_claude_settings(base_url: &str, proxy_port: u16) -> Result<(), String> {
-    update_claude_settings(base_url, proxy_port)
-}
-
-// 프록시 중지 시 settings.json에서 프록시 설정 제거
-fn clear_claude_settings() -> Result<(), String> {
-    use std::fs;
-
-    let settings_path = get_claude_settings_path()?;
-
-    if !settings_path.exists() {
-        return Ok(());
-    }
-
-    // 기존 설정 읽기
-    let content = fs::read_to_string(&settings_path)
-        .map_err(|e| format!("Failed to read settings.json: {}", e))?;
-
-    if let Ok(mut settings) = serde_json::from_str::<serde_json::Value>(&content) {
-        if let Some(obj) = settings.as_object_mut() {
-            // env에서 ANTHROPIC_BASE_URL 제거
-            if let Some(env) = obj.get_mut("env").and_then(|e| e.as_object_mut()) {
-                env.remove("ANTHROPIC_BASE_URL");
-
-                // env가 비어있으면 env 자체 제거
-                if env.is_empty() {
-                    obj.remove("env");
-                }
-            }
-
-            // 파일이 비어있으면 삭제, 아니면 업데이트
-            if obj.is_empty() {
-                fs::remove_file(&settings_path)
-                    .map_err(|e| format!("Failed to delete settings.json: {}", e))?;
-            } else {
-                let settings_json = serde_json::to_string_pretty(&settings)
-                    .map_err(|e| format!("Failed to serialize settings: {}", e))?;
-                fs::write(&settings_path, settings_json)
-                    .map_err(|e| format!("Failed to write settings.json: {}", e))?;
-            }
-        }
-    } else {
-        // 파싱 실패시 파일 삭제
-        fs::remove_file(&settings_path)
-            .map_err(|e| format!("Failed to delete settings.json: {}", e))?;
-    }
-
-    Ok(())
-}
-
-// Claude settings.json 업데이트 - 항상 프록시 URL 설정 (사용량 추적을 위해)
-fn update_claude_settings(_base_url: &str, proxy_port: u16) -> Result<(), String> {
-    use std::fs;
-
-    let settings_path = get_claude_settings_path()?;
-
-    // 프록시 URL 설정 (Anthropic이든 GLM이든 모두 프록시를 통해 사용량 추적)
-    let proxy_url = format!("http://localhost:{}", proxy_port);
-
-    let mut settings: serde_json::Value = if settings_path.exists() {
-        let content = fs::read_to_string(&settings_path)
-            .map_err(|e| format!("Failed to read settings.json: {}", e))?;
-        serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse settings.json: {}", e))?
-    } else {
-        serde_json::json!({})
-    };
-
-    // env 객체가 없으면 생성
-    if !settings.as_object().map(|o| o.contains_key("env")).unwrap_or(false) {
-        settings["env"] = serde_json::json!({});
-    }
-
-    // BASE_URL을 로컬 프록시로 설정 (프록시에서 실제 API로 전달)
-    settings["env"]["ANTHROPIC_BASE_URL"] = serde_json::Value::String(proxy_url.clone());
-
-    // settings.json 쓰기
-    let settings_json = serde_json::to_string_pretty(&settings)
-        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
-
-    // 디렉토리가 없으면 생성
-    if let Some(parent) = settings_path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
-    }
-
-    fs::write(&settings_path, settings_json)
-        .map_err(|e| format!("Failed to write settings.json: {}", e))?;
-
-    tracing::info!("Updated Claude settings.json with proxy_url: {}", proxy_url);
-
-    Ok(())
-}
-
 #[tauri::command]
 pub async fn delete_account(
     account_id: String,
@@ -187,6 +101,22 @@ pub async fn delete_account(
         .map_err(|e| e.to_string())
 }
 
+// 계정별 업스트림 타임아웃 설정 (None = 기본값)
+#[tauri::command]
+pub async fn set_account_timeouts(
+    account_id: String,
+    connect_timeout_secs: Option<u32>,
+    first_byte_timeout_secs: Option<u32>,
+    idle_timeout_secs: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .db
+        .set_account_timeouts(&account_id, connect_timeout_secs, first_byte_timeout_secs, idle_timeout_secs)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[derive(serde::Serialize)]
 pub struct ProxyStatus {
     pub running: bool,
@@ -201,7 +131,7 @@ pub async fn start_proxy(port: u16, state: State<'_, AppState>) -> Result<(), St
         return Err("Proxy is already running".to_string());
     }
 
-    let mut server = ProxyServer::new(state.db.clone());
+    let mut server = ProxyServer::new(state.db.clone()).with_event_bus(state.events.clone());
     server.start(port).await.map_err(|e| e.to_string())?;
     *proxy = Some(server);
 
@@ -239,30 +169,6 @@ pub async fn get_proxy_status(state: State<'_, AppState>) -> Result<ProxyStatus,
     })
 }
 
-// Claude 설정 경로 가져오기
-// Claude Code는 모든 플랫폼에서 ~/.claude/settings.json 사용
-fn get_claude_settings_path() -> Result<PathBuf, String> {
-    let home = get_home_dir()?;
-    Ok(PathBuf::from(home).join(".claude").join("settings.json"))
-}
-
-// 크로스 플랫폼 홈 디렉토리 가져오기
-fn get_home_dir() -> Result<String, String> {
-    // Windows: USERPROFILE 환경변수 사용
-    // macOS/Linux: HOME 환경변수 사용
-    #[cfg(target_os = "windows")]
-    {
-        std::env::var("USERPROFILE")
-            .map_err(|e| format!("Failed to get USERPROFILE: {}", e))
-    }
-
-    #[cfg(not(target_os = "windows"))]
-    {
-        std::env::var("HOME")
-            .map_err(|e| format!("Failed to get HOME: {}", e))
-    }
-}
-
 // macOS Keychain에서 Claude 토큰 가져오기
 #[derive(serde::Serialize)]
 pub struct ClaudeTokenInfo {
@@ -469,10 +375,10 @@ pub struct UsageStats {
 }
 
 #[tauri::command]
-pub async fn get_usage_stats(state: State<'_, AppState>) -> Result<UsageStats, String> {
+pub async fn get_usage_stats(project: Option<String>, state: State<'_, AppState>) -> Result<UsageStats, String> {
     let (request_count, input_tokens, output_tokens) = state
         .db
-        .get_usage_stats()
+        .get_usage_stats(project.as_deref())
         .await
         .map_err(|e| e.to_string())?;
 
@@ -527,68 +433,31 @@ pub async fn get_proxy_port(state: State<'_, AppState>) -> Result<u16, String> {
 
 // ===== Hook 설정 Commands =====
 
-#[derive(serde::Serialize, serde::Deserialize)]
-pub struct HookConfig {
-    pub hooks_enabled: bool,
-    pub hooks_retention_days: u64,
-    pub compaction_injection_enabled: bool,
-    pub compaction_summarization_instructions: String,
-    pub compaction_context_injection: String,
-}
-
 #[tauri::command]
 pub async fn get_hook_config(state: State<'_, AppState>) -> Result<HookConfig, String> {
-    let hooks_enabled = state.db.get_config("hooks_enabled").await
-        .map_err(|e| e.to_string())?
-        .map(|v| v == "true")
-        .unwrap_or(true);
-
-    let hooks_retention_days = state.db.get_config("hooks_retention_days").await
-        .map_err(|e| e.to_string())?
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(30);
-
-    let compaction_injection_enabled = state.db.get_config("compaction_injection_enabled").await
-        .map_err(|e| e.to_string())?
-        .map(|v| v == "true")
-        .unwrap_or(false);
-
-    let compaction_summarization_instructions = state.db.get_config("compaction_summarization_instructions").await
-        .map_err(|e| e.to_string())?
-        .unwrap_or_default();
-
-    let compaction_context_injection = state.db.get_config("compaction_context_injection").await
-        .map_err(|e| e.to_string())?
-        .unwrap_or_default();
-
-    Ok(HookConfig {
-        hooks_enabled,
-        hooks_retention_days,
-        compaction_injection_enabled,
-        compaction_summarization_instructions,
-        compaction_context_injection,
-    })
+    state.db.get_hook_config().await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn set_hook_config(config: HookConfig, state: State<'_, AppState>) -> Result<(), String> {
-    state.db.set_config("hooks_enabled", if config.hooks_enabled { "true" } else { "false" })
-        .await.map_err(|e| e.to_string())?;
-
-    state.db.set_config("hooks_retention_days", &config.hooks_retention_days.to_string())
-        .await.map_err(|e| e.to_string())?;
+    state.db.set_hook_config(&config).await.map_err(|e| e.to_string())?;
 
-    state.db.set_config("compaction_injection_enabled", if config.compaction_injection_enabled { "true" } else { "false" })
-        .await.map_err(|e| e.to_string())?;
+    tracing::info!("Hook config updated: enabled={}, compaction={}", config.hooks_enabled, config.compaction_injection_enabled);
+    Ok(())
+}
 
-    state.db.set_config("compaction_summarization_instructions", &config.compaction_summarization_instructions)
-        .await.map_err(|e| e.to_string())?;
+// ===== Admin API 토큰 Commands =====
 
-    state.db.set_config("compaction_context_injection", &config.compaction_context_injection)
-        .await.map_err(|e| e.to_string())?;
+#[tauri::command]
+pub async fn get_admin_api_token(state: State<'_, AppState>) -> Result<String, String> {
+    state.db.get_admin_api_token().await.map_err(|e| e.to_string())
+}
 
-    tracing::info!("Hook config updated: enabled={}, compaction={}", config.hooks_enabled, config.compaction_injection_enabled);
-    Ok(())
+#[tauri::command]
+pub async fn regenerate_admin_api_token(state: State<'_, AppState>) -> Result<String, String> {
+    let token = state.db.regenerate_admin_api_token().await.map_err(|e| e.to_string())?;
+    tracing::info!("Admin API token regenerated");
+    Ok(token)
 }
 
 #[tauri::command]
@@ -598,7 +467,7 @@ pub fn get_app_version() -> String {
 
 // ===== 세션별 Hook 설정 Commands =====
 
-use crate::storage::database::SessionHookConfig;
+use crate::storage::database::{HookConfig, SessionHookConfig};
 
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct SessionHookConfigInput {
@@ -646,26 +515,27 @@ pub async fn get_all_session_hooks(state: State<'_, AppState>) -> Result<Vec<Ses
 }
 
 // 사용량 관련 명령어
-use crate::storage::database::{UsageLog, AccountUsageStats, ModelUsageStats, DailyUsageStats, SessionUsageStats};
+use crate::storage::database::{UsageLog, AccountUsageStats, ModelUsageStats, DailyUsageStats, SessionUsageStats, KindUsageStats, ProjectUsageStats, LatencyGroup, LatencyStats, ErrorLog, ErrorBucket, AccountErrorStats, ErrorTypeStats, SearchHit, SearchQuery, ToolStatsFilter, ToolUsageReport, UsageImportReport, OutboxEntry, DeadLetter, PhaseStats, StepTimeline, SessionLifecycle, SessionTurn};
+use crate::storage::usage_export::{self, ExportFormat, ExportOptions};
 
 #[tauri::command]
-pub async fn get_recent_usage(limit: i64, state: State<'_, AppState>) -> Result<Vec<UsageLog>, String> {
-    state.db.get_recent_usage(limit).await.map_err(|e| e.to_string())
+pub async fn get_recent_usage(limit: i64, project: Option<String>, state: State<'_, AppState>) -> Result<Vec<UsageLog>, String> {
+    state.db.get_recent_usage(limit, project.as_deref()).await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn get_usage_by_account(state: State<'_, AppState>) -> Result<Vec<AccountUsageStats>, String> {
-    state.db.get_usage_by_account().await.map_err(|e| e.to_string())
+pub async fn get_usage_by_account(project: Option<String>, state: State<'_, AppState>) -> Result<Vec<AccountUsageStats>, String> {
+    state.db.get_usage_by_account(project.as_deref()).await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn get_usage_by_model(state: State<'_, AppState>) -> Result<Vec<ModelUsageStats>, String> {
-    state.db.get_usage_by_model().await.map_err(|e| e.to_string())
+pub async fn get_usage_by_model(project: Option<String>, state: State<'_, AppState>) -> Result<Vec<ModelUsageStats>, String> {
+    state.db.get_usage_by_model(project.as_deref()).await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn get_daily_usage(days: i64, state: State<'_, AppState>) -> Result<Vec<DailyUsageStats>, String> {
-    state.db.get_daily_usage(days).await.map_err(|e| e.to_string())
+pub async fn get_daily_usage(days: i64, project: Option<String>, state: State<'_, AppState>) -> Result<Vec<DailyUsageStats>, String> {
+    state.db.get_daily_usage(days, project.as_deref()).await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -674,66 +544,238 @@ pub async fn clear_usage_logs(state: State<'_, AppState>) -> Result<(), String>
 }
 
 #[tauri::command]
-pub async fn get_usage_by_session(state: State<'_, AppState>) -> Result<Vec<SessionUsageStats>, String> {
-    state.db.get_usage_by_session().await.map_err(|e| e.to_string())
+pub async fn get_usage_by_session(project: Option<String>, state: State<'_, AppState>) -> Result<Vec<SessionUsageStats>, String> {
+    state.db.get_usage_by_session(project.as_deref()).await.map_err(|e| e.to_string())
 }
 
-// ===== 세션 관리 Commands =====
+// 요청 종류별 사용량 (메인 턴 / 서브에이전트 / 제목 생성 등)
+#[tauri::command]
+pub async fn get_usage_by_kind(project: Option<String>, state: State<'_, AppState>) -> Result<Vec<KindUsageStats>, String> {
+    state.db.get_usage_by_kind(project.as_deref()).await.map_err(|e| e.to_string())
+}
 
-// 모델 정보
-#[derive(serde::Serialize, Clone)]
-pub struct ModelInfo {
-    pub id: String,
-    pub name: String,
-}
-
-// Anthropic 모델 목록
-fn get_anthropic_models() -> Vec<ModelInfo> {
-    vec![
-        ModelInfo {
-            id: "claude-sonnet-4-20250514".to_string(),
-            name: "Claude Sonnet 4".to_string(),
-        },
-        ModelInfo {
-            id: "claude-opus-4-20250514".to_string(),
-            name: "Claude Opus 4".to_string(),
-        },
-        ModelInfo {
-            id: "claude-3-5-haiku-20241022".to_string(),
-            name: "Claude 3.5 Haiku".to_string(),
-        },
-        ModelInfo {
-            id: "claude-3-5-sonnet-20241022".to_string(),
-            name: "Claude 3.5 Sonnet".to_string(),
-        },
-    ]
-}
-
-// GLM 모델 목록
-fn get_glm_models() -> Vec<ModelInfo> {
-    vec![
-        ModelInfo {
-            id: "glm-4".to_string(),
-            name: "GLM-4".to_string(),
-        },
-        ModelInfo {
-            id: "glm-4-flash".to_string(),
-            name: "GLM-4 Flash".to_string(),
-        },
-    ]
+// 프로젝트(작업 디렉토리)별 사용량
+#[tauri::command]
+pub async fn get_usage_by_project(state: State<'_, AppState>) -> Result<Vec<ProjectUsageStats>, String> {
+    state.db.get_usage_by_project().await.map_err(|e| e.to_string())
 }
 
+// 최근 업스트림/전송 오류
+#[tauri::command]
+pub async fn get_recent_errors(limit: i64, account_id: Option<String>, state: State<'_, AppState>) -> Result<Vec<ErrorLog>, String> {
+    state.db.get_recent_errors(limit, account_id.as_deref()).await.map_err(|e| e.to_string())
+}
+
+// 기간별 오류율 (hourly: 시간 단위)
+#[tauri::command]
+pub async fn get_error_timeline(days: i64, hourly: bool, account_id: Option<String>, state: State<'_, AppState>) -> Result<Vec<ErrorBucket>, String> {
+    state.db.get_error_timeline(days, hourly, account_id.as_deref()).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_errors_by_account(days: i64, state: State<'_, AppState>) -> Result<Vec<AccountErrorStats>, String> {
+    state.db.get_errors_by_account(days).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_errors_by_type(days: i64, account_id: Option<String>, state: State<'_, AppState>) -> Result<Vec<ErrorTypeStats>, String> {
+    state.db.get_errors_by_type(days, account_id.as_deref()).await.map_err(|e| e.to_string())
+}
+
+// 알림 규칙 (비용/토큰/요청 수/오류율 임계값)
+#[tauri::command]
+pub async fn get_alert_rules(state: State<'_, AppState>) -> Result<Vec<AlertRule>, String> {
+    state.db.get_alert_rules().await.map_err(|e| e.to_string())
+}
+
+// 전체 규칙 교체 (새 규칙은 ID 생성)
+#[tauri::command]
+pub async fn set_alert_rules(rules: Vec<AlertRule>, state: State<'_, AppState>) -> Result<Vec<AlertRule>, String> {
+    for rule in &rules {
+        rule.validate()?;
+    }
+    state.db.set_alert_rules(&rules).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_alert_history(limit: i64, state: State<'_, AppState>) -> Result<Vec<AlertFiring>, String> {
+    state.db.get_alert_firings(limit).await.map_err(|e| e.to_string())
+}
+
+// 웹훅 싱크 (URL, 이벤트 필터, HMAC 서명, 헤더, 페이로드 템플릿)
+#[tauri::command]
+pub async fn get_webhook_sinks(state: State<'_, AppState>) -> Result<Vec<WebhookSink>, String> {
+    state.db.get_webhook_sinks().await.map_err(|e| e.to_string())
+}
+
+// 전체 교체 - 프록시 실행 중이면 바로 적용
+#[tauri::command]
+pub async fn set_webhook_sinks(sinks: Vec<WebhookSink>, state: State<'_, AppState>) -> Result<Vec<WebhookSink>, String> {
+    for sink in &sinks {
+        sink.validate()?;
+    }
+    let sinks = state.db.set_webhook_sinks(&sinks).await.map_err(|e| e.to_string())?;
+    if let Some(server) = state.proxy.read().await.as_ref() {
+        server.webhook().configure(sinks.clone()).await;
+    }
+    Ok(sinks)
+}
+
+// 테스트 이벤트 전송 (응답 상태 코드 반환)
+#[tauri::command]
+pub async fn test_webhook_sink(id: String, state: State<'_, AppState>) -> Result<u16, String> {
+    let sinks = state.db.get_webhook_sinks().await.map_err(|e| e.to_string())?;
+    let sink = sinks.iter().find(|s| s.id == id).ok_or("Webhook not found")?;
+    let status = WebhookClient::new()
+        .deliver(sink, &WebhookPayload::test())
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(status.as_u16())
+}
+
+// 웹훅 아웃박스 (전달 대기 중)
+#[tauri::command]
+pub async fn get_webhook_outbox(limit: i64, state: State<'_, AppState>) -> Result<Vec<OutboxEntry>, String> {
+    state.db.get_webhook_outbox(limit).await.map_err(|e| e.to_string())
+}
+
+// 최대 시도 횟수를 넘겨 실패한 전달
+#[tauri::command]
+pub async fn get_webhook_dead_letters(limit: i64, sink_id: Option<String>, state: State<'_, AppState>) -> Result<Vec<DeadLetter>, String> {
+    state.db.get_webhook_dead_letters(limit, sink_id.as_deref()).await.map_err(|e| e.to_string())
+}
+
+// 실패한 전달 재전송 (ids가 비어 있으면 전체)
+#[tauri::command]
+pub async fn replay_webhook_dead_letters(ids: Vec<i64>, sink_id: Option<String>, state: State<'_, AppState>) -> Result<u64, String> {
+    let replayed = state
+        .db
+        .replay_webhook_dead_letters(&ids, sink_id.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Some(server) = state.proxy.read().await.as_ref() {
+        server.webhook().notify_outbox();
+    }
+    Ok(replayed)
+}
+
+#[tauri::command]
+pub async fn purge_webhook_dead_letters(ids: Vec<i64>, sink_id: Option<String>, state: State<'_, AppState>) -> Result<u64, String> {
+    state.db.purge_webhook_dead_letters(&ids, sink_id.as_deref()).await.map_err(|e| e.to_string())
+}
+
+// 도구 → 단계 매핑 (설정이 없으면 기본 매핑)
+#[tauri::command]
+pub async fn get_step_config(state: State<'_, AppState>) -> Result<StepConfig, String> {
+    state.db.get_step_config().await.map_err(|e| e.to_string())
+}
+
+// 단계 매핑 저장 (실행 중인 프록시에 즉시 적용)
+#[tauri::command]
+pub async fn set_step_config(config: StepConfig, state: State<'_, AppState>) -> Result<(), String> {
+    config.validate()?;
+    state.db.set_step_config(&config).await.map_err(|e| e.to_string())?;
+    if let Some(server) = state.proxy.read().await.as_ref() {
+        server.step_tracker().configure(&config).await?;
+    }
+    Ok(())
+}
+
+// 세션의 단계 구간 (분석/설계/구현/검증에 걸린 시간)
+#[tauri::command]
+pub async fn get_step_timeline(session_id: String, state: State<'_, AppState>) -> Result<StepTimeline, String> {
+    state.db.get_step_timeline(&session_id).await.map_err(|e| e.to_string())
+}
+
+// 전체 세션의 단계별 시간 (최근 N일)
+#[tauri::command]
+pub async fn get_phase_stats(days: i64, state: State<'_, AppState>) -> Result<Vec<PhaseStats>, String> {
+    state.db.get_phase_stats(days).await.map_err(|e| e.to_string())
+}
+
+// 세션 상태와 턴별 소요 시간
+#[tauri::command]
+pub async fn get_session_lifecycle(
+    session_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<(SessionLifecycle, Vec<SessionTurn>)>, String> {
+    let Some(lifecycle) = state.db.get_session_lifecycle(&session_id).await.map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+    let turns = state.db.get_session_turns(&session_id).await.map_err(|e| e.to_string())?;
+    Ok(Some((lifecycle, turns)))
+}
+
+// 세션을 종료 상태로 표시 (프록시 실행 중이면 session_state_changed 이벤트도 발생)
+#[tauri::command]
+pub async fn end_session(session_id: String, state: State<'_, AppState>) -> Result<bool, String> {
+    if let Some(server) = state.proxy.read().await.as_ref() {
+        return server.sessions().end_session(&session_id).await.map_err(|e| e.to_string());
+    }
+    state
+        .db
+        .set_session_state(&session_id, SessionState::Ended, Some("explicit"), chrono::Utc::now().timestamp())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// 질문 감지 규칙 테스트 (~/.sessioncast/question_rules.toml, 없으면 기본 규칙)
+#[tauri::command]
+pub async fn test_question_rules(text: String) -> Result<Option<DetectedQuestion>, String> {
+    let rules = QuestionRules::load(&QuestionRules::default_config_path())?;
+    Ok(rules.detect(&text))
+}
+
+// 지연 시간 통계 (by: account / model / hour)
+#[tauri::command]
+pub async fn get_latency_stats(by: LatencyGroup, days: i64, project: Option<String>, state: State<'_, AppState>) -> Result<Vec<LatencyStats>, String> {
+    state.db.get_latency_stats(by, days, project.as_deref()).await.map_err(|e| e.to_string())
+}
+
+// 도구 사용 통계 (도구별 호출/오류율, 모델별 효율, Bash 명령, 파일별 편집)
+#[tauri::command]
+pub async fn get_tool_usage_report(filter: ToolStatsFilter, state: State<'_, AppState>) -> Result<ToolUsageReport, String> {
+    state.db.get_tool_usage_report(&filter).await.map_err(|e| e.to_string())
+}
+
+// 대화 기록 전문 검색 (세션/계정/모델/기간/요청 종류 필터)
+#[tauri::command]
+pub async fn search_transcripts(query: SearchQuery, state: State<'_, AppState>) -> Result<Vec<SearchHit>, String> {
+    crate::proxy::search::search(&state.db, query)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// 사용량 내보내기 (CSV / JSONL, 요청 단위 또는 기간/항목별 집계) - 기록된 행 수 반환
+#[tauri::command]
+pub async fn export_usage(path: String, options: ExportOptions, state: State<'_, AppState>) -> Result<usize, String> {
+    let (content, count) = usage_export::export_usage(&state.db, &options)
+        .await
+        .map_err(|e| e.to_string())?;
+    tokio::fs::write(&path, content).await.map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+// 다른 머신에서 내보낸 사용량 가져오기 (row_id 기준 중복 제외)
+#[tauri::command]
+pub async fn import_usage(
+    path: String,
+    fallback_account_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<UsageImportReport, String> {
+    let content = tokio::fs::read_to_string(&path).await.map_err(|e| e.to_string())?;
+    let format = ExportFormat::from_path(std::path::Path::new(&path));
+    usage_export::import_usage(&state.db, &content, format, fallback_account_id.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ===== 세션 관리 Commands =====
+
 // Provider별 사용 가능한 모델 목록 조회
 #[tauri::command]
 pub async fn get_available_models(base_url: String) -> Result<Vec<ModelInfo>, String> {
-    if base_url.contains("anthropic.com") {
-        Ok(get_anthropic_models())
-    } else if base_url.contains("z.ai") || base_url.contains("glm") {
-        Ok(get_glm_models())
-    } else {
-        // 알 수 없는 provider - Anthropic 기본값 반환
-        Ok(get_anthropic_models())
-    }
+    Ok(available_models(&base_url))
 }
 
 // 활성 세션 목록 조회 (최근 24시간)
@@ -742,6 +784,22 @@ pub async fn get_active_sessions(state: State<'_, AppState>) -> Result<Vec<Sessi
     state.db.get_active_sessions().await.map_err(|e| e.to_string())
 }
 
+// 세션 대화 타임라인 (after_id 이후 항목, 서브에이전트 포함 여부 선택)
+#[tauri::command]
+pub async fn get_session_timeline(
+    session_id: String,
+    include_subagents: bool,
+    after_id: Option<i64>,
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<TranscriptEntry>, String> {
+    state
+        .db
+        .get_session_timeline(&session_id, include_subagents, after_id.unwrap_or(0), limit.unwrap_or(500))
+        .await
+        .map_err(|e| e.to_string())
+}
+
 // 세션 설정 변경 (계정 및 모델 오버라이드)
 #[tauri::command]
 pub async fn set_session_config(
@@ -793,3 +851,66 @@ pub async fn delete_session_config(
 
     Ok(())
 }
+
+// ===== 서브에이전트 Commands =====
+
+use crate::storage::database::{SubagentRouting, SubagentSession};
+
+// 부모 세션의 서브에이전트 목록 (사용량 포함)
+#[tauri::command]
+pub async fn get_subagent_sessions(session_id: String, state: State<'_, AppState>) -> Result<Vec<SubagentSession>, String> {
+    state.db.get_subagent_sessions(&session_id).await.map_err(|e| e.to_string())
+}
+
+// 서브에이전트 라우팅 설정 조회
+#[tauri::command]
+pub async fn get_subagent_routing(state: State<'_, AppState>) -> Result<SubagentRouting, String> {
+    state.db.get_subagent_routing().await.map_err(|e| e.to_string())
+}
+
+// 서브에이전트 라우팅 설정 저장 (새로 감지되는 서브에이전트 세션부터 적용)
+#[tauri::command]
+pub async fn set_subagent_routing(routing: SubagentRouting, state: State<'_, AppState>) -> Result<(), String> {
+    if let Some(ref account_id) = routing.account_id {
+        state
+            .db
+            .get_account(account_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Account not found".to_string())?;
+    }
+    state.db.set_subagent_routing(&routing).await.map_err(|e| e.to_string())
+}
+
+// ===== 요청 종류별 라우팅 Commands =====
+
+use crate::proxy::RequestKind;
+use crate::storage::database::KindRoute;
+use std::collections::HashMap;
+
+// 요청 종류별 라우팅 조회
+#[tauri::command]
+pub async fn get_request_kind_routes(state: State<'_, AppState>) -> Result<HashMap<String, KindRoute>, String> {
+    state.db.get_request_kind_routes().await.map_err(|e| e.to_string())
+}
+
+// 요청 종류별 라우팅 저장 (전체 교체)
+#[tauri::command]
+pub async fn set_request_kind_routes(routes: HashMap<String, KindRoute>, state: State<'_, AppState>) -> Result<(), String> {
+    for (kind, route) in &routes {
+        if RequestKind::parse(kind).is_none() {
+            return Err(format!("Unknown request kind: {}", kind));
+        }
+        state
+            .db
+            .get_account(&route.account_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Account not found".to_string())?;
+    }
+    state.db.set_request_kind_routes(&routes).await.map_err(|e| e.to_string())?;
+    if let Some(server) = state.proxy.read().await.as_ref() {
+        server.kind_router().configure(&routes).await;
+    }
+    Ok(())
+}
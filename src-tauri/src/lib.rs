@@ -0,0 +1,8 @@
+//! SwiftCast core: proxy server, storage and hooks.
+//!
+//! Shared by the Tauri desktop app (`swiftcast`) and the headless daemon (`swiftcast-daemon`).
+
+pub mod claude_settings;
+pub mod models;
+pub mod proxy;
+pub mod storage;
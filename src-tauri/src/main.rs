@@ -2,23 +2,23 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod commands;
-mod models;
-mod proxy;
-mod storage;
 
-use proxy::ProxyServer;
+use swiftcast_lib::{claude_settings, models, proxy, storage};
+
+use proxy::{EventBus, ProxyServer};
 use std::sync::Arc;
 use storage::Database;
 use tauri::{
     menu::{Menu, MenuItem, PredefinedMenuItem},
     tray::TrayIconBuilder,
-    Manager, WindowEvent,
+    Emitter, Manager, WindowEvent,
 };
 use tokio::sync::RwLock;
 
 pub struct AppState {
     pub db: Arc<Database>,
     pub proxy: Arc<RwLock<Option<ProxyServer>>>,
+    pub events: EventBus,
 }
 
 #[tokio::main]
@@ -68,6 +68,8 @@ async fn main() {
     };
 
     let proxy = Arc::new(RwLock::new(None));
+    // 프록시 이벤트 버스 (프론트엔드로 미러링, 프록시 재시작 시에도 유지)
+    let events = EventBus::new();
 
     // Dev 모드 확인
     let is_dev = storage::Database::is_dev_mode();
@@ -80,7 +82,7 @@ async fn main() {
     let port = db.get_proxy_port().await.unwrap_or_else(|_| storage::Database::default_port());
 
     if auto_start {
-        let mut server = ProxyServer::new(db.clone());
+        let mut server = ProxyServer::new(db.clone()).with_event_bus(events.clone());
         if let Err(e) = server.start(port).await {
             tracing::error!("Failed to auto-start proxy: {}", e);
         } else {
@@ -91,16 +93,18 @@ async fn main() {
 
     // 활성 계정에 따라 Claude settings.json 업데이트
     if let Ok(Some(account)) = db.get_active_account().await {
-        if let Err(e) = commands::init_claude_settings(&account.base_url, port) {
+        if let Err(e) = claude_settings::init_claude_settings(&account.base_url, port) {
             tracing::error!("Failed to init Claude settings: {}", e);
         } else {
             tracing::info!("Claude settings initialized for account: {}", account.name);
         }
     }
 
+    let events_for_frontend = events.clone();
     let app_state = AppState {
         db,
         proxy,
+        events,
     };
 
     tauri::Builder::default()
@@ -119,6 +123,7 @@ async fn main() {
             commands::get_active_account,
             commands::switch_account,
             commands::delete_account,
+            commands::set_account_timeouts,
             commands::start_proxy,
             commands::stop_proxy,
             commands::get_proxy_status,
@@ -135,15 +140,51 @@ async fn main() {
             commands::get_usage_by_model,
             commands::get_daily_usage,
             commands::get_usage_by_session,
+            commands::get_usage_by_kind,
+            commands::get_usage_by_project,
+            commands::get_latency_stats,
+            commands::get_recent_errors,
+            commands::get_error_timeline,
+            commands::get_errors_by_account,
+            commands::get_errors_by_type,
+            commands::get_alert_rules,
+            commands::set_alert_rules,
+            commands::get_alert_history,
+            commands::get_webhook_sinks,
+            commands::set_webhook_sinks,
+            commands::test_webhook_sink,
+            commands::test_question_rules,
+            commands::get_step_config,
+            commands::set_step_config,
+            commands::get_step_timeline,
+            commands::get_phase_stats,
+            commands::get_session_lifecycle,
+            commands::end_session,
+            commands::get_webhook_outbox,
+            commands::get_webhook_dead_letters,
+            commands::replay_webhook_dead_letters,
+            commands::purge_webhook_dead_letters,
+            commands::get_tool_usage_report,
             commands::clear_usage_logs,
             // 세션 관리
             commands::get_available_models,
             commands::get_active_sessions,
             commands::set_session_config,
             commands::delete_session_config,
+            commands::get_subagent_sessions,
+            commands::get_session_timeline,
+            commands::search_transcripts,
+            commands::export_usage,
+            commands::import_usage,
+            commands::get_subagent_routing,
+            commands::set_subagent_routing,
+            commands::get_request_kind_routes,
+            commands::set_request_kind_routes,
             // Hook 설정
             commands::get_hook_config,
             commands::set_hook_config,
+            commands::get_admin_api_token,
+            commands::regenerate_admin_api_token,
             commands::get_app_version,
             // 세션별 Hook 설정
             commands::get_session_hooks,
@@ -151,7 +192,22 @@ async fn main() {
             commands::delete_session_hooks,
             commands::get_all_session_hooks,
         ])
-        .setup(|app| {
+        .setup(move |app| {
+            // 프록시 이벤트를 프론트엔드로 전달 ("proxy-event")
+            let handle = app.handle().clone();
+            let mut rx = events_for_frontend.subscribe();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    match rx.recv().await {
+                        Ok(event) => {
+                            let _ = handle.emit("proxy-event", &event);
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+
             // 트레이 메뉴 생성
             let show_i = MenuItem::with_id(app, "show", "SwiftCast 열기", true, None::<&str>)?;
             let separator = PredefinedMenuItem::separator(app)?;
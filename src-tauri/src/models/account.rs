@@ -1,4 +1,10 @@
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// 업스트림 타임아웃 기본값 (초)
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u32 = 30;
+pub const DEFAULT_FIRST_BYTE_TIMEOUT_SECS: u32 = 300;
+pub const DEFAULT_IDLE_TIMEOUT_SECS: u32 = 120;
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Account {
@@ -7,6 +13,26 @@ pub struct Account {
     pub base_url: String,
     pub created_at: i64,
     pub is_active: bool,
+    /// TCP/TLS connect timeout (None = default)
+    #[sqlx(default)]
+    #[serde(default)]
+    pub connect_timeout_secs: Option<u32>,
+    /// Time until upstream response headers arrive (None = default)
+    #[sqlx(default)]
+    #[serde(default)]
+    pub first_byte_timeout_secs: Option<u32>,
+    /// Max gap between streamed chunks (None = default)
+    #[sqlx(default)]
+    #[serde(default)]
+    pub idle_timeout_secs: Option<u32>,
+}
+
+/// Resolved upstream timeouts for an account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountTimeouts {
+    pub connect: Duration,
+    pub first_byte: Duration,
+    pub idle: Duration,
 }
 
 impl Account {
@@ -17,6 +43,19 @@ impl Account {
             base_url,
             created_at: chrono::Utc::now().timestamp(),
             is_active: false,
+            connect_timeout_secs: None,
+            first_byte_timeout_secs: None,
+            idle_timeout_secs: None,
+        }
+    }
+
+    /// Per-account timeouts with defaults applied (0 is treated as unset)
+    pub fn timeouts(&self) -> AccountTimeouts {
+        let secs = |v: Option<u32>, default: u32| Duration::from_secs(v.filter(|s| *s > 0).unwrap_or(default) as u64);
+        AccountTimeouts {
+            connect: secs(self.connect_timeout_secs, DEFAULT_CONNECT_TIMEOUT_SECS),
+            first_byte: secs(self.first_byte_timeout_secs, DEFAULT_FIRST_BYTE_TIMEOUT_SECS),
+            idle: secs(self.idle_timeout_secs, DEFAULT_IDLE_TIMEOUT_SECS),
         }
     }
 }
@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+
+/// A threshold rule evaluated whenever a usage row or an error is logged
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    /// Generated when empty
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub metric: AlertMetric,
+    /// Fires when the metric reaches this value (error_rate is a fraction: 0.2 = 20%)
+    pub threshold: f64,
+    pub window: AlertWindow,
+    /// What the metric is computed per (each account, model or session is tracked separately)
+    #[serde(default)]
+    pub scope: AlertScope,
+    /// Only count this account
+    #[serde(default)]
+    pub account_id: Option<String>,
+    /// Only count this model
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Only count this error type (`errors` / `error_rate`), e.g. `authentication_error`
+    #[serde(default)]
+    pub error_type: Option<String>,
+    /// `error_rate` needs at least this many requests in the window (default 5)
+    #[serde(default)]
+    pub min_requests: Option<i64>,
+    /// Defaults to the event channel
+    #[serde(default)]
+    pub channels: Vec<AlertChannel>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl AlertRule {
+    /// Reject rules that could never fire or deliver
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Alert rule name is required".to_string());
+        }
+        if !self.threshold.is_finite() || self.threshold < 0.0 {
+            return Err(format!("{}: threshold must be a non-negative number", self.name));
+        }
+        if matches!(self.window, AlertWindow::Minutes(n) if n <= 0) {
+            return Err(format!("{}: window must be at least 1 minute", self.name));
+        }
+        for channel in &self.channels {
+            match channel {
+                AlertChannel::Webhook { url } if !(url.starts_with("http://") || url.starts_with("https://")) => {
+                    return Err(format!("{}: webhook URL must be http(s): {}", self.name, url));
+                }
+                AlertChannel::Command { command } if command.trim().is_empty() => {
+                    return Err(format!("{}: command is empty", self.name));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    CostUsd,
+    /// Input + output tokens
+    Tokens,
+    Requests,
+    Errors,
+    /// Errors / (successful requests + errors)
+    ErrorRate,
+}
+
+impl AlertMetric {
+    pub const ALL: [AlertMetric; 5] = [
+        AlertMetric::CostUsd,
+        AlertMetric::Tokens,
+        AlertMetric::Requests,
+        AlertMetric::Errors,
+        AlertMetric::ErrorRate,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::CostUsd => "cost_usd",
+            Self::Tokens => "tokens",
+            Self::Requests => "requests",
+            Self::Errors => "errors",
+            Self::ErrorRate => "error_rate",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.as_str() == s)
+    }
+
+    /// Value formatted for alert messages
+    pub fn format(&self, value: f64) -> String {
+        match self {
+            Self::CostUsd => format!("${:.2}", value),
+            Self::ErrorRate => format!("{:.1}%", value * 100.0),
+            _ => format!("{}", value.round() as i64),
+        }
+    }
+}
+
+/// Period the metric is summed over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertWindow {
+    /// Last N minutes; fires at most once per N minutes
+    Minutes(i64),
+    /// Current local day; fires at most once per day
+    Day,
+    /// All time (e.g. per session); fires once
+    Total,
+}
+
+impl AlertWindow {
+    /// `10m`, `2h`, `day` or `total`
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "day" => Some(Self::Day),
+            "total" => Some(Self::Total),
+            _ => {
+                let (n, unit) = s.split_at(s.len().checked_sub(1)?);
+                let n: i64 = n.parse().ok().filter(|n| *n > 0)?;
+                match unit {
+                    "m" => Some(Self::Minutes(n)),
+                    "h" => Some(Self::Minutes(n * 60)),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::Minutes(n) if n % 60 == 0 => format!("{}h", n / 60),
+            Self::Minutes(n) => format!("{}m", n),
+            Self::Day => "day".to_string(),
+            Self::Total => "total".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertScope {
+    #[default]
+    Global,
+    Account,
+    Model,
+    /// Session including its subagents
+    Session,
+}
+
+impl AlertScope {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "global" => Some(Self::Global),
+            "account" => Some(Self::Account),
+            "model" => Some(Self::Model),
+            "session" => Some(Self::Session),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Global => "global",
+            Self::Account => "account",
+            Self::Model => "model",
+            Self::Session => "session",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertChannel {
+    /// `alert_fired` on the live event stream (`/_swiftcast/events`, Tauri `proxy-event`)
+    Event,
+    /// POST the firing as JSON
+    Webhook { url: String },
+    /// Run a shell command with `SWIFTCAST_ALERT_*` environment variables
+    Command { command: String },
+}
+
+impl AlertChannel {
+    /// `event`, `webhook:<url>` or `command:<shell command>`
+    pub fn parse(s: &str) -> Option<Self> {
+        if s == "event" {
+            return Some(Self::Event);
+        }
+        let (kind, value) = s.split_once(':')?;
+        match kind {
+            "webhook" if value.starts_with("http://") || value.starts_with("https://") => {
+                Some(Self::Webhook { url: value.to_string() })
+            }
+            "command" if !value.trim().is_empty() => Some(Self::Command { command: value.to_string() }),
+            _ => None,
+        }
+    }
+}
+
+/// A rule that fired (DB row)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AlertFiring {
+    pub id: i64,
+    pub rule_id: String,
+    pub rule_name: String,
+    pub metric: String,
+    /// Account id, model or session the metric was computed for (`*` for global rules)
+    pub group_key: String,
+    /// Local day for daily rules, `total`, or empty for minute windows
+    pub window_key: String,
+    pub value: f64,
+    pub threshold: f64,
+    pub message: String,
+    pub fired_at: i64,
+}
@@ -1,5 +1,17 @@
 pub mod account;
+pub mod alert;
+pub mod model_info;
 pub mod session;
+pub mod step_rule;
+pub mod tool_usage;
+pub mod transcript;
+pub mod webhook_sink;
 
-pub use account::Account;
-pub use session::{SessionConfig, SessionDetail};
+pub use account::{Account, AccountTimeouts};
+pub use alert::{AlertChannel, AlertFiring, AlertMetric, AlertRule, AlertScope, AlertWindow};
+pub use model_info::{available_models, ModelInfo};
+pub use session::{project_name, ProjectInfo, SessionConfig, SessionDetail, SessionState};
+pub use step_rule::{StepConfig, StepRule};
+pub use tool_usage::{ToolCall, ToolResult};
+pub use transcript::{TranscriptAppend, TranscriptBlock, TranscriptEntry, TranscriptState};
+pub use webhook_sink::{WebhookSink, WEBHOOK_EVENTS};
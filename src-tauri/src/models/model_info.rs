@@ -0,0 +1,54 @@
+// 모델 정보
+#[derive(serde::Serialize, Clone)]
+pub struct ModelInfo {
+    pub id: String,
+    pub name: String,
+}
+
+// Anthropic 모델 목록
+fn get_anthropic_models() -> Vec<ModelInfo> {
+    vec![
+        ModelInfo {
+            id: "claude-sonnet-4-20250514".to_string(),
+            name: "Claude Sonnet 4".to_string(),
+        },
+        ModelInfo {
+            id: "claude-opus-4-20250514".to_string(),
+            name: "Claude Opus 4".to_string(),
+        },
+        ModelInfo {
+            id: "claude-3-5-haiku-20241022".to_string(),
+            name: "Claude 3.5 Haiku".to_string(),
+        },
+        ModelInfo {
+            id: "claude-3-5-sonnet-20241022".to_string(),
+            name: "Claude 3.5 Sonnet".to_string(),
+        },
+    ]
+}
+
+// GLM 모델 목록
+fn get_glm_models() -> Vec<ModelInfo> {
+    vec![
+        ModelInfo {
+            id: "glm-4".to_string(),
+            name: "GLM-4".to_string(),
+        },
+        ModelInfo {
+            id: "glm-4-flash".to_string(),
+            name: "GLM-4 Flash".to_string(),
+        },
+    ]
+}
+
+/// Provider별 사용 가능한 모델 목록 (base_url로 판별)
+pub fn available_models(base_url: &str) -> Vec<ModelInfo> {
+    if base_url.contains("anthropic.com") {
+        get_anthropic_models()
+    } else if base_url.contains("z.ai") || base_url.contains("glm") {
+        get_glm_models()
+    } else {
+        // 알 수 없는 provider - Anthropic 기본값 반환
+        get_anthropic_models()
+    }
+}
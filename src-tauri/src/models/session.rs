@@ -24,4 +24,91 @@ pub struct SessionDetail {
     pub request_count: i64,
     pub total_input_tokens: i64,
     pub total_output_tokens: i64,
+    /// 서브에이전트 수 및 사용량 (위 합계에 포함됨)
+    #[serde(default)]
+    pub subagent_count: i64,
+    #[serde(default)]
+    pub subagent_input_tokens: i64,
+    #[serde(default)]
+    pub subagent_output_tokens: i64,
+    /// 작업 디렉토리 및 git 브랜치 (Claude Code 환경 정보에서 추출)
+    #[serde(default)]
+    pub project_path: Option<String>,
+    #[serde(default)]
+    pub git_branch: Option<String>,
+    /// 세션 상태, 턴 수, 요청 처리 시간 (세션 수명 주기 기록이 없으면 None)
+    #[serde(default)]
+    pub state: Option<SessionState>,
+    #[serde(default)]
+    pub turn_count: i64,
+    #[serde(default)]
+    pub active_ms: i64,
+    #[serde(default)]
+    pub ended_at: Option<i64>,
+}
+
+/// Where a session is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionState {
+    /// A turn is in progress (requests streaming or tools running)
+    Active,
+    /// The last turn ended (`end_turn`); waiting for the user's next message
+    WaitingForUser,
+    /// No requests for a while
+    Idle,
+    /// Timed out or ended explicitly; the next message reopens it
+    Ended,
+}
+
+impl SessionState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionState::Active => "active",
+            SessionState::WaitingForUser => "waiting_for_user",
+            SessionState::Idle => "idle",
+            SessionState::Ended => "ended",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "active" => Some(SessionState::Active),
+            "waiting_for_user" => Some(SessionState::WaitingForUser),
+            "idle" => Some(SessionState::Idle),
+            "ended" => Some(SessionState::Ended),
+            _ => None,
+        }
+    }
+
+    /// State after `quiet_secs` without requests, if it changes
+    pub fn after_quiet(self, quiet_secs: i64, idle_after_secs: i64, end_after_secs: i64) -> Option<Self> {
+        match self {
+            SessionState::Ended => None,
+            _ if quiet_secs >= end_after_secs => Some(SessionState::Ended),
+            SessionState::Idle => None,
+            _ if quiet_secs >= idle_after_secs => Some(SessionState::Idle),
+            _ => None,
+        }
+    }
+}
+
+/// Project a session works in, taken from Claude Code's environment block
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ProjectInfo {
+    /// Working directory
+    pub project_path: String,
+    pub is_git_repo: bool,
+    pub git_branch: Option<String>,
+}
+
+impl ProjectInfo {
+    /// Last path component (`/Users/me/code/acme-web` → `acme-web`)
+    pub fn name(&self) -> &str {
+        project_name(&self.project_path)
+    }
+}
+
+pub fn project_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
 }
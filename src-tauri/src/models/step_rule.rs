@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+/// Phases tracked when none are configured
+pub const DEFAULT_PHASES: [&str; 4] = ["ANALYSIS", "DESIGN", "IMPLEMENTATION", "VERIFICATION"];
+
+/// Tool-to-phase mapping used by the step tracker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepConfig {
+    /// Phases rules may map to (add custom phases here)
+    #[serde(default = "default_phases")]
+    pub phases: Vec<String>,
+    /// First matching rule wins; tools without a matching rule are not tracked
+    pub rules: Vec<StepRule>,
+    /// Close a session's open phase after this long without tool calls
+    #[serde(default = "default_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepRule {
+    /// Tool name; `mcp__github__*` matches a prefix, `*` any tool
+    pub tool: String,
+    /// Regex the tool input field must match (e.g. on the Bash command)
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Tool input field for `pattern` (default `command`)
+    #[serde(default)]
+    pub field: Option<String>,
+    /// Phase to enter; the tool is ignored when unset
+    #[serde(default)]
+    pub phase: Option<String>,
+}
+
+fn default_phases() -> Vec<String> {
+    DEFAULT_PHASES.iter().map(|p| p.to_string()).collect()
+}
+
+fn default_idle_timeout_secs() -> u64 {
+    30 * 60
+}
+
+impl StepRule {
+    pub fn new(tool: &str, phase: Option<&str>) -> Self {
+        Self {
+            tool: tool.to_string(),
+            pattern: None,
+            field: None,
+            phase: phase.map(str::to_string),
+        }
+    }
+
+    pub fn with_pattern(mut self, pattern: &str) -> Self {
+        self.pattern = Some(pattern.to_string());
+        self
+    }
+
+    pub fn matches_tool(&self, tool_name: &str) -> bool {
+        match self.tool.strip_suffix('*') {
+            Some(prefix) => tool_name.starts_with(prefix),
+            None => self.tool == tool_name,
+        }
+    }
+}
+
+impl Default for StepConfig {
+    fn default() -> Self {
+        let rule = |tool: &str, phase: Option<&str>| StepRule::new(tool, phase);
+        let mut rules: Vec<StepRule> = Vec::new();
+        rules.extend(["Read", "Glob", "Grep", "WebFetch", "WebSearch"].map(|t| rule(t, Some("ANALYSIS"))));
+        rules.extend(
+            ["EnterPlanMode", "ExitPlanMode", "TaskCreate", "TaskUpdate", "TaskList", "TaskGet"]
+                .map(|t| rule(t, Some("DESIGN"))),
+        );
+        rules.extend(["Edit", "Write", "NotebookEdit"].map(|t| rule(t, Some("IMPLEMENTATION"))));
+        // 테스트/빌드 명령은 검증, 그 외 Bash는 구현
+        rules.push(
+            rule("Bash", Some("VERIFICATION"))
+                .with_pattern(r"(?i)test|jest|pytest|vitest|cargo (?:check|clippy|build)|tsc\b|eslint|mvn verify|gradlew (?:check|build)"),
+        );
+        rules.push(rule("Bash", Some("IMPLEMENTATION")));
+        Self {
+            phases: default_phases(),
+            rules,
+            idle_timeout_secs: default_idle_timeout_secs(),
+        }
+    }
+}
+
+impl StepConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.phases.is_empty() {
+            return Err("At least one phase is required".to_string());
+        }
+        for (i, phase) in self.phases.iter().enumerate() {
+            if phase.trim().is_empty() {
+                return Err("Phase names cannot be empty".to_string());
+            }
+            if self.phases[..i].contains(phase) {
+                return Err(format!("Duplicate phase: {}", phase));
+            }
+        }
+        for rule in &self.rules {
+            if rule.tool.trim().is_empty() {
+                return Err("Rule tool is required".to_string());
+            }
+            if let Some(phase) = rule.phase.as_ref().filter(|p| !self.phases.contains(p)) {
+                return Err(format!("{}: unknown phase {} (expected one of {})", rule.tool, phase, self.phases.join(", ")));
+            }
+            if let Some(pattern) = &rule.pattern {
+                regex::Regex::new(pattern).map_err(|e| format!("{}: invalid pattern: {}", rule.tool, e))?;
+            }
+        }
+        if self.idle_timeout_secs == 0 {
+            return Err("idle_timeout_secs must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+/// Tools whose calls count as file edits
+pub const FILE_EDIT_TOOLS: [&str; 4] = ["Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// A tool_use block with its full input, as resent in the next request's history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub tool_use_id: String,
+    pub tool_name: String,
+    /// Size of the JSON input in bytes
+    pub input_size: i64,
+    /// Bash command (truncated)
+    pub command: Option<String>,
+    /// Program and subcommand of a Bash command (`cargo test`, `git status`)
+    pub command_head: Option<String>,
+    /// `file_path` / `notebook_path` input
+    pub file_path: Option<String>,
+}
+
+/// Outcome of a tool call, from the tool_result block that answers it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolResult {
+    pub tool_use_id: String,
+    pub is_error: bool,
+}
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+/// One normalized block of a conversation (DB row)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TranscriptEntry {
+    pub id: i64,
+    pub session_id: String,
+    /// Incremented when the conversation is rewritten (compaction, rewind)
+    pub epoch: i64,
+    /// Position of the message in the conversation
+    pub msg_index: i64,
+    pub request_id: Option<String>,
+    pub role: String,
+    /// text, thinking, tool_use, tool_result, usage
+    pub entry_type: String,
+    pub content: String,
+    pub tool_name: Option<String>,
+    pub tool_use_id: Option<String>,
+    pub input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+    pub created_at: i64,
+}
+
+/// A block to be stored (before it has an ID)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptBlock {
+    pub role: String,
+    pub entry_type: String,
+    pub content: String,
+    pub tool_name: Option<String>,
+    pub tool_use_id: Option<String>,
+}
+
+/// Per-session bookkeeping used to skip history Claude Code resends every turn
+#[derive(Debug, Clone, Default, PartialEq, sqlx::FromRow)]
+pub struct TranscriptState {
+    pub epoch: i64,
+    /// Messages stored so far in the current epoch
+    pub message_count: i64,
+    /// Hash of the first message's text; changes after compaction
+    pub first_hash: String,
+    /// Index of an assistant message stored from the response stream (replaced by the full version from the next request)
+    pub pending_response_index: Option<i64>,
+}
+
+/// Messages of one request to append to a session transcript
+#[derive(Debug, Clone)]
+pub struct TranscriptAppend {
+    pub session_id: String,
+    pub request_id: String,
+    pub epoch: i64,
+    pub first_hash: String,
+    /// Total messages in the request (becomes the stored message count)
+    pub message_count: i64,
+    /// Stream-captured assistant message being replaced (its index is also in `messages`)
+    pub replace_index: Option<i64>,
+    /// (message index, blocks)
+    pub messages: Vec<(i64, Vec<TranscriptBlock>)>,
+}
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Event types sent to webhook sinks
+pub const WEBHOOK_EVENTS: [&str; 4] = ["usage_logged", "ai_question_detected", "step_update", "session_complete"];
+
+pub const DEFAULT_SIGNATURE_HEADER: &str = "X-SwiftCast-Signature";
+
+/// An HTTP endpoint that receives proxy events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookSink {
+    /// Generated when empty
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Event types to deliver (see `WEBHOOK_EVENTS`); empty means all
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// HMAC-SHA256 key; the body signature is sent as `sha256=<hex>`
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default = "default_signature_header")]
+    pub signature_header: String,
+    /// Extra request headers (e.g. `Authorization`)
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// JSON body with `{{path}}` placeholders into the event payload; the payload itself when unset
+    #[serde(default)]
+    pub template: Option<serde_json::Value>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_signature_header() -> String {
+    DEFAULT_SIGNATURE_HEADER.to_string()
+}
+
+impl WebhookSink {
+    pub fn new(name: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            id: String::new(),
+            name: name.into(),
+            url: url.into(),
+            enabled: true,
+            events: Vec::new(),
+            secret: None,
+            signature_header: default_signature_header(),
+            headers: BTreeMap::new(),
+            template: None,
+        }
+    }
+
+    pub fn subscribes(&self, event: &str) -> bool {
+        self.enabled && (self.events.is_empty() || self.events.iter().any(|e| e == event))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Webhook name is required".to_string());
+        }
+        if !(self.url.starts_with("http://") || self.url.starts_with("https://")) {
+            return Err(format!("{}: URL must be http(s): {}", self.name, self.url));
+        }
+        if let Some(event) = self.events.iter().find(|e| !WEBHOOK_EVENTS.contains(&e.as_str())) {
+            return Err(format!("{}: unknown event {} (expected one of {})", self.name, event, WEBHOOK_EVENTS.join(", ")));
+        }
+        for name in self.headers.keys().chain(std::iter::once(&self.signature_header)) {
+            if name.is_empty() || !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_') {
+                return Err(format!("{}: invalid header name: {:?}", self.name, name));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,881 @@
+//! Versioned JSON admin API (`/_swiftcast/api/v1/...`) mirroring the Tauri commands.
+//!
+//! Every request must carry the local admin token, either as
+//! `Authorization: Bearer <token>` or `x-swiftcast-token: <token>`.
+//! The token lives in the `config` table and is also written to
+//! `<app_data_dir>/admin_token` so scripts can read it.
+
+use crate::claude_settings::update_claude_settings;
+use crate::models::{available_models, Account, AlertFiring, AlertRule, StepConfig, TranscriptEntry, WebhookSink};
+use crate::proxy::question_detector::{questions_from_tool_input, DetectedQuestion, QuestionRules};
+use crate::proxy::request_kind::{KindRouter, RequestKind};
+use crate::proxy::search;
+use crate::proxy::session_lifecycle::{SessionTracker, DEFAULT_END_AFTER_SECS, DEFAULT_IDLE_AFTER_SECS};
+use crate::proxy::step_tracker::StepTracker;
+use crate::proxy::webhook::{WebhookClient, WebhookPayload};
+use crate::storage::database::{
+    DeadLetter, HookConfig, KindRoute, OutboxEntry, PhaseStats, SearchHit, SearchQuery, SessionHookConfig, SessionLifecycle, SessionTurn,
+    SubagentRouting, SubagentSession,
+    LatencyGroup, StepTimeline, ToolStatsFilter, ToolUsageReport,
+};
+use crate::storage::usage_export::{self, ExportFormat, ExportOptions, GroupKey, Period};
+use crate::storage::Database;
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Clone)]
+struct AdminState {
+    db: Arc<Database>,
+    webhook: WebhookClient,
+    steps: StepTracker,
+    sessions: SessionTracker,
+    kinds: KindRouter,
+}
+
+/// JSON error body: `{"error": "..."}`
+struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn not_found(msg: impl Into<String>) -> Self {
+        Self(StatusCode::NOT_FOUND, msg.into())
+    }
+
+    fn bad_request(msg: impl Into<String>) -> Self {
+        Self(StatusCode::BAD_REQUEST, msg.into())
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        Self(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+/// Build the admin router; nest it under `/_swiftcast/api/v1`
+pub fn router<S>(db: Arc<Database>, webhook: WebhookClient, steps: StepTracker, sessions: SessionTracker, kinds: KindRouter) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let state = AdminState { db, webhook, steps, sessions, kinds };
+
+    Router::new()
+        .route("/status", get(status))
+        .route("/accounts", get(list_accounts).post(create_account))
+        .route("/accounts/active", get(active_account))
+        .route("/accounts/:id", axum::routing::delete(delete_account))
+        .route("/accounts/:id/switch", post(switch_account))
+        .route("/accounts/:id/timeouts", put(set_account_timeouts))
+        .route("/sessions", get(list_sessions))
+        .route("/sessions/:id", put(set_session_config).delete(delete_session_config))
+        .route(
+            "/sessions/:id/hooks",
+            get(get_session_hooks).put(set_session_hooks).delete(delete_session_hooks),
+        )
+        .route("/sessions/:id/subagents", get(list_subagents))
+        .route("/sessions/:id/timeline", get(session_timeline))
+        .route("/sessions/:id/steps", get(session_steps))
+        .route("/sessions/:id/lifecycle", get(session_lifecycle))
+        .route("/sessions/:id/end", post(end_session))
+        .route("/session-hooks", get(list_session_hooks))
+        .route("/subagents/routing", get(get_subagent_routing).put(set_subagent_routing))
+        .route("/routing/kinds", get(get_kind_routes).put(set_kind_routes))
+        .route("/usage", axum::routing::delete(clear_usage))
+        .route("/usage/export", get(export_usage))
+        .route("/usage/import", post(import_usage))
+        .route("/usage/stats", get(usage_stats))
+        .route("/usage/recent", get(recent_usage))
+        .route("/usage/by-account", get(usage_by_account))
+        .route("/usage/by-model", get(usage_by_model))
+        .route("/usage/by-session", get(usage_by_session))
+        .route("/usage/by-kind", get(usage_by_kind))
+        .route("/usage/by-project", get(usage_by_project))
+        .route("/usage/latency", get(usage_latency))
+        .route("/errors", get(recent_errors))
+        .route("/errors/timeline", get(error_timeline))
+        .route("/errors/by-account", get(errors_by_account))
+        .route("/errors/by-type", get(errors_by_type))
+        .route("/tools/stats", get(tool_stats))
+        .route("/alerts/rules", get(get_alert_rules).put(set_alert_rules))
+        .route("/alerts/history", get(alert_history))
+        .route("/steps/config", get(get_step_config).put(set_step_config))
+        .route("/steps/phases", get(phase_stats))
+        .route("/questions/rules", get(question_rules))
+        .route("/questions/test", post(test_question_rules))
+        .route("/webhooks", get(get_webhook_sinks).put(set_webhook_sinks))
+        .route("/webhooks/:id/test", post(test_webhook_sink))
+        .route("/webhooks/outbox", get(webhook_outbox))
+        .route("/webhooks/dead-letters", get(webhook_dead_letters).delete(purge_dead_letters))
+        .route("/webhooks/dead-letters/replay", post(replay_dead_letters))
+        .route("/search", get(search_transcripts))
+        .route("/usage/daily", get(daily_usage))
+        .route("/hooks/config", get(get_hook_config).put(set_hook_config))
+        .route("/config", get(get_config).put(set_config))
+        .route("/models", get(list_models))
+        .route_layer(middleware::from_fn_with_state(state.db.clone(), require_token))
+        .with_state(state)
+}
+
+/// Reject requests without a matching admin token (read from DB each time so regeneration applies immediately)
+/// Also guards `/_swiftcast/metrics` and `/_swiftcast/events`
+pub async fn require_token(State(db): State<Arc<Database>>, req: Request, next: Next) -> Response {
+    let expected = match db.get_admin_api_token().await {
+        Ok(token) => token,
+        Err(e) => return ApiError::from(e).into_response(),
+    };
+
+    match request_token(req.headers()) {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => next.run(req).await,
+        _ => ApiError(StatusCode::UNAUTHORIZED, "Missing or invalid admin token".to_string()).into_response(),
+    }
+}
+
+/// Compare without short-circuiting on the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn request_token(headers: &HeaderMap) -> Option<&str> {
+    if let Some(bearer) = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+    {
+        return Some(bearer.trim());
+    }
+    headers.get("x-swiftcast-token").and_then(|v| v.to_str().ok()).map(str::trim)
+}
+
+// ===== Status / Accounts =====
+
+async fn status(State(state): State<AdminState>) -> ApiResult<Value> {
+    let port = state.db.get_proxy_port().await?;
+    let active = state.db.get_active_account().await?;
+    Ok(Json(json!({
+        "running": true,
+        "port": port,
+        "version": env!("CARGO_PKG_VERSION"),
+        "active_account": active,
+    })))
+}
+
+async fn list_accounts(State(state): State<AdminState>) -> ApiResult<Vec<Account>> {
+    Ok(Json(state.db.get_accounts().await?))
+}
+
+async fn active_account(State(state): State<AdminState>) -> ApiResult<Option<Account>> {
+    Ok(Json(state.db.get_active_account().await?))
+}
+
+#[derive(Deserialize)]
+struct CreateAccountInput {
+    name: String,
+    base_url: String,
+    api_key: String,
+}
+
+async fn create_account(
+    State(state): State<AdminState>,
+    Json(input): Json<CreateAccountInput>,
+) -> Result<(StatusCode, Json<Account>), ApiError> {
+    let account = Account::new(input.name, input.base_url);
+    state.db.create_account(account.clone(), input.api_key).await?;
+
+    // 첫 번째 계정이면 자동 활성화 (GUI와 동일)
+    if state.db.get_accounts().await?.len() == 1 {
+        state.db.switch_account(&account.id).await?;
+    }
+
+    Ok((StatusCode::CREATED, Json(account)))
+}
+
+async fn switch_account(State(state): State<AdminState>, Path(id): Path<String>) -> ApiResult<Account> {
+    if state.db.get_account(&id).await?.is_none() {
+        return Err(ApiError::not_found("Account not found"));
+    }
+    state.db.switch_account(&id).await?;
+
+    let account = state
+        .db
+        .get_active_account()
+        .await?
+        .ok_or_else(|| ApiError::not_found("Account not found after switch"))?;
+    let proxy_port = state.db.get_proxy_port().await?;
+    update_claude_settings(&account.base_url, proxy_port)
+        .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    tracing::info!("Admin API: switched active account to {}", account.name);
+    Ok(Json(account))
+}
+
+async fn delete_account(State(state): State<AdminState>, Path(id): Path<String>) -> Result<StatusCode, ApiError> {
+    state.db.delete_account(&id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct AccountTimeoutsInput {
+    connect_timeout_secs: Option<u32>,
+    first_byte_timeout_secs: Option<u32>,
+    idle_timeout_secs: Option<u32>,
+}
+
+async fn set_account_timeouts(
+    State(state): State<AdminState>,
+    Path(id): Path<String>,
+    Json(input): Json<AccountTimeoutsInput>,
+) -> ApiResult<Account> {
+    if state.db.get_account(&id).await?.is_none() {
+        return Err(ApiError::not_found("Account not found"));
+    }
+    state
+        .db
+        .set_account_timeouts(&id, input.connect_timeout_secs, input.first_byte_timeout_secs, input.idle_timeout_secs)
+        .await?;
+    let account = state.db.get_account(&id).await?.ok_or_else(|| ApiError::not_found("Account not found"))?;
+    Ok(Json(account))
+}
+
+// ===== Sessions =====
+
+async fn list_sessions(State(state): State<AdminState>) -> ApiResult<Value> {
+    Ok(Json(json!(state.db.get_active_sessions().await?)))
+}
+
+#[derive(Deserialize)]
+struct SessionConfigInput {
+    account_id: String,
+    model_override: Option<String>,
+}
+
+async fn set_session_config(
+    State(state): State<AdminState>,
+    Path(session_id): Path<String>,
+    Json(input): Json<SessionConfigInput>,
+) -> Result<StatusCode, ApiError> {
+    if state.db.get_account(&input.account_id).await?.is_none() {
+        return Err(ApiError::not_found("Account not found"));
+    }
+    state
+        .db
+        .upsert_session_config(&session_id, &input.account_id, input.model_override.as_deref())
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_session_config(
+    State(state): State<AdminState>,
+    Path(session_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.db.delete_session_config(&session_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_subagents(
+    State(state): State<AdminState>,
+    Path(session_id): Path<String>,
+) -> ApiResult<Vec<SubagentSession>> {
+    Ok(Json(state.db.get_subagent_sessions(&session_id).await?))
+}
+
+#[derive(Deserialize)]
+struct TimelineQuery {
+    /// Only entries with a larger id (for polling)
+    after_id: Option<i64>,
+    limit: Option<i64>,
+    #[serde(default)]
+    include_subagents: bool,
+}
+
+async fn session_timeline(
+    State(state): State<AdminState>,
+    Path(session_id): Path<String>,
+    Query(q): Query<TimelineQuery>,
+) -> ApiResult<Vec<TranscriptEntry>> {
+    let limit = q.limit.unwrap_or(500).clamp(1, 5000);
+    Ok(Json(
+        state
+            .db
+            .get_session_timeline(&session_id, q.include_subagents, q.after_id.unwrap_or(0), limit)
+            .await?,
+    ))
+}
+
+#[derive(serde::Serialize)]
+struct SessionLifecycleReport {
+    #[serde(flatten)]
+    lifecycle: SessionLifecycle,
+    turns: Vec<SessionTurn>,
+}
+
+/// `GET /sessions/:id/lifecycle` - state, wall/active time and per-turn breakdown
+async fn session_lifecycle(
+    State(state): State<AdminState>,
+    Path(session_id): Path<String>,
+) -> ApiResult<SessionLifecycleReport> {
+    let lifecycle = state
+        .db
+        .get_session_lifecycle(&session_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("No recorded activity for this session"))?;
+    let turns = state.db.get_session_turns(&session_id).await?;
+    Ok(Json(SessionLifecycleReport { lifecycle, turns }))
+}
+
+/// `POST /sessions/:id/end` - mark a session ended (e.g. from a SessionEnd hook)
+async fn end_session(State(state): State<AdminState>, Path(session_id): Path<String>) -> Result<StatusCode, ApiError> {
+    if !state.sessions.end_session(&session_id).await? {
+        return Err(ApiError::not_found("No recorded activity for this session"));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_subagent_routing(State(state): State<AdminState>) -> ApiResult<SubagentRouting> {
+    Ok(Json(state.db.get_subagent_routing().await?))
+}
+
+async fn set_subagent_routing(
+    State(state): State<AdminState>,
+    Json(routing): Json<SubagentRouting>,
+) -> Result<StatusCode, ApiError> {
+    if let Some(ref account_id) = routing.account_id {
+        if state.db.get_account(account_id).await?.is_none() {
+            return Err(ApiError::not_found("Account not found"));
+        }
+    }
+    state.db.set_subagent_routing(&routing).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_kind_routes(State(state): State<AdminState>) -> ApiResult<HashMap<String, KindRoute>> {
+    Ok(Json(state.db.get_request_kind_routes().await?))
+}
+
+/// Replace all request-kind routes (`{"title_generation": {"account_id": "...", "model_override": null}}`)
+async fn set_kind_routes(
+    State(state): State<AdminState>,
+    Json(routes): Json<HashMap<String, KindRoute>>,
+) -> Result<StatusCode, ApiError> {
+    for (kind, route) in &routes {
+        if RequestKind::parse(kind).is_none() {
+            return Err(ApiError::bad_request(format!("Unknown request kind: {}", kind)));
+        }
+        if state.db.get_account(&route.account_id).await?.is_none() {
+            return Err(ApiError::not_found(format!("Account not found: {}", route.account_id)));
+        }
+    }
+    state.db.set_request_kind_routes(&routes).await?;
+    state.kinds.configure(&routes).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_session_hooks(
+    State(state): State<AdminState>,
+    Path(session_id): Path<String>,
+) -> ApiResult<SessionHookConfig> {
+    let config = state.db.get_session_hooks(&session_id).await?;
+    Ok(Json(config.unwrap_or_else(|| SessionHookConfig::new(session_id))))
+}
+
+#[derive(Deserialize)]
+struct SessionHooksInput {
+    api_logging_enabled: bool,
+    compaction_injection_enabled: bool,
+    compaction_summarization_instructions: Option<String>,
+    compaction_context_injection: Option<String>,
+    custom_tasks_enabled: bool,
+}
+
+async fn set_session_hooks(
+    State(state): State<AdminState>,
+    Path(session_id): Path<String>,
+    Json(input): Json<SessionHooksInput>,
+) -> Result<StatusCode, ApiError> {
+    let config = SessionHookConfig {
+        api_logging_enabled: input.api_logging_enabled,
+        compaction_injection_enabled: input.compaction_injection_enabled,
+        compaction_summarization_instructions: input.compaction_summarization_instructions,
+        compaction_context_injection: input.compaction_context_injection,
+        custom_tasks_enabled: input.custom_tasks_enabled,
+        ..SessionHookConfig::new(session_id)
+    };
+    state.db.set_session_hooks(&config).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_session_hooks(
+    State(state): State<AdminState>,
+    Path(session_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.db.delete_session_hooks(&session_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_session_hooks(State(state): State<AdminState>) -> ApiResult<Vec<SessionHookConfig>> {
+    Ok(Json(state.db.get_all_session_hooks().await?))
+}
+
+// ===== Usage =====
+
+/// `?project=` filter accepted by usage reports (full path or directory name)
+#[derive(Deserialize)]
+struct ProjectQuery {
+    project: Option<String>,
+}
+
+async fn usage_stats(State(state): State<AdminState>, Query(q): Query<ProjectQuery>) -> ApiResult<Value> {
+    let (request_count, input_tokens, output_tokens) = state.db.get_usage_stats(q.project.as_deref()).await?;
+    Ok(Json(json!({
+        "request_count": request_count,
+        "input_tokens": input_tokens,
+        "output_tokens": output_tokens,
+    })))
+}
+
+#[derive(Deserialize)]
+struct RecentQuery {
+    limit: Option<i64>,
+    project: Option<String>,
+}
+
+async fn recent_usage(State(state): State<AdminState>, Query(q): Query<RecentQuery>) -> ApiResult<Value> {
+    let limit = q.limit.unwrap_or(50).clamp(1, 1000);
+    Ok(Json(json!(state.db.get_recent_usage(limit, q.project.as_deref()).await?)))
+}
+
+async fn usage_by_account(State(state): State<AdminState>, Query(q): Query<ProjectQuery>) -> ApiResult<Value> {
+    Ok(Json(json!(state.db.get_usage_by_account(q.project.as_deref()).await?)))
+}
+
+async fn usage_by_model(State(state): State<AdminState>, Query(q): Query<ProjectQuery>) -> ApiResult<Value> {
+    Ok(Json(json!(state.db.get_usage_by_model(q.project.as_deref()).await?)))
+}
+
+async fn usage_by_session(State(state): State<AdminState>, Query(q): Query<ProjectQuery>) -> ApiResult<Value> {
+    Ok(Json(json!(state.db.get_usage_by_session(q.project.as_deref()).await?)))
+}
+
+async fn usage_by_kind(State(state): State<AdminState>, Query(q): Query<ProjectQuery>) -> ApiResult<Value> {
+    Ok(Json(json!(state.db.get_usage_by_kind(q.project.as_deref()).await?)))
+}
+
+async fn usage_by_project(State(state): State<AdminState>) -> ApiResult<Value> {
+    Ok(Json(json!(state.db.get_usage_by_project().await?)))
+}
+
+#[derive(Deserialize)]
+struct LatencyQuery {
+    /// account, model or hour
+    by: Option<String>,
+    days: Option<i64>,
+    project: Option<String>,
+}
+
+async fn usage_latency(State(state): State<AdminState>, Query(q): Query<LatencyQuery>) -> ApiResult<Value> {
+    let by = match q.by.as_deref() {
+        Some(by) => LatencyGroup::parse(by).ok_or_else(|| ApiError::bad_request("by must be account, model or hour"))?,
+        None => LatencyGroup::Model,
+    };
+    let days = q.days.unwrap_or(7).clamp(1, 365);
+    Ok(Json(json!(state.db.get_latency_stats(by, days, q.project.as_deref()).await?)))
+}
+
+#[derive(Deserialize)]
+struct ErrorQuery {
+    days: Option<i64>,
+    account_id: Option<String>,
+    /// Timeline buckets per hour instead of per day
+    #[serde(default)]
+    hourly: bool,
+    limit: Option<i64>,
+}
+
+async fn recent_errors(State(state): State<AdminState>, Query(q): Query<ErrorQuery>) -> ApiResult<Value> {
+    let limit = q.limit.unwrap_or(50).clamp(1, 1000);
+    Ok(Json(json!(state.db.get_recent_errors(limit, q.account_id.as_deref()).await?)))
+}
+
+async fn error_timeline(State(state): State<AdminState>, Query(q): Query<ErrorQuery>) -> ApiResult<Value> {
+    let days = q.days.unwrap_or(7).clamp(1, 365);
+    Ok(Json(json!(state.db.get_error_timeline(days, q.hourly, q.account_id.as_deref()).await?)))
+}
+
+async fn errors_by_account(State(state): State<AdminState>, Query(q): Query<ErrorQuery>) -> ApiResult<Value> {
+    let days = q.days.unwrap_or(7).clamp(1, 365);
+    Ok(Json(json!(state.db.get_errors_by_account(days).await?)))
+}
+
+async fn errors_by_type(State(state): State<AdminState>, Query(q): Query<ErrorQuery>) -> ApiResult<Value> {
+    let days = q.days.unwrap_or(7).clamp(1, 365);
+    Ok(Json(json!(state.db.get_errors_by_type(days, q.account_id.as_deref()).await?)))
+}
+
+/// `GET /tools/stats?session_id=&model=&days=`
+async fn tool_stats(State(state): State<AdminState>, Query(filter): Query<ToolStatsFilter>) -> ApiResult<ToolUsageReport> {
+    Ok(Json(state.db.get_tool_usage_report(&filter).await?))
+}
+
+/// `GET /search?query=&session_id=&account_id=&model=&request_kind=&since=&until=&limit=`
+async fn get_alert_rules(State(state): State<AdminState>) -> ApiResult<Vec<AlertRule>> {
+    Ok(Json(state.db.get_alert_rules().await?))
+}
+
+/// Replace all alert rules; returns them with generated IDs
+async fn set_alert_rules(
+    State(state): State<AdminState>,
+    Json(rules): Json<Vec<AlertRule>>,
+) -> ApiResult<Vec<AlertRule>> {
+    for rule in &rules {
+        rule.validate().map_err(ApiError::bad_request)?;
+    }
+    Ok(Json(state.db.set_alert_rules(&rules).await?))
+}
+
+async fn alert_history(State(state): State<AdminState>, Query(q): Query<RecentQuery>) -> ApiResult<Vec<AlertFiring>> {
+    let limit = q.limit.unwrap_or(50).clamp(1, 1000);
+    Ok(Json(state.db.get_alert_firings(limit).await?))
+}
+
+async fn get_step_config(State(state): State<AdminState>) -> ApiResult<StepConfig> {
+    Ok(Json(state.db.get_step_config().await?))
+}
+
+async fn set_step_config(State(state): State<AdminState>, Json(config): Json<StepConfig>) -> ApiResult<StepConfig> {
+    state.steps.configure(&config).await.map_err(ApiError::bad_request)?;
+    state.db.set_step_config(&config).await?;
+    Ok(Json(config))
+}
+
+#[derive(Deserialize)]
+struct PhaseQuery {
+    days: Option<i64>,
+}
+
+/// `GET /steps/phases?days=` - time per phase across sessions
+async fn phase_stats(State(state): State<AdminState>, Query(q): Query<PhaseQuery>) -> ApiResult<Vec<PhaseStats>> {
+    let days = q.days.unwrap_or(7).clamp(1, 365);
+    Ok(Json(state.db.get_phase_stats(days).await?))
+}
+
+/// `GET /sessions/:id/steps` - phase spans of a session with time per phase
+async fn session_steps(State(state): State<AdminState>, Path(session_id): Path<String>) -> ApiResult<StepTimeline> {
+    Ok(Json(state.db.get_step_timeline(&session_id).await?))
+}
+
+/// `GET /questions/rules` - active question rules (name, language) in evaluation order
+async fn question_rules() -> ApiResult<Value> {
+    let path = QuestionRules::default_config_path();
+    let rules = QuestionRules::load(&path).map_err(ApiError::bad_request)?;
+    let rules: Vec<Value> = rules
+        .rule_names()
+        .into_iter()
+        .map(|(name, language)| json!({ "name": name, "language": language }))
+        .collect();
+    Ok(Json(json!({ "path": path, "rules": rules })))
+}
+
+#[derive(Deserialize)]
+struct QuestionTestInput {
+    #[serde(default)]
+    text: String,
+    /// `AskUserQuestion` tool input, parsed instead of `text` when present
+    tool_input: Option<Value>,
+}
+
+/// `POST /questions/test` - run a sample through the question rules file
+async fn test_question_rules(Json(input): Json<QuestionTestInput>) -> ApiResult<Vec<DetectedQuestion>> {
+    if let Some(tool_input) = input.tool_input {
+        return Ok(Json(questions_from_tool_input(&tool_input)));
+    }
+    let rules = QuestionRules::load(&QuestionRules::default_config_path()).map_err(ApiError::bad_request)?;
+    Ok(Json(rules.detect(&input.text).into_iter().collect()))
+}
+
+async fn get_webhook_sinks(State(state): State<AdminState>) -> ApiResult<Vec<WebhookSink>> {
+    Ok(Json(state.db.get_webhook_sinks().await?))
+}
+
+/// Replace all webhook sinks (applies immediately); returns them with generated IDs
+async fn set_webhook_sinks(
+    State(state): State<AdminState>,
+    Json(sinks): Json<Vec<WebhookSink>>,
+) -> ApiResult<Vec<WebhookSink>> {
+    for sink in &sinks {
+        sink.validate().map_err(ApiError::bad_request)?;
+    }
+    let sinks = state.db.set_webhook_sinks(&sinks).await?;
+    state.webhook.configure(sinks.clone()).await;
+    Ok(Json(sinks))
+}
+
+/// Send a `test` event to one sink and report the receiver's status
+async fn test_webhook_sink(State(state): State<AdminState>, Path(id): Path<String>) -> ApiResult<Value> {
+    let sink = state
+        .db
+        .get_webhook_sinks()
+        .await?
+        .into_iter()
+        .find(|s| s.id == id || s.name == id)
+        .ok_or_else(|| ApiError::not_found("Webhook not found"))?;
+    let status = state.webhook.deliver(&sink, &WebhookPayload::test()).await?;
+    Ok(Json(json!({ "status": status.as_u16(), "success": status.is_success() })))
+}
+
+/// `GET /webhooks/outbox?limit=` - queued deliveries, oldest first
+async fn webhook_outbox(State(state): State<AdminState>, Query(q): Query<RecentQuery>) -> ApiResult<Vec<OutboxEntry>> {
+    let limit = q.limit.unwrap_or(100).clamp(1, 1000);
+    Ok(Json(state.db.get_webhook_outbox(limit).await?))
+}
+
+#[derive(Deserialize)]
+struct DeadLetterQuery {
+    limit: Option<i64>,
+    sink_id: Option<String>,
+    /// Comma-separated dead letter IDs (replay / purge all matching when empty)
+    ids: Option<String>,
+}
+
+impl DeadLetterQuery {
+    fn ids(&self) -> Result<Vec<i64>, ApiError> {
+        self.ids
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .filter(|id| !id.trim().is_empty())
+            .map(|id| id.trim().parse().map_err(|_| ApiError::bad_request(format!("Invalid id: {}", id))))
+            .collect()
+    }
+}
+
+async fn webhook_dead_letters(State(state): State<AdminState>, Query(q): Query<DeadLetterQuery>) -> ApiResult<Vec<DeadLetter>> {
+    let limit = q.limit.unwrap_or(100).clamp(1, 1000);
+    Ok(Json(state.db.get_webhook_dead_letters(limit, q.sink_id.as_deref()).await?))
+}
+
+/// `POST /webhooks/dead-letters/replay?ids=&sink_id=` - queue failed deliveries again
+async fn replay_dead_letters(State(state): State<AdminState>, Query(q): Query<DeadLetterQuery>) -> ApiResult<Value> {
+    let replayed = state.db.replay_webhook_dead_letters(&q.ids()?, q.sink_id.as_deref()).await?;
+    state.webhook.notify_outbox();
+    Ok(Json(json!({ "replayed": replayed })))
+}
+
+async fn purge_dead_letters(State(state): State<AdminState>, Query(q): Query<DeadLetterQuery>) -> ApiResult<Value> {
+    let purged = state.db.purge_webhook_dead_letters(&q.ids()?, q.sink_id.as_deref()).await?;
+    Ok(Json(json!({ "purged": purged })))
+}
+
+async fn search_transcripts(
+    State(state): State<AdminState>,
+    Query(query): Query<SearchQuery>,
+) -> ApiResult<Vec<SearchHit>> {
+    if query.query.trim().is_empty() {
+        return Err(ApiError::bad_request("query is required"));
+    }
+    if let Some(kind) = &query.request_kind {
+        if RequestKind::parse(kind).is_none() {
+            return Err(ApiError::bad_request(format!("Unknown request kind: {}", kind)));
+        }
+    }
+    Ok(Json(search::search(&state.db, query).await?))
+}
+
+#[derive(Deserialize)]
+struct DailyQuery {
+    days: Option<i64>,
+    project: Option<String>,
+}
+
+async fn daily_usage(State(state): State<AdminState>, Query(q): Query<DailyQuery>) -> ApiResult<Value> {
+    let days = q.days.unwrap_or(7).clamp(1, 365);
+    Ok(Json(json!(state.db.get_daily_usage(days, q.project.as_deref()).await?)))
+}
+
+async fn clear_usage(State(state): State<AdminState>) -> Result<StatusCode, ApiError> {
+    state.db.clear_usage_logs().await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    format: Option<String>,
+    since: Option<i64>,
+    until: Option<i64>,
+    origin: Option<String>,
+    /// `day` or `month`
+    period: Option<String>,
+    /// Comma-separated: origin, account, model, session, kind, project, branch
+    group_by: Option<String>,
+}
+
+async fn export_usage(State(state): State<AdminState>, Query(q): Query<ExportQuery>) -> Result<Response, ApiError> {
+    let format = match q.format.as_deref() {
+        Some(f) => ExportFormat::parse(f).ok_or_else(|| ApiError::bad_request(format!("Unknown format: {}", f)))?,
+        None => ExportFormat::Csv,
+    };
+    let period = match q.period.as_deref() {
+        Some(p) => Some(Period::parse(p).ok_or_else(|| ApiError::bad_request(format!("Unknown period: {}", p)))?),
+        None => None,
+    };
+    let group_by = GroupKey::parse_list(q.group_by.as_deref().unwrap_or_default())
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    let options = ExportOptions { format, since: q.since, until: q.until, origin: q.origin, period, group_by };
+
+    let (content, _) = usage_export::export_usage(&state.db, &options).await?;
+    Ok(([(axum::http::header::CONTENT_TYPE, format.content_type())], content).into_response())
+}
+
+#[derive(Deserialize)]
+struct ImportQuery {
+    /// Detected from the body when omitted
+    format: Option<String>,
+    /// Account for rows whose account matches neither by id nor by name
+    fallback_account_id: Option<String>,
+}
+
+async fn import_usage(
+    State(state): State<AdminState>,
+    Query(q): Query<ImportQuery>,
+    body: String,
+) -> ApiResult<Value> {
+    let format = match q.format.as_deref() {
+        Some(f) => Some(ExportFormat::parse(f).ok_or_else(|| ApiError::bad_request(format!("Unknown format: {}", f)))?),
+        None => None,
+    };
+    let report = usage_export::import_usage(&state.db, &body, format, q.fallback_account_id.as_deref())
+        .await
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    Ok(Json(json!(report)))
+}
+
+// ===== Hooks / Config / Models =====
+
+async fn get_hook_config(State(state): State<AdminState>) -> ApiResult<HookConfig> {
+    Ok(Json(state.db.get_hook_config().await?))
+}
+
+async fn set_hook_config(
+    State(state): State<AdminState>,
+    Json(config): Json<HookConfig>,
+) -> Result<StatusCode, ApiError> {
+    state.db.set_hook_config(&config).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_config(State(state): State<AdminState>) -> ApiResult<Value> {
+    Ok(Json(json!({
+        "proxy_port": state.db.get_proxy_port().await?,
+        "auto_start": state.db.get_auto_start().await?,
+        "session_headers": state.db.get_config("session_headers").await?.unwrap_or_default(),
+        "transcripts_enabled": state.db.get_config("transcripts_enabled").await?.map(|v| v == "true").unwrap_or(true),
+        "session_idle_secs": config_secs(&state.db, "session_idle_secs", DEFAULT_IDLE_AFTER_SECS).await?,
+        "session_end_secs": config_secs(&state.db, "session_end_secs", DEFAULT_END_AFTER_SECS).await?,
+    })))
+}
+
+async fn config_secs(db: &Database, key: &str, default: i64) -> anyhow::Result<i64> {
+    Ok(db.get_config(key).await?.and_then(|v| v.parse().ok()).unwrap_or(default))
+}
+
+#[derive(Deserialize)]
+struct ConfigInput {
+    proxy_port: Option<u16>,
+    auto_start: Option<bool>,
+    /// Extra headers checked for a session ID, comma separated (applies on next proxy start)
+    session_headers: Option<String>,
+    /// Store conversation transcripts (per-session timeline)
+    transcripts_enabled: Option<bool>,
+    /// Quiet time before a session counts as idle
+    session_idle_secs: Option<i64>,
+    /// Quiet time before a session counts as ended
+    session_end_secs: Option<i64>,
+}
+
+async fn set_config(
+    State(state): State<AdminState>,
+    Json(input): Json<ConfigInput>,
+) -> Result<StatusCode, ApiError> {
+    if let Some(port) = input.proxy_port {
+        if port < 1024 {
+            return Err(ApiError::bad_request("proxy_port must be >= 1024"));
+        }
+        state.db.set_config("proxy_port", &port.to_string()).await?;
+    }
+    if let Some(enabled) = input.auto_start {
+        state.db.set_config("auto_start", if enabled { "true" } else { "false" }).await?;
+    }
+    if let Some(headers) = input.session_headers {
+        state.db.set_config("session_headers", headers.trim()).await?;
+    }
+    if let Some(enabled) = input.transcripts_enabled {
+        state.db.set_config("transcripts_enabled", if enabled { "true" } else { "false" }).await?;
+    }
+    for (key, secs) in [("session_idle_secs", input.session_idle_secs), ("session_end_secs", input.session_end_secs)] {
+        if let Some(secs) = secs {
+            if secs < 60 {
+                return Err(ApiError::bad_request(format!("{} must be >= 60", key)));
+            }
+            state.db.set_config(key, &secs.to_string()).await?;
+        }
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct ModelsQuery {
+    base_url: Option<String>,
+}
+
+async fn list_models(State(state): State<AdminState>, Query(q): Query<ModelsQuery>) -> ApiResult<Value> {
+    let base_url = match q.base_url {
+        Some(url) => url,
+        None => state
+            .db
+            .get_active_account()
+            .await?
+            .map(|a| a.base_url)
+            .unwrap_or_default(),
+    };
+    Ok(Json(json!(available_models(&base_url))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_request_token() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(request_token(&headers), None);
+
+        headers.insert("x-swiftcast-token", HeaderValue::from_static("abc"));
+        assert_eq!(request_token(&headers), Some("abc"));
+
+        headers.insert("authorization", HeaderValue::from_static("Bearer xyz"));
+        assert_eq!(request_token(&headers), Some("xyz"));
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+    }
+}
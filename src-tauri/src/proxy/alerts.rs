@@ -0,0 +1,338 @@
+use super::events::{EventBus, ProxyEventKind};
+use crate::models::{AlertChannel, AlertFiring, AlertMetric, AlertRule, AlertScope, AlertWindow};
+use crate::storage::database::AlertQuery;
+use crate::storage::Database;
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// error_rate rules stay quiet until the window has this many requests
+const DEFAULT_MIN_REQUESTS: i64 = 5;
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What was just logged
+#[derive(Debug, Clone)]
+pub struct AlertTrigger {
+    pub account_id: String,
+    pub model: String,
+    pub session_id: Option<String>,
+    /// An error row (otherwise a usage row)
+    pub is_error: bool,
+}
+
+/// Evaluates alert rules after usage rows and errors are logged and delivers firings
+#[derive(Clone)]
+pub struct AlertEngine {
+    db: Arc<Database>,
+    events: EventBus,
+    client: reqwest::Client,
+    /// Serializes evaluation so concurrent requests can't fire the same alert twice
+    lock: Arc<Mutex<()>>,
+}
+
+impl AlertEngine {
+    pub fn new(db: Arc<Database>, events: EventBus) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("Failed to create HTTP client");
+        Self { db, events, client, lock: Arc::new(Mutex::new(())) }
+    }
+
+    /// Evaluate rules affected by the logged row (errors are logged, not returned)
+    pub async fn evaluate(&self, trigger: AlertTrigger) {
+        if let Err(e) = self.evaluate_rules(&trigger).await {
+            tracing::warn!("Alert evaluation failed: {}", e);
+        }
+    }
+
+    async fn evaluate_rules(&self, trigger: &AlertTrigger) -> Result<()> {
+        let rules = self.db.get_alert_rules().await?;
+        if rules.is_empty() {
+            return Ok(());
+        }
+        let _guard = self.lock.lock().await;
+        let now = chrono::Utc::now().timestamp();
+
+        for rule in rules.iter().filter(|r| r.enabled && affected_by(r, trigger)) {
+            let group_key = match rule.scope {
+                AlertScope::Global => "*".to_string(),
+                AlertScope::Account => trigger.account_id.clone(),
+                AlertScope::Model => trigger.model.clone(),
+                AlertScope::Session => match &trigger.session_id {
+                    Some(sid) => self.db.root_session_id(sid).await?,
+                    None => continue,
+                },
+            };
+            let (window_key, suppress_since) = window_key(rule.window, now);
+            if let Some(last) = self.db.last_alert_firing(&rule.id, &group_key, &window_key).await? {
+                if last >= suppress_since {
+                    continue;
+                }
+            }
+
+            let query = AlertQuery {
+                since: window_start(rule.window, now),
+                scope: rule.scope,
+                scope_value: (rule.scope != AlertScope::Global).then_some(group_key.as_str()),
+                account_id: rule.account_id.as_deref(),
+                model: rule.model.as_deref(),
+            };
+            let Some(value) = self.metric_value(rule, &query).await? else {
+                continue;
+            };
+            if value < rule.threshold {
+                continue;
+            }
+
+            let mut firing = AlertFiring {
+                id: 0,
+                rule_id: rule.id.clone(),
+                rule_name: rule.name.clone(),
+                metric: rule.metric.as_str().to_string(),
+                group_key: group_key.clone(),
+                window_key,
+                value,
+                threshold: rule.threshold,
+                message: message(rule, &group_key, value),
+                fired_at: now,
+            };
+            firing.id = self.db.record_alert_firing(&firing).await?;
+            tracing::warn!("ALERT: {}", firing.message);
+            self.deliver(rule, firing, trigger.session_id.clone());
+        }
+        Ok(())
+    }
+
+    async fn metric_value(&self, rule: &AlertRule, query: &AlertQuery<'_>) -> Result<Option<f64>> {
+        let value = match rule.metric {
+            AlertMetric::CostUsd => self.db.alert_usage_totals(query).await?.2,
+            AlertMetric::Tokens => self.db.alert_usage_totals(query).await?.1 as f64,
+            AlertMetric::Requests => self.db.alert_usage_totals(query).await?.0 as f64,
+            AlertMetric::Errors => self.db.alert_error_count(query, rule.error_type.as_deref()).await? as f64,
+            AlertMetric::ErrorRate => {
+                let (succeeded, _, _) = self.db.alert_usage_totals(query).await?;
+                let all_errors = self.db.alert_error_count(query, None).await?;
+                let errors = match rule.error_type.as_deref() {
+                    Some(error_type) => self.db.alert_error_count(query, Some(error_type)).await?,
+                    None => all_errors,
+                };
+                let total = succeeded + all_errors;
+                if total < rule.min_requests.unwrap_or(DEFAULT_MIN_REQUESTS).max(1) {
+                    return Ok(None);
+                }
+                errors as f64 / total as f64
+            }
+        };
+        Ok(Some(value))
+    }
+
+    fn deliver(&self, rule: &AlertRule, firing: AlertFiring, session_id: Option<String>) {
+        let channels = if rule.channels.is_empty() { vec![AlertChannel::Event] } else { rule.channels.clone() };
+        for channel in channels {
+            match channel {
+                AlertChannel::Event => self.events.emit(
+                    None,
+                    session_id.as_deref(),
+                    ProxyEventKind::AlertFired {
+                        rule_id: firing.rule_id.clone(),
+                        rule_name: firing.rule_name.clone(),
+                        group_key: firing.group_key.clone(),
+                        value: firing.value,
+                        threshold: firing.threshold,
+                        message: firing.message.clone(),
+                    },
+                ),
+                AlertChannel::Webhook { url } => {
+                    let client = self.client.clone();
+                    let payload = serde_json::json!({"event": "alert", "alert": firing});
+                    tokio::spawn(async move {
+                        match client.post(&url).json(&payload).send().await {
+                            Ok(resp) if resp.status().is_success() => {}
+                            Ok(resp) => tracing::warn!("Alert webhook {} returned {}", url, resp.status()),
+                            Err(e) => tracing::warn!("Alert webhook {} failed: {}", url, e),
+                        }
+                    });
+                }
+                AlertChannel::Command { command } => {
+                    let firing = firing.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = run_command(&command, &firing).await {
+                            tracing::warn!("Alert command failed: {}", e);
+                        }
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Usage rows can change usage metrics and error rates, errors change error metrics
+fn affected_by(rule: &AlertRule, trigger: &AlertTrigger) -> bool {
+    let metric_matches = match rule.metric {
+        AlertMetric::Errors => trigger.is_error,
+        AlertMetric::ErrorRate => true,
+        _ => !trigger.is_error,
+    };
+    metric_matches
+        && rule.account_id.as_ref().is_none_or(|id| *id == trigger.account_id)
+        && rule.model.as_ref().is_none_or(|m| *m == trigger.model)
+}
+
+/// Start of the window ending now
+fn window_start(window: AlertWindow, now: i64) -> i64 {
+    match window {
+        AlertWindow::Minutes(n) => now - n * 60,
+        AlertWindow::Day => chrono::DateTime::from_timestamp(now, 0)
+            .map(|t| t.with_timezone(&chrono::Local).date_naive())
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .and_then(|t| t.and_local_timezone(chrono::Local).earliest())
+            .map(|t| t.timestamp())
+            .unwrap_or(now - 24 * 60 * 60),
+        AlertWindow::Total => 0,
+    }
+}
+
+/// (window key stored with the firing, earliest firing that suppresses a new one)
+fn window_key(window: AlertWindow, now: i64) -> (String, i64) {
+    match window {
+        // 슬라이딩 기간: 마지막 발생 후 N분 동안 다시 발생하지 않음
+        AlertWindow::Minutes(n) => (String::new(), now - n * 60),
+        AlertWindow::Day => {
+            let day = chrono::DateTime::from_timestamp(now, 0)
+                .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            (day, 0)
+        }
+        AlertWindow::Total => ("total".to_string(), 0),
+    }
+}
+
+fn message(rule: &AlertRule, group_key: &str, value: f64) -> String {
+    let scope = match rule.scope {
+        AlertScope::Global => String::new(),
+        scope => format!(" [{} {}]", scope.as_str(), group_key),
+    };
+    let error_type = rule.error_type.as_deref().map(|t| format!(" ({})", t)).unwrap_or_default();
+    format!(
+        "{}{}: {}{} {} >= {} over {}",
+        rule.name,
+        scope,
+        rule.metric.as_str(),
+        error_type,
+        rule.metric.format(value),
+        rule.metric.format(rule.threshold),
+        rule.window.label()
+    )
+}
+
+async fn run_command(command: &str, firing: &AlertFiring) -> Result<()> {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = tokio::process::Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+    } else {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c");
+        cmd
+    };
+    cmd.arg(command)
+        .env("SWIFTCAST_ALERT_RULE", &firing.rule_name)
+        .env("SWIFTCAST_ALERT_METRIC", &firing.metric)
+        .env("SWIFTCAST_ALERT_GROUP", &firing.group_key)
+        .env("SWIFTCAST_ALERT_VALUE", firing.value.to_string())
+        .env("SWIFTCAST_ALERT_THRESHOLD", firing.threshold.to_string())
+        .env("SWIFTCAST_ALERT_MESSAGE", &firing.message)
+        .env("SWIFTCAST_ALERT_JSON", serde_json::to_string(firing)?)
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true);
+
+    let output = tokio::time::timeout(COMMAND_TIMEOUT, cmd.output())
+        .await
+        .map_err(|_| anyhow::anyhow!("`{}` timed out after {}s", command, COMMAND_TIMEOUT.as_secs()))??;
+    if !output.status.success() {
+        anyhow::bail!("`{}` exited with {}: {}", command, output.status, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(metric: AlertMetric) -> AlertRule {
+        AlertRule {
+            id: "r1".to_string(),
+            name: "Auth errors".to_string(),
+            enabled: true,
+            metric,
+            threshold: 3.0,
+            window: AlertWindow::Minutes(60),
+            scope: AlertScope::Account,
+            account_id: None,
+            model: None,
+            error_type: Some("authentication_error".to_string()),
+            min_requests: None,
+            channels: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_affected_by() {
+        let trigger = AlertTrigger {
+            account_id: "a1".to_string(),
+            model: "glm-4.6".to_string(),
+            session_id: None,
+            is_error: false,
+        };
+        assert!(!affected_by(&rule(AlertMetric::Errors), &trigger));
+        assert!(affected_by(&rule(AlertMetric::ErrorRate), &trigger));
+        assert!(affected_by(&rule(AlertMetric::CostUsd), &trigger));
+
+        let other_account = AlertRule { account_id: Some("a2".to_string()), ..rule(AlertMetric::CostUsd) };
+        assert!(!affected_by(&other_account, &trigger));
+        assert!(affected_by(&rule(AlertMetric::Errors), &AlertTrigger { is_error: true, ..trigger }));
+    }
+
+    #[test]
+    fn test_windows_and_message() {
+        let now = 1_790_000_000;
+        assert_eq!(window_start(AlertWindow::Minutes(10), now), now - 600);
+        assert_eq!(window_key(AlertWindow::Minutes(10), now), (String::new(), now - 600));
+        assert_eq!(window_key(AlertWindow::Total, now), ("total".to_string(), 0));
+        let day_start = window_start(AlertWindow::Day, now);
+        assert!(day_start <= now && now - day_start < 25 * 60 * 60);
+
+        assert_eq!(
+            message(&rule(AlertMetric::Errors), "a1", 3.0),
+            "Auth errors [account a1]: errors (authentication_error) 3 >= 3 over 1h"
+        );
+        let rate = AlertRule { threshold: 0.2, window: AlertWindow::Minutes(10), scope: AlertScope::Global, error_type: None, ..rule(AlertMetric::ErrorRate) };
+        assert_eq!(message(&rate, "*", 0.25), "Auth errors: error_rate 25.0% >= 20.0% over 10m");
+    }
+
+    #[test]
+    fn test_parse_rule_parts() {
+        assert_eq!(AlertWindow::parse("10m"), Some(AlertWindow::Minutes(10)));
+        assert_eq!(AlertWindow::parse("2h"), Some(AlertWindow::Minutes(120)));
+        assert_eq!(AlertWindow::parse("day"), Some(AlertWindow::Day));
+        assert_eq!(AlertWindow::parse("0m"), None);
+        assert_eq!(AlertWindow::parse(""), None);
+        assert_eq!(
+            AlertChannel::parse("webhook:https://hooks.example.com/x"),
+            Some(AlertChannel::Webhook { url: "https://hooks.example.com/x".to_string() })
+        );
+        assert_eq!(
+            AlertChannel::parse("command:notify-send \"$SWIFTCAST_ALERT_MESSAGE\""),
+            Some(AlertChannel::Command { command: "notify-send \"$SWIFTCAST_ALERT_MESSAGE\"".to_string() })
+        );
+        assert_eq!(AlertChannel::parse("webhook:ftp://x"), None);
+
+        let json = r#"{"name": "Daily cost", "metric": "cost_usd", "threshold": 50, "window": "day", "channels": [{"type": "event"}]}"#;
+        let parsed: AlertRule = serde_json::from_str(json).unwrap();
+        assert!(parsed.enabled);
+        assert_eq!(parsed.scope, AlertScope::Global);
+        assert_eq!(serde_json::to_value(AlertWindow::Minutes(10)).unwrap(), serde_json::json!({"minutes": 10}));
+    }
+}
@@ -0,0 +1,67 @@
+use serde_json::Value;
+
+/// Anthropic error type and message from an error body (`{"type":"error","error":{"type":..,"message":..}}`);
+/// non-JSON bodies become `unknown_error` with the raw text
+pub fn parse_error_body(body: &[u8]) -> (String, String) {
+    let text = String::from_utf8_lossy(body);
+    match serde_json::from_slice::<Value>(body) {
+        Ok(json) => error_fields(&json, &text),
+        Err(_) => ("unknown_error".to_string(), text.to_string()),
+    }
+}
+
+/// Error event inside an SSE stream (e.g. `overloaded_error` after the response started)
+pub fn parse_error_from_sse(data: &str) -> Option<(String, String)> {
+    data.lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .filter_map(|json_str| serde_json::from_str::<Value>(json_str).ok())
+        .find(|json| json.get("type").and_then(|t| t.as_str()) == Some("error"))
+        .map(|json| error_fields(&json, ""))
+}
+
+fn error_fields(json: &Value, fallback_message: &str) -> (String, String) {
+    let error = json.get("error");
+    let error_type = error
+        .and_then(|e| e.get("type"))
+        .and_then(|t| t.as_str())
+        .unwrap_or("unknown_error");
+    let message = error
+        .and_then(|e| e.get("message"))
+        .and_then(|m| m.as_str())
+        .unwrap_or(fallback_message);
+    (error_type.to_string(), message.to_string())
+}
+
+/// `retry-after` header value in seconds (delay-seconds or HTTP date)
+pub fn retry_after_secs(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<f64>() {
+        return Some(secs.ceil() as i64);
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.timestamp() - chrono::Utc::now().timestamp()).max(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_errors() {
+        let body = br#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        assert_eq!(parse_error_body(body), ("overloaded_error".to_string(), "Overloaded".to_string()));
+        assert_eq!(parse_error_body(b"<html>502 Bad Gateway</html>").0, "unknown_error");
+
+        let sse = "event: ping\ndata: {\"type\":\"ping\"}\n\nevent: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"api_error\",\"message\":\"Internal\"}}\n\n";
+        assert_eq!(parse_error_from_sse(sse), Some(("api_error".to_string(), "Internal".to_string())));
+        assert_eq!(parse_error_from_sse("data: {\"type\":\"message_stop\"}\n"), None);
+    }
+
+    #[test]
+    fn test_retry_after() {
+        assert_eq!(retry_after_secs("30"), Some(30));
+        assert_eq!(retry_after_secs(" 1.5 "), Some(2));
+        assert_eq!(retry_after_secs("Wed, 21 Oct 2015 07:28:00 GMT"), Some(0));
+        assert_eq!(retry_after_secs("soon"), None);
+    }
+}
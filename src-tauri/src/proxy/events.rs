@@ -0,0 +1,173 @@
+use super::webhook::StepUpdateData;
+use crate::models::SessionState;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// Buffered events per subscriber before it starts lagging
+const EVENT_BUS_CAPACITY: usize = 1024;
+
+/// Live proxy activity, published on `/_swiftcast/events` and to the Tauri frontend (`proxy-event`)
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyEvent {
+    /// Unix epoch milliseconds
+    pub timestamp: i64,
+    pub request_id: Option<String>,
+    pub session_id: Option<String>,
+    #[serde(flatten)]
+    pub kind: ProxyEventKind,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProxyEventKind {
+    RequestStarted {
+        session_source: Option<String>,
+        request_kind: String,
+        account: String,
+        model: String,
+        method: String,
+        path: String,
+    },
+    FirstToken {
+        elapsed_ms: u64,
+    },
+    ToolUse {
+        tool_name: String,
+        input: Option<serde_json::Value>,
+    },
+    QuestionDetected {
+        question: String,
+        options: Vec<String>,
+        /// Matching rule name (`AskUserQuestion` for structured questions)
+        rule: String,
+    },
+    StepChanged {
+        step: StepUpdateData,
+    },
+    /// A new subagent session was linked to its parent (`session_id` is the child)
+    SubagentDetected {
+        parent_session_id: String,
+        tool_use_id: Option<String>,
+        detection: String,
+    },
+    /// The session moved to another lifecycle state
+    SessionStateChanged {
+        state: SessionState,
+        previous: Option<SessionState>,
+        turn_count: i64,
+    },
+    UsageLogged {
+        account: String,
+        model: String,
+        input_tokens: i64,
+        output_tokens: i64,
+        cost_usd: f64,
+        stop_reason: Option<String>,
+    },
+    Error {
+        account: String,
+        status_code: u16,
+        message: String,
+    },
+    /// An alert rule reached its threshold
+    AlertFired {
+        rule_id: String,
+        rule_name: String,
+        group_key: String,
+        value: f64,
+        threshold: f64,
+        message: String,
+    },
+}
+
+impl ProxyEvent {
+    pub fn new(request_id: Option<String>, session_id: Option<String>, kind: ProxyEventKind) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            request_id,
+            session_id,
+            kind,
+        }
+    }
+
+    /// SSE event name (same as the `type` field)
+    pub fn event_name(&self) -> &'static str {
+        match self.kind {
+            ProxyEventKind::RequestStarted { .. } => "request_started",
+            ProxyEventKind::FirstToken { .. } => "first_token",
+            ProxyEventKind::ToolUse { .. } => "tool_use",
+            ProxyEventKind::QuestionDetected { .. } => "question_detected",
+            ProxyEventKind::StepChanged { .. } => "step_changed",
+            ProxyEventKind::SubagentDetected { .. } => "subagent_detected",
+            ProxyEventKind::SessionStateChanged { .. } => "session_state_changed",
+            ProxyEventKind::UsageLogged { .. } => "usage_logged",
+            ProxyEventKind::Error { .. } => "error",
+            ProxyEventKind::AlertFired { .. } => "alert_fired",
+        }
+    }
+}
+
+/// Fan-out channel for `ProxyEvent`s; publishing never blocks the proxy
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<ProxyEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (tx, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        Self { tx }
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn publish(&self, event: ProxyEvent) {
+        // 구독자가 없으면 send가 실패하지만 무시
+        let _ = self.tx.send(event);
+    }
+
+    pub fn emit(&self, request_id: Option<&str>, session_id: Option<&str>, kind: ProxyEventKind) {
+        if self.tx.receiver_count() == 0 {
+            return;
+        }
+        self.publish(ProxyEvent::new(
+            request_id.map(str::to_string),
+            session_id.map(str::to_string),
+            kind,
+        ));
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ProxyEvent> {
+        self.tx.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_publish_and_serialize() {
+        let bus = EventBus::new();
+        let mut rx = bus.subscribe();
+        bus.emit(Some("req-1"), Some("sess-1"), ProxyEventKind::FirstToken { elapsed_ms: 42 });
+
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.event_name(), "first_token");
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "first_token");
+        assert_eq!(json["elapsed_ms"], 42);
+        assert_eq!(json["session_id"], "sess-1");
+    }
+
+    #[test]
+    fn test_emit_without_subscribers_is_noop() {
+        let bus = EventBus::new();
+        bus.emit(None, None, ProxyEventKind::FirstToken { elapsed_ms: 1 });
+    }
+}
@@ -97,7 +97,7 @@ impl CompactionInjectorHook {
     }
 
     /// Check if this is a compaction summarization request
-    fn is_compaction_request(body: &str) -> bool {
+    pub(crate) fn is_compaction_request(body: &str) -> bool {
         body.contains("Your task is to create a detailed summary of the conversation")
     }
 
@@ -1,5 +1,6 @@
+use crate::proxy::request_kind::RequestKind;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
 
 /// Context for an incoming request
@@ -7,8 +8,12 @@ use tokio::sync::RwLock;
 pub struct RequestContext {
     /// Unique request identifier
     pub request_id: String,
-    /// Session ID (from sentry-trace or x-session-id header)
+    /// Session ID (resolved by the session resolver chain)
     pub session_id: Option<String>,
+    /// Resolver that produced `session_id` (e.g. `metadata_user_id`, `header`)
+    pub session_source: Option<String>,
+    /// What the request is for (main turn, subagent, title generation, ...)
+    pub request_kind: RequestKind,
     /// Model being used
     pub model: String,
     /// HTTP method
@@ -35,6 +40,8 @@ impl RequestContext {
         Self {
             request_id: uuid::Uuid::new_v4().to_string(),
             session_id,
+            session_source: None,
+            request_kind: RequestKind::default(),
             model,
             method,
             path,
@@ -44,6 +51,16 @@ impl RequestContext {
         }
     }
 
+    pub fn with_session_source(mut self, source: Option<String>) -> Self {
+        self.session_source = source;
+        self
+    }
+
+    pub fn with_request_kind(mut self, kind: RequestKind) -> Self {
+        self.request_kind = kind;
+        self
+    }
+
     /// Get short session ID (first 16 chars) for directory naming
     pub fn short_session_id(&self) -> Option<String> {
         self.session_id.as_ref().map(|s| {
@@ -69,6 +86,8 @@ pub struct ResponseContext {
     pub error_message: Option<String>,
     /// Full response text (accumulated from streaming)
     pub response_text: String,
+    /// Response content in stream order (text, thinking, tool_use with assembled input)
+    pub content_blocks: Vec<ContentBlock>,
     /// Response timestamp (Unix epoch seconds)
     pub timestamp: i64,
     /// Stop reason (end_turn, tool_use, max_tokens, etc.)
@@ -79,7 +98,7 @@ impl ResponseContext {
     pub fn new(status_code: u16) -> Self {
         Self {
             status_code,
-            is_success: status_code >= 200 && status_code < 300,
+            is_success: (200..300).contains(&status_code),
             timestamp: chrono::Utc::now().timestamp(),
             ..Default::default()
         }
@@ -103,10 +122,134 @@ impl ResponseContext {
     }
 }
 
+/// A content block of the response, in the Messages API format
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentBlock {
+    Text {
+        text: String,
+    },
+    Thinking {
+        thinking: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        signature: Option<String>,
+    },
+    RedactedThinking {
+        data: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+}
+
 /// Builder for accumulating response data during streaming
 #[derive(Debug, Clone)]
 pub struct ResponseBuilder {
     inner: Arc<RwLock<ResponseBuilderInner>>,
+    /// Fed synchronously from the stream so deltas are applied in order
+    blocks: Arc<Mutex<ContentBlockCollector>>,
+}
+
+/// Assembles content blocks from SSE events (`content_block_start` / `_delta` / `_stop`)
+#[derive(Debug, Default)]
+struct ContentBlockCollector {
+    /// Incomplete line carried over from the previous chunk (may end mid UTF-8 character)
+    partial_line: Vec<u8>,
+    /// (stream index, block, raw `input_json_delta` of a tool_use)
+    blocks: Vec<(u64, ContentBlock, String)>,
+    /// tool_use blocks whose input is complete (content_block_stop seen), not yet taken
+    finished_tool_uses: Vec<ContentBlock>,
+}
+
+impl ContentBlockCollector {
+    fn feed(&mut self, chunk: &[u8]) {
+        self.partial_line.extend_from_slice(chunk);
+        let Some(end) = self.partial_line.iter().rposition(|b| *b == b'\n') else {
+            return;
+        };
+        let complete: Vec<u8> = self.partial_line.drain(..=end).collect();
+        for line in String::from_utf8_lossy(&complete).lines() {
+            if let Some(data) = line.strip_prefix("data:") {
+                if let Ok(event) = serde_json::from_str::<serde_json::Value>(data.trim_start()) {
+                    self.apply(&event);
+                }
+            }
+        }
+    }
+
+    fn apply(&mut self, event: &serde_json::Value) {
+        let Some(index) = event.get("index").and_then(|i| i.as_u64()) else {
+            return;
+        };
+        let str_field = |v: &serde_json::Value, name: &str| v.get(name).and_then(|s| s.as_str()).unwrap_or_default().to_string();
+
+        match event.get("type").and_then(|t| t.as_str()) {
+            Some("content_block_start") => {
+                let Some(block) = event.get("content_block") else { return };
+                let block = match block.get("type").and_then(|t| t.as_str()) {
+                    Some("text") => ContentBlock::Text { text: str_field(block, "text") },
+                    Some("thinking") => ContentBlock::Thinking {
+                        thinking: str_field(block, "thinking"),
+                        signature: block.get("signature").and_then(|s| s.as_str()).filter(|s| !s.is_empty()).map(str::to_string),
+                    },
+                    Some("redacted_thinking") => ContentBlock::RedactedThinking { data: str_field(block, "data") },
+                    Some("tool_use") => ContentBlock::ToolUse {
+                        id: str_field(block, "id"),
+                        name: str_field(block, "name"),
+                        input: block.get("input").cloned().unwrap_or_else(|| serde_json::json!({})),
+                    },
+                    _ => return,
+                };
+                self.blocks.push((index, block, String::new()));
+            }
+            Some("content_block_delta") => {
+                let Some(delta) = event.get("delta") else { return };
+                let Some((_, block, input_json)) = self.blocks.iter_mut().rev().find(|(i, _, _)| *i == index) else {
+                    return;
+                };
+                match (block, delta.get("type").and_then(|t| t.as_str())) {
+                    (ContentBlock::Text { text }, Some("text_delta")) => text.push_str(&str_field(delta, "text")),
+                    (ContentBlock::Thinking { thinking, .. }, Some("thinking_delta")) => {
+                        thinking.push_str(&str_field(delta, "thinking"))
+                    }
+                    (ContentBlock::Thinking { signature, .. }, Some("signature_delta")) => {
+                        signature.get_or_insert_with(String::new).push_str(&str_field(delta, "signature"))
+                    }
+                    (ContentBlock::ToolUse { .. }, Some("input_json_delta")) => {
+                        input_json.push_str(&str_field(delta, "partial_json"))
+                    }
+                    _ => {}
+                }
+            }
+            Some("content_block_stop") => {
+                if let Some(entry) = self.blocks.iter().rev().find(|(i, _, _)| *i == index) {
+                    if matches!(entry.1, ContentBlock::ToolUse { .. }) {
+                        self.finished_tool_uses.push(Self::parse_input(entry));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn parse_input((_, block, input_json): &(u64, ContentBlock, String)) -> ContentBlock {
+        match block {
+            ContentBlock::ToolUse { id, name, .. } if !input_json.is_empty() => ContentBlock::ToolUse {
+                id: id.clone(),
+                name: name.clone(),
+                input: serde_json::from_str(input_json)
+                    .unwrap_or_else(|_| serde_json::Value::String(input_json.clone())),
+            },
+            _ => block.clone(),
+        }
+    }
+
+    /// Blocks with tool inputs parsed (inputs cut off mid-stream are kept as the raw string)
+    fn build(&self) -> Vec<ContentBlock> {
+        self.blocks.iter().map(Self::parse_input).collect()
+    }
 }
 
 #[derive(Debug, Default)]
@@ -128,10 +271,26 @@ impl ResponseBuilder {
                 start_time: Some(std::time::Instant::now()),
                 ..Default::default()
             })),
+            blocks: Arc::default(),
         }
     }
 
-    pub async fn append_text(&self, text: &str) {
+    /// Feed a raw SSE chunk; events split across chunks are handled
+    pub fn append_sse(&self, chunk: &[u8]) {
+        if let Ok(mut blocks) = self.blocks.lock() {
+            blocks.feed(chunk);
+        }
+    }
+
+    /// tool_use blocks completed since the last call, with their full input
+    pub fn take_finished_tool_uses(&self) -> Vec<ContentBlock> {
+        self.blocks
+            .lock()
+            .map(|mut blocks| std::mem::take(&mut blocks.finished_tool_uses))
+            .unwrap_or_default()
+    }
+
+        pub async fn append_text(&self, text: &str) {
         let mut inner = self.inner.write().await;
         inner.response_text.push_str(text);
     }
@@ -157,6 +316,17 @@ impl ResponseBuilder {
         let duration_ms = inner.start_time
             .map(|s| s.elapsed().as_millis() as u64)
             .unwrap_or(0);
+        let content_blocks = self.blocks.lock().map(|b| b.build()).unwrap_or_default();
+
+        // SSE로 받은 경우 블록의 텍스트가 기준 (append_text는 SSE가 아닌 응답용)
+        let streamed_text: String = content_blocks
+            .iter()
+            .filter_map(|b| match b {
+                ContentBlock::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        let response_text = if streamed_text.is_empty() { inner.response_text.clone() } else { streamed_text };
 
         ResponseContext {
             status_code: inner.status_code,
@@ -165,7 +335,8 @@ impl ResponseBuilder {
             output_tokens: inner.output_tokens,
             is_success: inner.status_code >= 200 && inner.status_code < 300 && inner.error_message.is_none(),
             error_message: inner.error_message.clone(),
-            response_text: inner.response_text.clone(),
+            response_text,
+            content_blocks,
             timestamp: chrono::Utc::now().timestamp(),
             stop_reason: inner.stop_reason.clone(),
         }
@@ -188,6 +359,8 @@ pub struct RequestLogData {
     pub model: String,
     pub method: String,
     pub path: String,
+    #[serde(default)]
+    pub request_kind: RequestKind,
     pub body: serde_json::Value,
 }
 
@@ -201,6 +374,8 @@ pub struct ResponseLogData {
     pub is_success: bool,
     pub error_message: Option<String>,
     pub response_text: String,
+    #[serde(default)]
+    pub content_blocks: Vec<ContentBlock>,
     pub stop_reason: Option<String>,
 }
 
@@ -215,6 +390,7 @@ impl HookLogEntry {
                 model: req.model.clone(),
                 method: req.method.clone(),
                 path: req.path.clone(),
+                request_kind: req.request_kind,
                 body: req.body.clone(),
             },
             response: ResponseLogData {
@@ -226,8 +402,59 @@ impl HookLogEntry {
                 is_success: res.is_success,
                 error_message: res.error_message.clone(),
                 response_text: res.response_text.clone(),
+                content_blocks: res.content_blocks.clone(),
                 stop_reason: res.stop_reason.clone(),
             },
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_content_blocks_from_split_chunks() {
+        let events = [
+            json!({"type": "content_block_start", "index": 0, "content_block": {"type": "thinking", "thinking": ""}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "thinking_delta", "thinking": "Read the "}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "thinking_delta", "thinking": "file first"}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "signature_delta", "signature": "sig=="}}),
+            json!({"type": "content_block_stop", "index": 0}),
+            json!({"type": "content_block_start", "index": 1, "content_block": {"type": "text", "text": ""}}),
+            json!({"type": "content_block_delta", "index": 1, "delta": {"type": "text_delta", "text": "Reading. 읽는 중"}}),
+            json!({"type": "content_block_start", "index": 2, "content_block": {"type": "tool_use", "id": "toolu_1", "name": "Read", "input": {}}}),
+            json!({"type": "content_block_delta", "index": 2, "delta": {"type": "input_json_delta", "partial_json": "{\"file_path\": \"/a"}}),
+            json!({"type": "content_block_delta", "index": 2, "delta": {"type": "input_json_delta", "partial_json": ".rs\"}"}}),
+            json!({"type": "content_block_stop", "index": 2}),
+        ];
+        let stream: String = events
+            .iter()
+            .map(|e| format!("event: {}\ndata: {}\n\n", e["type"].as_str().unwrap(), e))
+            .collect();
+
+        // 이벤트와 UTF-8 문자 중간에서 잘린 청크
+        let builder = ResponseBuilder::new(200);
+        for chunk in stream.as_bytes().chunks(37) {
+            builder.append_sse(chunk);
+        }
+
+        assert_eq!(builder.take_finished_tool_uses(), vec![
+            ContentBlock::ToolUse { id: "toolu_1".to_string(), name: "Read".to_string(), input: json!({"file_path": "/a.rs"}) },
+        ]);
+        assert!(builder.take_finished_tool_uses().is_empty());
+
+        let res = builder.build().await;
+        assert_eq!(res.response_text, "Reading. 읽는 중");
+        assert_eq!(res.content_blocks, vec![
+            ContentBlock::Thinking { thinking: "Read the file first".to_string(), signature: Some("sig==".to_string()) },
+            ContentBlock::Text { text: "Reading. 읽는 중".to_string() },
+            ContentBlock::ToolUse { id: "toolu_1".to_string(), name: "Read".to_string(), input: json!({"file_path": "/a.rs"}) },
+        ]);
+        assert_eq!(
+            serde_json::to_value(&res.content_blocks[2]).unwrap(),
+            json!({"type": "tool_use", "id": "toolu_1", "name": "Read", "input": {"file_path": "/a.rs"}})
+        );
+    }
+}
@@ -1,11 +1,19 @@
-use async_trait::async_trait;
+use bytes::Bytes;
+use futures::future::{join, join_all};
+use futures::Stream;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::convert::Infallible;
 use std::path::PathBuf;
-use std::process::Command;
-use tokio::sync::RwLock;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::{mpsc, RwLock};
 
 use super::context::RequestContext;
+use super::task_params::{self, TaskParam};
 
 /// Task definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +36,48 @@ pub struct TaskDefinition {
     pub file_path: Option<String>,
     /// Environment variables to set
     pub env: Option<HashMap<String, String>>,
+    /// Steps to run (for Composite type)
+    pub steps: Option<Vec<TaskStep>>,
+    /// Run all steps at once instead of in order (Composite type)
+    pub parallel: Option<bool>,
+    /// Keep running later steps after one fails (sequential Composite type)
+    pub continue_on_failure: Option<bool>,
+    /// Stop the task after this many seconds (Shell and Http default 600; Composite unlimited)
+    pub timeout_secs: Option<u64>,
+    /// Parameter schema; without one the task takes free-form `{args}`
+    pub params: Option<Vec<TaskParam>>,
+}
+
+/// Timeout for Shell and Http tasks without `timeout_secs`
+pub const DEFAULT_TIMEOUT_SECS: u64 = 600;
+
+/// Composite task step: another task by name, or an inline shell/http/read_file task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskStep {
+    /// Name for `{steps.<name>.output}`; defaults to the referenced task or `step<N>`
+    pub name: Option<String>,
+    /// Task to run
+    pub task: Option<String>,
+    /// Arguments for the step (placeholders allowed; defaults to the composite's args)
+    pub args: Option<String>,
+    /// Inline task type (instead of `task`)
+    pub task_type: Option<TaskType>,
+    pub command: Option<String>,
+    pub working_dir: Option<String>,
+    pub url: Option<String>,
+    pub http_method: Option<String>,
+    pub file_path: Option<String>,
+    pub env: Option<HashMap<String, String>>,
+    pub timeout_secs: Option<u64>,
+}
+
+impl TaskStep {
+    fn display_name(&self, index: usize) -> String {
+        self.name
+            .clone()
+            .or_else(|| self.task.clone())
+            .unwrap_or_else(|| format!("step{}", index + 1))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -50,6 +100,143 @@ pub struct TaskContext {
     pub model: String,
     /// Arguments passed to the task
     pub args: String,
+    /// Parameter values parsed from the arguments (tasks with a schema)
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub params: HashMap<String, String>,
+    /// Results of earlier composite steps by step name
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub steps: HashMap<String, StepOutput>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepOutput {
+    /// `ok` or `failed`
+    pub status: String,
+    pub output: String,
+}
+
+impl TaskContext {
+    /// Replace `{args}`, `{session_id}`, `{path}`, `{model}`, `{params.<name>}` and `{steps.<name>.output|status}`
+    fn expand(&self, template: &str) -> String {
+        let mut text = template
+            .replace("{args}", &self.args)
+            .replace("{session_id}", self.session_id.as_deref().unwrap_or(""))
+            .replace("{path}", &self.path)
+            .replace("{model}", &self.model);
+        for (name, value) in &self.params {
+            text = text.replace(&format!("{{params.{}}}", name), value);
+        }
+        for (name, step) in &self.steps {
+            text = text
+                .replace(&format!("{{steps.{}.output}}", name), &step.output)
+                .replace(&format!("{{steps.{}.status}}", name), &step.status);
+        }
+        text
+    }
+}
+
+/// Outcome of running a task
+#[derive(Debug, Clone)]
+pub struct TaskOutput {
+    /// Command exited with 0 / HTTP status was 2xx / file was read
+    pub success: bool,
+    /// Raw output, available to later composite steps
+    pub output: String,
+}
+
+impl TaskOutput {
+    fn failed(output: String) -> Self {
+        Self { success: false, output }
+    }
+}
+
+/// Task output shown in Claude Code, streamed as it is written
+#[derive(Debug, Clone)]
+pub struct TaskLog {
+    tx: Option<mpsc::UnboundedSender<String>>,
+    text: Arc<Mutex<String>>,
+    /// Line prefix of parallel steps (`[name] `); their output is not fenced
+    prefix: Option<String>,
+}
+
+impl TaskLog {
+    pub fn new(tx: Option<mpsc::UnboundedSender<String>>) -> Self {
+        Self { tx, text: Arc::new(Mutex::new(String::new())), prefix: None }
+    }
+
+    /// Log of a parallel step, prefixing each line with `[name] `
+    fn prefixed(&self, name: &str) -> Self {
+        Self {
+            prefix: Some(format!("{}[{}] ", self.prefix.as_deref().unwrap_or(""), name)),
+            ..self.clone()
+        }
+    }
+
+    fn write(&self, text: &str) {
+        let text = match &self.prefix {
+            Some(prefix) => text.lines().map(|line| format!("{}{}\n", prefix, line)).collect(),
+            None => text.to_string(),
+        };
+        self.text.lock().unwrap_or_else(|e| e.into_inner()).push_str(&text);
+        if let Some(ref tx) = self.tx {
+            let _ = tx.send(text);
+        }
+    }
+
+    fn line(&self, line: &str) {
+        self.write(&format!("{}\n", line));
+    }
+
+    /// Code fence around output (skipped in prefixed logs)
+    fn fence(&self) {
+        if self.prefix.is_none() {
+            self.write("```\n");
+        }
+    }
+
+    /// Everything written so far
+    pub fn text(&self) -> String {
+        self.text.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Kills a shell task's process group when the task is cancelled or times out
+struct ProcessGroupGuard(Option<u32>);
+
+impl ProcessGroupGuard {
+    fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(pid) = self.0 {
+            let _ = std::process::Command::new("kill").arg("-KILL").arg(format!("-{}", pid)).status();
+        }
+    }
+}
+
+/// Composite step result (None when skipped after an earlier failure)
+struct StepResult {
+    name: String,
+    elapsed: Duration,
+    output: Option<TaskOutput>,
+}
+
+impl StepResult {
+    fn summary(&self) -> String {
+        match &self.output {
+            Some(output) => format!(
+                "{}: {} ({:.1}s)",
+                self.name,
+                if output.success { "ok" } else { "failed" },
+                self.elapsed.as_secs_f64()
+            ),
+            None => format!("{}: skipped", self.name),
+        }
+    }
 }
 
 /// Result of task interception
@@ -61,6 +248,15 @@ pub struct InterceptResult {
     pub response_text: String,
     /// Task name that was executed
     pub task_name: Option<String>,
+    /// Task to run while streaming its output after `response_text` (see `stream_task`)
+    pub pending: Option<PendingTask>,
+}
+
+/// Task selected by `try_intercept`
+#[derive(Debug, Clone)]
+pub struct PendingTask {
+    task: TaskDefinition,
+    ctx: TaskContext,
 }
 
 /// Custom Task Hook for intercepting >>swiftcast commands
@@ -86,7 +282,17 @@ impl CustomTaskHook {
     fn load_tasks(path: &PathBuf) -> Option<HashMap<String, TaskDefinition>> {
         let content = std::fs::read_to_string(path).ok()?;
         let tasks: Vec<TaskDefinition> = serde_json::from_str(&content).ok()?;
-        let map = tasks.into_iter().map(|t| (t.name.clone(), t)).collect();
+        let map = tasks
+            .into_iter()
+            .filter(|t| match task_params::validate_params(t.params.as_deref().unwrap_or_default()) {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::warn!("Skipping task '{}': {}", t.name, e);
+                    false
+                }
+            })
+            .map(|t| (t.name.clone(), t))
+            .collect();
         Some(map)
     }
 
@@ -140,8 +346,7 @@ impl CustomTaskHook {
         let trimmed = message.trim_start();
         let pattern = ">>swiftcast ";
 
-        if trimmed.starts_with(pattern) {
-            let after = &trimmed[pattern.len()..];
+        if let Some(after) = trimmed.strip_prefix(pattern) {
             // Extract task name (first word)
             let task_name = after.split_whitespace().next()?;
             // Extract remaining args
@@ -151,39 +356,173 @@ impl CustomTaskHook {
         None
     }
 
-    /// Execute a task and return the result
-    async fn execute_task(&self, task: &TaskDefinition, task_ctx: &TaskContext) -> Result<String, String> {
+    /// Execute a task, writing its output to `log`
+    /// `chain` holds the composite tasks being run, to reject cycles
+    async fn execute_task(&self, task: &TaskDefinition, task_ctx: &TaskContext, chain: &[String], log: &TaskLog) -> Result<TaskOutput, String> {
         match task.task_type {
             TaskType::Shell => {
-                self.execute_shell_task(task, task_ctx).await
+                self.execute_shell_task(task, task_ctx, log).await
             }
             TaskType::Http => {
-                self.execute_http_task(task, task_ctx).await
+                self.execute_http_task(task, task_ctx, log).await
             }
             TaskType::ReadFile => {
-                self.execute_read_file_task(task, task_ctx).await
+                self.execute_read_file_task(task, task_ctx, log).await
             }
             TaskType::Composite => {
-                Ok("Composite tasks not yet implemented".to_string())
+                self.execute_composite_task(task, task_ctx, chain, log).await
+            }
+        }
+    }
+
+    async fn execute_composite_task(&self, task: &TaskDefinition, task_ctx: &TaskContext, chain: &[String], log: &TaskLog) -> Result<TaskOutput, String> {
+        let steps = task.steps.as_ref()
+            .filter(|steps| !steps.is_empty())
+            .ok_or_else(|| "No steps specified".to_string())?;
+        let mut chain = chain.to_vec();
+        chain.push(task.name.clone());
+        let names: Vec<String> = steps.iter().enumerate().map(|(i, step)| step.display_name(i)).collect();
+
+        let run = async {
+            if task.parallel.unwrap_or(false) {
+                // 병렬 실행: 출력은 단계 이름을 붙여 섞어서 표시, 단계끼리 출력을 주고받을 수 없음
+                log.fence();
+                let results = join_all(
+                    steps.iter().zip(&names).map(|(step, name)| self.run_step(step, name, task_ctx, &chain, log.prefixed(name))),
+                )
+                .await;
+                log.fence();
+                for result in &results {
+                    log.line(&format!("- {}", result.summary()));
+                }
+                results
+            } else {
+                let continue_on_failure = task.continue_on_failure.unwrap_or(false);
+                let mut ctx = task_ctx.clone();
+                let mut results = Vec::with_capacity(steps.len());
+                let mut failed = false;
+                for (step, name) in steps.iter().zip(&names) {
+                    if failed && !continue_on_failure {
+                        let result = StepResult { name: name.clone(), elapsed: Duration::ZERO, output: None };
+                        log.line(&format!("### {}\n", result.summary()));
+                        results.push(result);
+                        continue;
+                    }
+                    log.line(&format!("### {}\n", name));
+                    let result = self.run_step(step, name, &ctx, &chain, log.clone()).await;
+                    log.line(&format!("\n{}\n", result.summary()));
+                    if let Some(output) = &result.output {
+                        failed |= !output.success;
+                        ctx.steps.insert(name.clone(), StepOutput {
+                            status: if output.success { "ok" } else { "failed" }.to_string(),
+                            output: output.output.clone(),
+                        });
+                    }
+                    results.push(result);
+                }
+                results
+            }
+        };
+        let results = match task.timeout_secs {
+            Some(secs) => match tokio::time::timeout(Duration::from_secs(secs), run).await {
+                Ok(results) => results,
+                Err(_) => {
+                    log.line(&format!("\nTimed out after {}s", secs));
+                    return Ok(TaskOutput::failed(String::new()));
+                }
+            },
+            None => run.await,
+        };
+
+        let succeeded = results.iter().filter(|r| r.output.as_ref().is_some_and(|o| o.success)).count();
+        log.line(&format!("\n**{}/{} steps succeeded**", succeeded, results.len()));
+
+        let output = results
+            .iter()
+            .filter_map(|r| r.output.as_ref().map(|o| o.output.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(TaskOutput { success: succeeded == results.len(), output })
+    }
+
+    async fn run_step(&self, step: &TaskStep, name: &str, task_ctx: &TaskContext, chain: &[String], log: TaskLog) -> StepResult {
+        let started = Instant::now();
+        let mut step_ctx = task_ctx.clone();
+        if let Some(ref args) = step.args {
+            step_ctx.args = task_ctx.expand(args);
+        }
+        let output = match self.resolve_step(step, name, chain).await {
+            Ok(task) => match task.params.as_deref().map(|params| task_params::parse_args(params, &step_ctx.args)) {
+                Some(Err(e)) => Err(format!("{}\n\n{}", e, task_params::usage(&task.name, task.params.as_deref().unwrap_or_default()))),
+                parsed => {
+                    if let Some(Ok(params)) = parsed {
+                        step_ctx.params = params;
+                    }
+                    // 중첩된 composite 호출을 위해 재귀 future를 박싱
+                    Box::pin(self.execute_task(&task, &step_ctx, chain, &log)).await
+                }
+            },
+            Err(e) => Err(e),
+        };
+        let output = output.unwrap_or_else(|e| {
+            log.line(&format!("Error: {}", e));
+            TaskOutput::failed(e)
+        });
+        StepResult {
+            name: name.to_string(),
+            elapsed: started.elapsed(),
+            output: Some(output),
+        }
+    }
+
+    /// Task a step runs: the referenced task, or the inline definition
+    async fn resolve_step(&self, step: &TaskStep, name: &str, chain: &[String]) -> Result<TaskDefinition, String> {
+        match (&step.task, &step.task_type) {
+            (Some(task_name), None) => {
+                if chain.contains(task_name) {
+                    return Err(format!("Task cycle: {} -> {}", chain.join(" -> "), task_name));
+                }
+                self.tasks.read().await.get(task_name).cloned()
+                    .ok_or_else(|| format!("Unknown task: '{}'", task_name))
             }
+            (None, Some(task_type)) => Ok(TaskDefinition {
+                name: name.to_string(),
+                description: String::new(),
+                task_type: task_type.clone(),
+                command: step.command.clone(),
+                working_dir: step.working_dir.clone(),
+                url: step.url.clone(),
+                http_method: step.http_method.clone(),
+                file_path: step.file_path.clone(),
+                env: step.env.clone(),
+                steps: None,
+                parallel: None,
+                continue_on_failure: None,
+                timeout_secs: step.timeout_secs,
+                params: None,
+            }),
+            _ => Err("Step needs either `task` or `task_type`".to_string()),
         }
     }
 
-    async fn execute_shell_task(&self, task: &TaskDefinition, task_ctx: &TaskContext) -> Result<String, String> {
+    async fn execute_shell_task(&self, task: &TaskDefinition, task_ctx: &TaskContext, log: &TaskLog) -> Result<TaskOutput, String> {
         let command = task.command.as_ref()
             .ok_or_else(|| "No command specified".to_string())?;
 
         // Replace placeholders with context values
-        let command = command
-            .replace("{args}", &task_ctx.args)
-            .replace("{session_id}", task_ctx.session_id.as_deref().unwrap_or(""))
-            .replace("{path}", &task_ctx.path)
-            .replace("{model}", &task_ctx.model);
+        let command = task_ctx.expand(command);
 
         tracing::info!("Executing shell task '{}': {}", task.name, command);
 
         let mut cmd = Command::new("sh");
-        cmd.arg("-c").arg(&command);
+        cmd.arg("-c").arg(&command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        // 취소/타임아웃 시 하위 프로세스까지 종료하도록 별도 프로세스 그룹으로 실행
+        #[cfg(unix)]
+        cmd.process_group(0);
 
         // Set working directory if specified
         if let Some(ref wd) = task.working_dir {
@@ -197,6 +536,9 @@ impl CustomTaskHook {
         cmd.env("SWIFTCAST_PATH", &task_ctx.path);
         cmd.env("SWIFTCAST_MODEL", &task_ctx.model);
         cmd.env("SWIFTCAST_ARGS", &task_ctx.args);
+        for (name, value) in &task_ctx.params {
+            cmd.env(format!("SWIFTCAST_PARAM_{}", name.to_uppercase()), value);
+        }
 
         // Set user-defined environment variables
         if let Some(ref env) = task.env {
@@ -205,68 +547,119 @@ impl CustomTaskHook {
             }
         }
 
-        let output = cmd.output()
+        let mut child = cmd.spawn()
             .map_err(|e| format!("Failed to execute command: {}", e))?;
+        let guard = ProcessGroupGuard(child.id());
+        let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped")).lines();
+        let mut stderr = BufReader::new(child.stderr.take().expect("stderr is piped")).lines();
+        let timeout_secs = task.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS);
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
-
-        if output.status.success() {
-            Ok(format!("```\n{}\n```", stdout.trim()))
-        } else {
-            Ok(format!("Command failed (exit code: {:?}):\n```\n{}\n{}\n```",
-                output.status.code(),
-                stdout.trim(),
-                stderr.trim()
-            ))
+        // stdout/stderr 줄을 도착하는 대로 표시 (단계 출력으로는 stdout만 전달)
+        let mut stdout_text = String::new();
+        let mut all_text = String::new();
+        log.fence();
+        let run = async {
+            let (mut stdout_open, mut stderr_open) = (true, true);
+            while stdout_open || stderr_open {
+                let (line, is_stdout) = tokio::select! {
+                    line = stdout.next_line(), if stdout_open => (line, true),
+                    line = stderr.next_line(), if stderr_open => (line, false),
+                };
+                match line {
+                    Ok(Some(line)) => {
+                        log.line(&line);
+                        if is_stdout {
+                            stdout_text.push_str(&line);
+                            stdout_text.push('\n');
+                        }
+                        all_text.push_str(&line);
+                        all_text.push('\n');
+                    }
+                    _ if is_stdout => stdout_open = false,
+                    _ => stderr_open = false,
+                }
+            }
+            child.wait().await
+        };
+        let status = tokio::time::timeout(Duration::from_secs(timeout_secs), run).await;
+        log.fence();
+
+        match status {
+            Ok(Ok(status)) => {
+                guard.disarm();
+                if status.success() {
+                    Ok(TaskOutput { success: true, output: stdout_text.trim().to_string() })
+                } else {
+                    log.line(&format!(
+                        "Command failed (exit code: {})",
+                        status.code().map(|c| c.to_string()).unwrap_or_else(|| "signal".to_string())
+                    ));
+                    Ok(TaskOutput::failed(all_text.trim().to_string()))
+                }
+            }
+            Ok(Err(e)) => Err(format!("Failed to wait for command: {}", e)),
+            Err(_) => {
+                log.line(&format!("Timed out after {}s", timeout_secs));
+                Ok(TaskOutput::failed(all_text.trim().to_string()))
+            }
         }
     }
 
-    async fn execute_http_task(&self, task: &TaskDefinition, task_ctx: &TaskContext) -> Result<String, String> {
+    async fn execute_http_task(&self, task: &TaskDefinition, task_ctx: &TaskContext, log: &TaskLog) -> Result<TaskOutput, String> {
         let url = task.url.as_ref()
             .ok_or_else(|| "No URL specified".to_string())?;
 
         // Replace placeholders in URL
-        let url = url
-            .replace("{args}", &task_ctx.args)
-            .replace("{session_id}", task_ctx.session_id.as_deref().unwrap_or(""))
-            .replace("{path}", &task_ctx.path)
-            .replace("{model}", &task_ctx.model);
+        let url = task_ctx.expand(url);
 
         let method = task.http_method.as_deref().unwrap_or("GET");
+        let timeout_secs = task.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS);
 
         tracing::info!("Executing HTTP task '{}': {} {}", task.name, method, url);
 
-        let client = reqwest::Client::new();
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(timeout_secs))
+            .build()
+            .map_err(|e| e.to_string())?;
+        let timed_out = |e: reqwest::Error| {
+            if e.is_timeout() {
+                format!("Timed out after {}s", timeout_secs)
+            } else {
+                e.to_string()
+            }
+        };
         let response = match method.to_uppercase().as_str() {
             "GET" => client.get(&url).send().await,
             "POST" => client.post(&url)
                 .json(&task_ctx)
                 .send().await,
             _ => return Err(format!("Unsupported HTTP method: {}", method)),
-        }.map_err(|e| e.to_string())?;
+        }.map_err(timed_out)?;
 
         let status = response.status();
-        let body = response.text().await.map_err(|e| e.to_string())?;
+        let body = response.text().await.map_err(timed_out)?;
 
-        Ok(format!("HTTP {} {}\nStatus: {}\n\n{}", method, url, status, body))
+        log.line(&format!("HTTP {} {}\nStatus: {}\n\n{}", method, url, status, body));
+        Ok(TaskOutput { success: status.is_success(), output: body })
     }
 
-    async fn execute_read_file_task(&self, task: &TaskDefinition, task_ctx: &TaskContext) -> Result<String, String> {
+    async fn execute_read_file_task(&self, task: &TaskDefinition, task_ctx: &TaskContext, log: &TaskLog) -> Result<TaskOutput, String> {
         let path = task.file_path.as_ref()
             .ok_or_else(|| "No file path specified".to_string())?;
 
         // Replace placeholders in path
-        let path = path
-            .replace("{args}", &task_ctx.args)
-            .replace("{session_id}", task_ctx.session_id.as_deref().unwrap_or(""));
+        let path = task_ctx.expand(path);
 
         tracing::info!("Executing read file task '{}': {}", task.name, path);
 
-        let content = std::fs::read_to_string(&path)
+        let content = tokio::fs::read_to_string(&path)
+            .await
             .map_err(|e| format!("Failed to read file: {}", e))?;
 
-        Ok(format!("```\n{}\n```", content))
+        log.fence();
+        log.line(content.trim_end());
+        log.fence();
+        Ok(TaskOutput { success: true, output: content })
     }
 
     /// Try to intercept a request containing >>swiftcast command
@@ -300,6 +693,7 @@ impl CustomTaskHook {
                 intercepted: false,
                 response_text: String::new(),
                 task_name: None,
+                pending: None,
             },
         };
 
@@ -310,6 +704,7 @@ impl CustomTaskHook {
                 intercepted: false,
                 response_text: String::new(),
                 task_name: None,
+                pending: None,
             },
         };
 
@@ -326,8 +721,9 @@ impl CustomTaskHook {
             };
             return InterceptResult {
                 intercepted: true,
-                response_text: format!("## Available Custom Tasks\n\n{}", list),
+                response_text: format!("## Available Custom Tasks\n\n{}\n\nUse `>>swiftcast help <task>` to see a task's parameters.", list),
                 task_name: Some("list".to_string()),
+                pending: None,
             };
         }
 
@@ -340,6 +736,7 @@ impl CustomTaskHook {
                         intercepted: true,
                         response_text: format!("Reloaded {} tasks from {:?}", count, self.config_path),
                         task_name: Some("reload".to_string()),
+                        pending: None,
                     };
                 }
                 Err(e) => {
@@ -347,11 +744,27 @@ impl CustomTaskHook {
                         intercepted: true,
                         response_text: format!("Failed to reload tasks: {}", e),
                         task_name: Some("reload".to_string()),
+                        pending: None,
                     };
                 }
             }
         }
 
+        // Special command: >>swiftcast help <task>
+        if task_name == "help" {
+            let response_text = match self.tasks.read().await.get(args.trim()) {
+                Some(task) => Self::task_help(task),
+                None if args.trim().is_empty() => "Usage: `>>swiftcast help <task>`\n\nUse `>>swiftcast list` to see available tasks.".to_string(),
+                None => format!("Unknown task: '{}'\n\nUse `>>swiftcast list` to see available tasks.", args.trim()),
+            };
+            return InterceptResult {
+                intercepted: true,
+                response_text,
+                task_name: Some("help".to_string()),
+                pending: None,
+            };
+        }
+
         // Look up the task
         let tasks = self.tasks.read().await;
         let task = match tasks.get(&task_name) {
@@ -361,17 +774,34 @@ impl CustomTaskHook {
                     intercepted: true,
                     response_text: format!("Unknown task: '{}'\n\nUse `>>swiftcast list` to see available tasks.", task_name),
                     task_name: Some(task_name),
+                    pending: None,
                 };
             }
         };
         drop(tasks);
 
+        // 파라미터 스키마가 있으면 인자를 검증하고, 잘못되면 실행하지 않고 사용법 반환
+        let params = match task.params.as_deref().map(|params| task_params::parse_args(params, &args)) {
+            Some(Ok(params)) => params,
+            Some(Err(e)) => {
+                return InterceptResult {
+                    intercepted: true,
+                    response_text: format!("## Invalid arguments: {}\n\n{}\n\n{}", task.name, e, Self::task_usage(&task)),
+                    task_name: Some(task_name),
+                    pending: None,
+                };
+            }
+            None => HashMap::new(),
+        };
+
         // Build task context
         let task_ctx = TaskContext {
             session_id: ctx.session_id.clone(),
             path: ctx.path.clone(),
             model: ctx.model.clone(),
             args,
+            params,
+            steps: HashMap::new(),
         };
 
         // Execute the task
@@ -383,72 +813,139 @@ impl CustomTaskHook {
             task_ctx.args
         );
 
-        match self.execute_task(&task, &task_ctx).await {
-            Ok(result) => InterceptResult {
-                intercepted: true,
-                response_text: format!("## Task: {}\n\n{}\n\n---\n{}", task.name, task.description, result),
-                task_name: Some(task_name),
-            },
-            Err(e) => InterceptResult {
-                intercepted: true,
-                response_text: format!("## Task Failed: {}\n\nError: {}", task.name, e),
-                task_name: Some(task_name),
-            },
+        InterceptResult {
+            intercepted: true,
+            response_text: format!("## Task: {}\n\n{}\n\n---\n", task.name, task.description),
+            task_name: Some(task_name),
+            pending: Some(PendingTask { task, ctx: task_ctx }),
+        }
+    }
+
+    /// Usage and parameters of a task (`>>swiftcast help <task>`)
+    pub fn task_help(task: &TaskDefinition) -> String {
+        format!("## {}\n\n{}\n\n{}", task.name, task.description, Self::task_usage(task))
+    }
+
+    fn task_usage(task: &TaskDefinition) -> String {
+        match task.params.as_deref() {
+            Some(params) => task_params::usage(&task.name, params),
+            None => format!("Usage: `>>swiftcast {} [args]`\n\nArguments are passed as `{{args}}`.", task.name),
         }
     }
 
+    /// Run an intercepted task, streaming `header` and then its output as SSE while it runs
+    /// Dropping the stream (client disconnect) cancels the task and kills its processes
+    pub fn stream_task(self: Arc<Self>, header: String, pending: PendingTask) -> impl Stream<Item = Result<Bytes, Infallible>> {
+        let (sse_tx, sse_rx) = mpsc::unbounded_channel::<String>();
+        tokio::spawn(async move {
+            let PendingTask { task, ctx } = pending;
+            let name = task.name.clone();
+            let _ = sse_tx.send(sse_message_start());
+            let _ = sse_tx.send(sse_text_delta(&header));
+
+            let (log_tx, mut log_rx) = mpsc::unbounded_channel();
+            let log = TaskLog::new(Some(log_tx));
+            // 작업이 끝나면 log가 drop되어 전달 루프도 종료
+            let run = async move {
+                if let Err(e) = self.execute_task(&task, &ctx, &[], &log).await {
+                    log.line(&format!("Task failed: {}", e));
+                }
+            };
+            let forward = async {
+                let mut written = header.len();
+                while let Some(text) = log_rx.recv().await {
+                    written += text.len();
+                    let _ = sse_tx.send(sse_text_delta(&text));
+                }
+                written
+            };
+
+            tokio::select! {
+                (_, written) = join(run, forward) => {
+                    let _ = sse_tx.send(sse_message_end(written / 4));
+                }
+                _ = sse_tx.closed() => {
+                    tracing::info!("[CustomTask] Client disconnected, cancelled task: {}", name);
+                }
+            }
+        });
+
+        futures::stream::unfold(sse_rx, |mut rx| async move {
+            rx.recv().await.map(|event| (Ok(Bytes::from(event)), rx))
+        })
+    }
+
     /// Generate a fake SSE response for intercepted requests
     pub fn generate_sse_response(text: &str) -> String {
-        let message_id = format!("msg_{}", uuid::Uuid::new_v4().to_string().replace("-", "")[..24].to_string());
+        let output_tokens = text.len() / 4; // Rough estimate
+        sse_message_start() + &sse_text_delta(text) + &sse_message_end(output_tokens)
+    }
+}
 
-        // Build SSE events
-        let mut events = Vec::new();
+/// message_start and content_block_start events
+fn sse_message_start() -> String {
+    let message_id = format!("msg_{}", &uuid::Uuid::new_v4().to_string().replace("-", "")[..24]);
 
-        // message_start
-        events.push(format!(
-            r#"event: message_start
+    // Build SSE events
+    let mut events = Vec::new();
+
+    // message_start
+    events.push(format!(
+        r#"event: message_start
 data: {{"type":"message_start","message":{{"id":"{}","type":"message","role":"assistant","content":[],"model":"custom-task","stop_reason":null,"stop_sequence":null,"usage":{{"input_tokens":0,"output_tokens":0}}}}}}"#,
-            message_id
-        ));
+        message_id
+    ));
 
-        // content_block_start
-        events.push(r#"event: content_block_start
+    // content_block_start
+    events.push(r#"event: content_block_start
 data: {"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#.to_string());
 
-        // content_block_delta (split text into chunks for more natural streaming feel)
-        let chunk_size = 50;
-        for (i, chunk) in text.chars().collect::<Vec<_>>().chunks(chunk_size).enumerate() {
-            let chunk_text: String = chunk.iter().collect();
-            let escaped = serde_json::to_string(&chunk_text).unwrap_or_else(|_| format!("\"{}\"", chunk_text));
-            // Remove surrounding quotes from escaped string
-            let escaped = &escaped[1..escaped.len()-1];
-            events.push(format!(
-                r#"event: content_block_delta
+    events.join("\n\n") + "\n\n"
+}
+
+/// content_block_delta events for a piece of text
+fn sse_text_delta(text: &str) -> String {
+    let mut events = String::new();
+
+    // content_block_delta (split text into chunks for more natural streaming feel)
+    let chunk_size = 50;
+    for chunk in text.chars().collect::<Vec<_>>().chunks(chunk_size) {
+        let chunk_text: String = chunk.iter().collect();
+        let escaped = serde_json::to_string(&chunk_text).unwrap_or_else(|_| format!("\"{}\"", chunk_text));
+        // Remove surrounding quotes from escaped string
+        let escaped = &escaped[1..escaped.len()-1];
+        events.push_str(&format!(
+            r#"event: content_block_delta
 data: {{"type":"content_block_delta","index":0,"delta":{{"type":"text_delta","text":"{}"}}}}"#,
-                escaped
-            ));
-        }
+            escaped
+        ));
+        events.push_str("\n\n");
+    }
+    events
+}
 
-        // content_block_stop
-        events.push(r#"event: content_block_stop
+/// content_block_stop, message_delta and message_stop events
+fn sse_message_end(output_tokens: usize) -> String {
+    let mut events = Vec::new();
+
+    // content_block_stop
+    events.push(r#"event: content_block_stop
 data: {"type":"content_block_stop","index":0}"#.to_string());
 
-        // message_delta
-        // Use stop_reason: null to avoid triggering session_complete webhook
-        // "end_turn" would cause ThreadCast to think the session is complete
-        let output_tokens = text.len() / 4; // Rough estimate
-        events.push(format!(
-            r#"event: message_delta
+    // message_delta
+    // Use stop_reason: null to avoid triggering session_complete webhook
+    // "end_turn" would cause ThreadCast to think the session is complete
+    events.push(format!(
+        r#"event: message_delta
 data: {{"type":"message_delta","delta":{{"stop_reason":null,"stop_sequence":null}},"usage":{{"output_tokens":{}}}}}"#,
-            output_tokens
-        ));
+        output_tokens
+    ));
 
-        // message_stop
-        events.push(r#"event: message_stop
+    // message_stop
+    events.push(r#"event: message_stop
 data: {"type":"message_stop"}"#.to_string());
 
-        events.join("\n\n") + "\n\n"
-    }
+    events.join("\n\n") + "\n\n"
 }
 
 impl Clone for CustomTaskHook {
@@ -492,4 +989,158 @@ mod tests {
             None
         );
     }
+
+    fn task(name: &str, task_type: TaskType) -> TaskDefinition {
+        TaskDefinition {
+            name: name.to_string(),
+            description: String::new(),
+            task_type,
+            command: None,
+            working_dir: None,
+            url: None,
+            http_method: None,
+            file_path: None,
+            env: None,
+            steps: None,
+            parallel: None,
+            continue_on_failure: None,
+            timeout_secs: None,
+            params: None,
+        }
+    }
+
+    fn shell(name: &str, command: &str) -> TaskDefinition {
+        TaskDefinition { command: Some(command.to_string()), ..task(name, TaskType::Shell) }
+    }
+
+    fn step(task: &str) -> TaskStep {
+        serde_json::from_value(serde_json::json!({ "task": task })).unwrap()
+    }
+
+    fn hook(tasks: Vec<TaskDefinition>) -> CustomTaskHook {
+        let hook = CustomTaskHook::new(PathBuf::from("/nonexistent/tasks.json"));
+        *hook.tasks.try_write().unwrap() = tasks.into_iter().map(|t| (t.name.clone(), t)).collect();
+        hook
+    }
+
+    fn ctx(args: &str) -> TaskContext {
+        TaskContext {
+            session_id: None,
+            path: "/v1/messages".to_string(),
+            model: "m".to_string(),
+            args: args.to_string(),
+            params: HashMap::new(),
+            steps: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_composite_sequential() {
+        let inline: TaskStep = serde_json::from_value(serde_json::json!({
+            "name": "greet",
+            "task_type": "shell",
+            "command": "echo {steps.lint.output} {steps.lint.status} {args}"
+        }))
+        .unwrap();
+        let ship = TaskDefinition {
+            steps: Some(vec![step("lint"), inline, step("test"), step("build")]),
+            ..task("ship", TaskType::Composite)
+        };
+        let hook = hook(vec![shell("lint", "echo clean"), shell("test", "echo oops >&2; exit 3"), shell("build", "echo built"), ship.clone()]);
+
+        let log = TaskLog::new(None);
+        let result = hook.execute_task(&ship, &ctx("now"), &[], &log).await.unwrap();
+        let text = log.text();
+        assert!(!result.success);
+        assert!(result.output.contains("clean ok now"));
+        assert!(text.contains("oops\n```\nCommand failed (exit code: 3)"));
+        assert!(text.contains("test: failed"));
+        assert!(text.contains("### build: skipped"));
+        assert!(text.ends_with("**2/4 steps succeeded**\n"));
+
+        let ship = TaskDefinition { continue_on_failure: Some(true), ..ship };
+        let log = TaskLog::new(None);
+        hook.execute_task(&ship, &ctx(""), &[], &log).await.unwrap();
+        assert!(log.text().contains("build: ok"));
+        assert!(log.text().ends_with("**3/4 steps succeeded**\n"));
+    }
+
+    #[tokio::test]
+    async fn test_composite_parallel_and_cycles() {
+        let all = TaskDefinition {
+            steps: Some(vec![step("a"), step("b")]),
+            parallel: Some(true),
+            ..task("all", TaskType::Composite)
+        };
+        let hook = hook(vec![shell("a", "sleep 0.3; echo a"), shell("b", "sleep 0.3; echo b"), all.clone()]);
+        let started = Instant::now();
+        let log = TaskLog::new(None);
+        let result = hook.execute_task(&all, &ctx(""), &[], &log).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, "a\nb");
+        assert!(log.text().contains("[a] a\n"));
+        assert!(started.elapsed() < Duration::from_millis(550));
+
+        let outer = TaskDefinition { steps: Some(vec![step("inner")]), ..task("outer", TaskType::Composite) };
+        let inner = TaskDefinition { steps: Some(vec![step("outer")]), ..task("inner", TaskType::Composite) };
+        let hook = self::hook(vec![outer.clone(), inner]);
+        let log = TaskLog::new(None);
+        let result = hook.execute_task(&outer, &ctx(""), &[], &log).await.unwrap();
+        assert!(!result.success);
+        assert!(log.text().contains("Task cycle: outer -> inner -> outer"));
+    }
+
+    #[tokio::test]
+    async fn test_shell_timeout() {
+        let slow = TaskDefinition { timeout_secs: Some(1), ..shell("slow", "echo started; sleep 5") };
+        let hook = hook(vec![]);
+        let started = Instant::now();
+        let log = TaskLog::new(None);
+        let result = hook.execute_task(&slow, &ctx(""), &[], &log).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(log.text(), "```\nstarted\n```\nTimed out after 1s\n");
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+
+    #[tokio::test]
+    async fn test_intercept_params() {
+        let params = serde_json::from_value(serde_json::json!([
+            {"name": "env", "enum": ["dev", "prod"], "required": true, "positional": true},
+            {"name": "dry_run", "type": "bool"}
+        ]))
+        .unwrap();
+        let deploy = TaskDefinition { params: Some(params), ..shell("deploy", "echo {params.env} {params.dry_run}") };
+        let hook = hook(vec![deploy]);
+        let intercept = |text: &str| {
+            let body = serde_json::json!({ "messages": [{ "role": "user", "content": text }] });
+            RequestContext::new(None, "m".to_string(), "POST".to_string(), "/v1/messages".to_string(), body)
+        };
+
+        let result = hook.try_intercept(&intercept(">>swiftcast deploy env=prod --dry-run")).await;
+        let pending = result.pending.unwrap();
+        assert_eq!(pending.ctx.params["env"], "prod");
+        assert_eq!(pending.ctx.expand(pending.task.command.as_deref().unwrap()), "echo prod true");
+
+        let result = hook.try_intercept(&intercept(">>swiftcast deploy env=qa")).await;
+        assert!(result.pending.is_none());
+        assert!(result.response_text.starts_with("## Invalid arguments: deploy\n\nenv must be one of dev, prod"));
+        assert!(result.response_text.contains("Usage: `>>swiftcast deploy <env> [--dry-run]`"));
+
+        let result = hook.try_intercept(&intercept(">>swiftcast help deploy")).await;
+        assert!(result.response_text.contains("- `dry_run` (bool)"));
+    }
+
+    #[tokio::test]
+    async fn test_stream_task() {
+        use futures::StreamExt;
+
+        let hook = Arc::new(hook(vec![]));
+        let pending = PendingTask { task: shell("hello", "echo one; echo two"), ctx: ctx("") };
+        let events: Vec<Bytes> = hook.stream_task("## Task: hello\n".to_string(), pending).map(|e| e.unwrap()).collect().await;
+        let sse = String::from_utf8(events.concat()).unwrap();
+        assert!(sse.starts_with("event: message_start"));
+        assert!(sse.contains(r#""text":"one\n""#));
+        assert!(sse.contains(r#""text":"two\n""#));
+        assert!(sse.trim_end().ends_with(r#"data: {"type":"message_stop"}"#));
+    }
 }
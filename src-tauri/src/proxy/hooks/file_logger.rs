@@ -55,6 +55,19 @@ impl FileLoggerHook {
         self.log_dir.join(&short_id)
     }
 
+    /// Find the log file written for a request (file names carry the first 8 chars of the request ID)
+    pub async fn find_log_file(&self, session_id: &str, request_id: &str) -> Option<PathBuf> {
+        let short_request_id: String = request_id.chars().take(8).collect();
+        let needle = format!("_{}_", short_request_id);
+        let mut entries = tokio::fs::read_dir(self.get_session_dir(session_id)).await.ok()?;
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if entry.file_name().to_string_lossy().contains(&needle) {
+                return Some(entry.path());
+            }
+        }
+        None
+    }
+
     /// Generate log file name based on timestamp and request info
     fn generate_filename(&self, req_ctx: &RequestContext, request_num: u64) -> String {
         let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
@@ -4,12 +4,14 @@ pub mod context_provider;
 pub mod custom_task;
 pub mod file_logger;
 pub mod registry;
+pub mod task_params;
 pub mod traits;
 
 pub use compaction_injector::{CompactionConfig, CompactionInjectorHook};
-pub use context::{HookLogEntry, RequestContext, ResponseBuilder, ResponseContext};
+pub use context::{ContentBlock, HookLogEntry, RequestContext, ResponseBuilder, ResponseContext};
 pub use context_provider::{ContextProviderManager, ProviderConfig};
 pub use custom_task::{CustomTaskHook, TaskDefinition, TaskType};
 pub use file_logger::FileLoggerHook;
 pub use registry::HookRegistry;
+pub use task_params::{ParamType, TaskParam};
 pub use traits::{Hook, ModifyHook};
@@ -1,7 +1,12 @@
+use futures::FutureExt;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::RwLock;
 use super::context::{RequestContext, ResponseContext};
 use super::traits::{Hook, ModifyHook};
+use crate::proxy::metrics::Metrics;
 
 /// Registry for managing hooks
 #[derive(Clone)]
@@ -9,6 +14,7 @@ pub struct HookRegistry {
     hooks: Arc<RwLock<Vec<Arc<dyn Hook>>>>,
     modify_hooks: Arc<RwLock<Vec<Arc<dyn ModifyHook>>>>,
     enabled: Arc<RwLock<bool>>,
+    metrics: Metrics,
 }
 
 impl HookRegistry {
@@ -17,9 +23,30 @@ impl HookRegistry {
             hooks: Arc::new(RwLock::new(Vec::new())),
             modify_hooks: Arc::new(RwLock::new(Vec::new())),
             enabled: Arc::new(RwLock::new(true)),
+            metrics: Metrics::new(),
         }
     }
 
+    /// Record hook execution time and failures into the given metrics registry
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Run one hook invocation, recording its duration; a panicking hook is counted as failed
+    async fn instrument<F, T>(&self, hook_name: &str, event: &str, fut: F) -> Option<T>
+    where
+        F: Future<Output = T>,
+    {
+        let start = Instant::now();
+        let result = AssertUnwindSafe(fut).catch_unwind().await.ok();
+        if result.is_none() {
+            tracing::error!("Hook {} panicked during {}", hook_name, event);
+        }
+        self.metrics.observe_hook(hook_name, event, start.elapsed(), result.is_none());
+        result
+    }
+
     /// Register a new read-only hook
     pub async fn register(&self, hook: Arc<dyn Hook>) {
         let mut hooks = self.hooks.write().await;
@@ -54,7 +81,7 @@ impl HookRegistry {
 
         let hooks = self.hooks.read().await;
         for hook in hooks.iter() {
-            hook.on_request_before(ctx).await;
+            self.instrument(hook.name(), "request_before", hook.on_request_before(ctx)).await;
         }
     }
 
@@ -66,7 +93,7 @@ impl HookRegistry {
 
         let hooks = self.hooks.read().await;
         for hook in hooks.iter() {
-            hook.on_request_after(req_ctx, res_ctx).await;
+            self.instrument(hook.name(), "request_after", hook.on_request_after(req_ctx, res_ctx)).await;
         }
     }
 
@@ -78,7 +105,7 @@ impl HookRegistry {
 
         let hooks = self.hooks.read().await;
         for hook in hooks.iter() {
-            hook.on_request_success(req_ctx, res_ctx).await;
+            self.instrument(hook.name(), "request_success", hook.on_request_success(req_ctx, res_ctx)).await;
         }
     }
 
@@ -90,7 +117,7 @@ impl HookRegistry {
 
         let hooks = self.hooks.read().await;
         for hook in hooks.iter() {
-            hook.on_request_failed(req_ctx, res_ctx).await;
+            self.instrument(hook.name(), "request_failed", hook.on_request_failed(req_ctx, res_ctx)).await;
         }
     }
 
@@ -102,7 +129,7 @@ impl HookRegistry {
 
         let hooks = self.hooks.read().await;
         for hook in hooks.iter() {
-            hook.on_response_chunk(req_ctx, chunk).await;
+            self.instrument(hook.name(), "response_chunk", hook.on_response_chunk(req_ctx, chunk)).await;
         }
     }
 
@@ -114,7 +141,7 @@ impl HookRegistry {
 
         let hooks = self.hooks.read().await;
         for hook in hooks.iter() {
-            hook.on_response_complete(req_ctx, res_ctx).await;
+            self.instrument(hook.name(), "response_complete", hook.on_response_complete(req_ctx, res_ctx)).await;
         }
     }
 
@@ -140,7 +167,11 @@ impl HookRegistry {
         let mut modified = false;
 
         for hook in hooks.iter() {
-            if let Some(new_body) = hook.modify_request_body(&current_body, ctx).await {
+            let result = self
+                .instrument(hook.name(), "modify_request_body", hook.modify_request_body(&current_body, ctx))
+                .await
+                .flatten();
+            if let Some(new_body) = result {
                 tracing::debug!("Request body modified by hook: {}", hook.name());
                 current_body = new_body;
                 modified = true;
@@ -166,7 +197,11 @@ impl HookRegistry {
         let mut modified = false;
 
         for hook in hooks.iter() {
-            if let Some(new_text) = hook.modify_response_text(&current_text, ctx).await {
+            let result = self
+                .instrument(hook.name(), "modify_response_text", hook.modify_response_text(&current_text, ctx))
+                .await
+                .flatten();
+            if let Some(new_text) = result {
                 tracing::debug!("Response text modified by hook: {}", hook.name());
                 current_text = new_text;
                 modified = true;
@@ -0,0 +1,319 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Parameter of a custom task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskParam {
+    /// Name used as `name=value` / `--name` and in `{params.<name>}`
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(rename = "type", default)]
+    pub param_type: ParamType,
+    #[serde(default)]
+    pub required: bool,
+    /// Value used when the parameter is not given
+    pub default: Option<serde_json::Value>,
+    /// Allowed values
+    #[serde(rename = "enum")]
+    pub choices: Option<Vec<String>>,
+    /// Can also be given without its name, in declaration order
+    #[serde(default)]
+    pub positional: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ParamType {
+    #[default]
+    String,
+    Int,
+    Number,
+    Bool,
+}
+
+impl ParamType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ParamType::String => "string",
+            ParamType::Int => "int",
+            ParamType::Number => "number",
+            ParamType::Bool => "bool",
+        }
+    }
+
+    /// Normalized value, or an error naming the expected type
+    fn check(&self, name: &str, value: &str) -> Result<String, String> {
+        let invalid = || format!("{} must be {} (got '{}')", name, self.describe(), value);
+        match self {
+            ParamType::String => Ok(value.to_string()),
+            ParamType::Int => value.parse::<i64>().map(|n| n.to_string()).map_err(|_| invalid()),
+            ParamType::Number => value.parse::<f64>().map(|_| value.to_string()).map_err(|_| invalid()),
+            ParamType::Bool => match value.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "1" => Ok("true".to_string()),
+                "false" | "no" | "0" => Ok("false".to_string()),
+                _ => Err(invalid()),
+            },
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            ParamType::String => "a string",
+            ParamType::Int => "an integer",
+            ParamType::Number => "a number",
+            ParamType::Bool => "true or false",
+        }
+    }
+}
+
+impl TaskParam {
+    fn matches(&self, key: &str) -> bool {
+        self.name == key || self.name.replace('_', "-") == key || self.name == key.replace('-', "_")
+    }
+
+    fn check(&self, value: &str) -> Result<String, String> {
+        let value = self.param_type.check(&self.name, value)?;
+        if let Some(choices) = self.choices.as_ref().filter(|c| !c.is_empty()) {
+            if !choices.contains(&value) {
+                return Err(format!("{} must be one of {} (got '{}')", self.name, choices.join(", "), value));
+            }
+        }
+        Ok(value)
+    }
+
+    fn default_value(&self) -> Option<String> {
+        match self.default.as_ref()? {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        }
+    }
+
+    /// `<env>`, `[--dry-run]`, `region=<string>`
+    fn synopsis(&self) -> String {
+        let placeholder = match self.choices.as_ref().filter(|c| !c.is_empty()) {
+            Some(choices) => choices.join("|"),
+            None => self.param_type.as_str().to_string(),
+        };
+        let usage = if self.positional {
+            format!("<{}>", self.name)
+        } else if self.param_type == ParamType::Bool {
+            format!("--{}", self.name.replace('_', "-"))
+        } else {
+            format!("{}=<{}>", self.name, placeholder)
+        };
+        if self.required { usage } else { format!("[{}]", usage) }
+    }
+}
+
+/// Check a parameter schema (names unique, defaults valid)
+pub fn validate_params(params: &[TaskParam]) -> Result<(), String> {
+    for (i, param) in params.iter().enumerate() {
+        if param.name.trim().is_empty() || param.name.contains(char::is_whitespace) || param.name.contains('=') {
+            return Err(format!("Invalid parameter name: '{}'", param.name));
+        }
+        if params[..i].iter().any(|p| p.name == param.name) {
+            return Err(format!("Duplicate parameter: {}", param.name));
+        }
+        if let Some(default) = param.default_value() {
+            param.check(&default).map_err(|e| format!("Invalid default: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Parse `env=prod --dry-run 3` against a schema into parameter values (defaults filled in)
+pub fn parse_args(params: &[TaskParam], args: &str) -> Result<HashMap<String, String>, String> {
+    let find = |key: &str| {
+        params
+            .iter()
+            .find(|p| p.matches(key))
+            .ok_or_else(|| format!("Unknown parameter: {}", key))
+    };
+    let mut values: HashMap<String, String> = HashMap::new();
+    let set = |values: &mut HashMap<String, String>, param: &TaskParam, value: &str| -> Result<(), String> {
+        let value = param.check(value)?;
+        if values.insert(param.name.clone(), value).is_some() {
+            return Err(format!("{} given more than once", param.name));
+        }
+        Ok(())
+    };
+
+    let mut positional = params.iter().filter(|p| p.positional);
+    let mut tokens = split_args(args)?.into_iter();
+    while let Some(token) = tokens.next() {
+        if let Some(flag) = token.strip_prefix("--") {
+            match flag.split_once('=') {
+                Some((key, value)) => set(&mut values, find(key)?, value)?,
+                None => match find(flag) {
+                    Ok(param) if param.param_type == ParamType::Bool => set(&mut values, param, "true")?,
+                    // --name value
+                    Ok(param) => {
+                        let value = tokens.next().ok_or_else(|| format!("{} needs a value", param.name))?;
+                        set(&mut values, param, &value)?;
+                    }
+                    Err(e) => match flag.strip_prefix("no-").map(find) {
+                        Some(Ok(param)) if param.param_type == ParamType::Bool => set(&mut values, param, "false")?,
+                        _ => return Err(e),
+                    },
+                },
+            }
+        } else if let Some((key, value)) = token.split_once('=').filter(|(key, _)| params.iter().any(|p| p.matches(key))) {
+            set(&mut values, find(key)?, value)?;
+        } else {
+            // 이름 없는 값은 아직 지정되지 않은 positional 파라미터에 순서대로 배정
+            let param = loop {
+                match positional.next() {
+                    Some(p) if values.contains_key(&p.name) => continue,
+                    Some(p) => break p,
+                    None => return Err(format!("Unexpected argument: {}", token)),
+                }
+            };
+            set(&mut values, param, &token)?;
+        }
+    }
+
+    for param in params {
+        if values.contains_key(&param.name) {
+            continue;
+        }
+        match param.default_value() {
+            Some(default) => {
+                values.insert(param.name.clone(), default);
+            }
+            None if param.required => return Err(format!("Missing required parameter: {}", param.name)),
+            None if param.param_type == ParamType::Bool => {
+                values.insert(param.name.clone(), "false".to_string());
+            }
+            None => {}
+        }
+    }
+    Ok(values)
+}
+
+/// Usage line plus one line per parameter
+pub fn usage(task_name: &str, params: &[TaskParam]) -> String {
+    let mut text = format!(
+        "Usage: `>>swiftcast {}{}`",
+        task_name,
+        params.iter().map(|p| format!(" {}", p.synopsis())).collect::<String>()
+    );
+    if params.is_empty() {
+        return text;
+    }
+    text.push_str("\n\nParameters:\n");
+    for param in params {
+        let mut details = vec![param.param_type.as_str().to_string()];
+        if param.required {
+            details.push("required".to_string());
+        }
+        if param.positional {
+            details.push("positional".to_string());
+        }
+        if let Some(choices) = param.choices.as_ref().filter(|c| !c.is_empty()) {
+            details.push(format!("one of {}", choices.join(", ")));
+        }
+        if let Some(default) = param.default_value() {
+            details.push(format!("default {}", default));
+        }
+        text.push_str(&format!("- `{}` ({})", param.name, details.join(", ")));
+        if let Some(description) = param.description.as_deref().filter(|d| !d.is_empty()) {
+            text.push_str(&format!(": {}", description));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Split on whitespace, keeping "double" or 'single' quoted parts together
+fn split_args(args: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut quote: Option<char> = None;
+    for c in args.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_token = true;
+            }
+            None if c.is_whitespace() => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err("Unclosed quote in arguments".to_string());
+    }
+    if in_token {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Vec<TaskParam> {
+        serde_json::from_value(json!([
+            {"name": "env", "enum": ["dev", "staging", "prod"], "required": true, "positional": true, "description": "Target"},
+            {"name": "dry_run", "type": "bool"},
+            {"name": "replicas", "type": "int", "default": 2},
+            {"name": "message"}
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let params = schema();
+        let values = parse_args(&params, "env=prod --dry-run").unwrap();
+        assert_eq!(values["env"], "prod");
+        assert_eq!(values["dry_run"], "true");
+        assert_eq!(values["replicas"], "2");
+        assert!(!values.contains_key("message"));
+
+        let values = parse_args(&params, r#"staging --replicas 5 message="ship it" --no-dry-run"#).unwrap();
+        assert_eq!(values["env"], "staging");
+        assert_eq!(values["replicas"], "5");
+        assert_eq!(values["message"], "ship it");
+        assert_eq!(values["dry_run"], "false");
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        let params = schema();
+        assert_eq!(parse_args(&params, "").unwrap_err(), "Missing required parameter: env");
+        assert!(parse_args(&params, "env=qa").unwrap_err().contains("one of dev, staging, prod"));
+        assert!(parse_args(&params, "prod replicas=many").unwrap_err().contains("replicas must be an integer"));
+        assert_eq!(parse_args(&params, "prod --force").unwrap_err(), "Unknown parameter: force");
+        assert_eq!(parse_args(&params, "prod dev").unwrap_err(), "Unexpected argument: dev");
+        assert_eq!(parse_args(&params, "prod env=dev").unwrap_err(), "env given more than once");
+        assert_eq!(parse_args(&params, "prod message='oops").unwrap_err(), "Unclosed quote in arguments");
+    }
+
+    #[test]
+    fn test_usage_and_validation() {
+        let params = schema();
+        let text = usage("deploy", &params);
+        assert!(text.starts_with("Usage: `>>swiftcast deploy <env> [--dry-run] [replicas=<int>] [message=<string>]`"));
+        assert!(text.contains("- `env` (string, required, positional, one of dev, staging, prod): Target"));
+        assert!(text.contains("- `replicas` (int, default 2)"));
+
+        assert!(validate_params(&params).is_ok());
+        let mut bad = schema();
+        bad[2].default = Some(json!("two"));
+        assert!(validate_params(&bad).unwrap_err().contains("Invalid default"));
+    }
+}
@@ -0,0 +1,80 @@
+use std::time::{Duration, Instant};
+
+/// Generation shorter than this gives meaningless tokens/sec (single-chunk responses)
+const MIN_GENERATION: Duration = Duration::from_millis(50);
+
+/// Points in time of one proxied request
+#[derive(Debug, Clone, Copy)]
+pub struct RequestTimings {
+    /// Request arrived at the proxy
+    pub received_at: Instant,
+    /// Request sent upstream (after hooks, routing and body rewriting)
+    pub upstream_sent_at: Instant,
+    /// Upstream response headers received
+    pub headers_at: Instant,
+    /// First `content_block_delta`
+    pub first_token_at: Option<Instant>,
+}
+
+/// Per-request latency as stored on the usage row
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Latency {
+    /// Time spent in the proxy before the request went upstream
+    pub queue_ms: i64,
+    /// Upstream send to response headers
+    pub headers_ms: i64,
+    /// Upstream send to first content delta (time to first token)
+    pub ttft_ms: Option<i64>,
+    /// Upstream send to end of stream
+    pub duration_ms: i64,
+    /// Output tokens per second between the first token and the end of stream
+    pub output_tokens_per_sec: Option<f64>,
+}
+
+impl RequestTimings {
+    pub fn latency(&self, ended_at: Instant, output_tokens: i64) -> Latency {
+        let ms = |from: Instant, to: Instant| to.saturating_duration_since(from).as_millis() as i64;
+        let output_tokens_per_sec = self
+            .first_token_at
+            .map(|first| ended_at.saturating_duration_since(first))
+            .filter(|generation| *generation >= MIN_GENERATION && output_tokens > 0)
+            .map(|generation| output_tokens as f64 / generation.as_secs_f64());
+
+        Latency {
+            queue_ms: ms(self.received_at, self.upstream_sent_at),
+            headers_ms: ms(self.upstream_sent_at, self.headers_at),
+            ttft_ms: self.first_token_at.map(|first| ms(self.upstream_sent_at, first)),
+            duration_ms: ms(self.upstream_sent_at, ended_at),
+            output_tokens_per_sec,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency() {
+        let received_at = Instant::now();
+        let at = |ms: u64| received_at + Duration::from_millis(ms);
+        let timings = RequestTimings {
+            received_at,
+            upstream_sent_at: at(5),
+            headers_at: at(305),
+            first_token_at: Some(at(805)),
+        };
+
+        let latency = timings.latency(at(2805), 100);
+        assert_eq!(latency.queue_ms, 5);
+        assert_eq!(latency.headers_ms, 300);
+        assert_eq!(latency.ttft_ms, Some(800));
+        assert_eq!(latency.duration_ms, 2800);
+        assert_eq!(latency.output_tokens_per_sec, Some(50.0));
+
+        // 첫 토큰 직후 끝난 응답은 처리량 없음
+        assert_eq!(timings.latency(at(810), 3).output_tokens_per_sec, None);
+        let no_content = RequestTimings { first_token_at: None, ..timings };
+        assert_eq!(no_content.latency(at(400), 0).ttft_ms, None);
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Histogram buckets (seconds) for upstream latency and time-to-first-token
+const LATENCY_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0];
+/// Histogram buckets (seconds) for hook execution time
+const HOOK_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    buckets: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(buckets: &'static [f64]) -> Self {
+        Self {
+            buckets,
+            counts: vec![0; buckets.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (i, bound) in self.buckets.iter().enumerate() {
+            if value <= *bound {
+                self.counts[i] += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+/// Sorted label set, rendered as `{k="v",...}`
+type Labels = Vec<(&'static str, String)>;
+
+#[derive(Default)]
+struct MetricsInner {
+    requests: Mutex<BTreeMap<Labels, u64>>,
+    in_flight: AtomicI64,
+    upstream_latency: Mutex<BTreeMap<Labels, Histogram>>,
+    time_to_first_token: Mutex<BTreeMap<Labels, Histogram>>,
+    tokens: Mutex<BTreeMap<Labels, u64>>,
+    cost_usd: Mutex<BTreeMap<Labels, f64>>,
+    hook_duration: Mutex<BTreeMap<Labels, Histogram>>,
+    hook_failures: Mutex<BTreeMap<Labels, u64>>,
+    webhook_failures: Mutex<BTreeMap<Labels, u64>>,
+    webhook_dead_letters: Mutex<BTreeMap<Labels, u64>>,
+    usage_log_drops: AtomicU64,
+}
+
+/// Process-local metrics registry exposed at `/_swiftcast/metrics` (Prometheus text format)
+#[derive(Clone, Default)]
+pub struct Metrics {
+    inner: Arc<MetricsInner>,
+}
+
+/// Decrements the in-flight gauge when dropped (held by the response stream)
+pub struct InFlightGuard {
+    metrics: Metrics,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.metrics.inner.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark a request as in flight until the returned guard is dropped
+    pub fn start_request(&self) -> InFlightGuard {
+        self.inner.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard { metrics: self.clone() }
+    }
+
+    pub fn record_request(&self, account: &str, model: &str, status: &str, path: &str) {
+        let labels = vec![
+            ("account", account.to_string()),
+            ("model", model.to_string()),
+            ("path", path.to_string()),
+            ("status", status.to_string()),
+        ];
+        *lock(&self.inner.requests).entry(labels).or_insert(0) += 1;
+    }
+
+    pub fn observe_upstream_latency(&self, account: &str, model: &str, elapsed: Duration) {
+        let labels = vec![("account", account.to_string()), ("model", model.to_string())];
+        lock(&self.inner.upstream_latency)
+            .entry(labels)
+            .or_insert_with(|| Histogram::new(LATENCY_BUCKETS))
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn observe_time_to_first_token(&self, account: &str, model: &str, elapsed: Duration) {
+        let labels = vec![("account", account.to_string()), ("model", model.to_string())];
+        lock(&self.inner.time_to_first_token)
+            .entry(labels)
+            .or_insert_with(|| Histogram::new(LATENCY_BUCKETS))
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Record token usage; `kind` is one of input, output, cache_read, cache_creation
+    pub fn record_tokens(&self, account: &str, model: &str, kind: &str, count: i64) {
+        if count <= 0 {
+            return;
+        }
+        let labels = vec![
+            ("account", account.to_string()),
+            ("model", model.to_string()),
+            ("type", kind.to_string()),
+        ];
+        *lock(&self.inner.tokens).entry(labels).or_insert(0) += count as u64;
+    }
+
+    pub fn record_cost(&self, account: &str, model: &str, cost_usd: f64) {
+        let labels = vec![("account", account.to_string()), ("model", model.to_string())];
+        *lock(&self.inner.cost_usd).entry(labels).or_insert(0.0) += cost_usd;
+    }
+
+    pub fn observe_hook(&self, hook: &str, event: &str, elapsed: Duration, failed: bool) {
+        let labels = vec![("event", event.to_string()), ("hook", hook.to_string())];
+        if failed {
+            *lock(&self.inner.hook_failures).entry(labels.clone()).or_insert(0) += 1;
+        }
+        lock(&self.inner.hook_duration)
+            .entry(labels)
+            .or_insert_with(|| Histogram::new(HOOK_BUCKETS))
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn record_webhook_failure(&self, event: &str) {
+        let labels = vec![("event", event.to_string())];
+        *lock(&self.inner.webhook_failures).entry(labels).or_insert(0) += 1;
+    }
+
+    /// Webhook delivery given up after the maximum number of attempts
+    pub fn record_webhook_dead_letter(&self, event: &str) {
+        let labels = vec![("event", event.to_string())];
+        *lock(&self.inner.webhook_dead_letters).entry(labels).or_insert(0) += 1;
+    }
+
+    /// Usage row skipped because the DB task semaphore was exhausted
+    pub fn record_usage_log_dropped(&self) {
+        self.inner.usage_log_drops.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Render all series in Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        write_counter(
+            &mut out,
+            "swiftcast_requests_total",
+            "Proxied requests by account, model, status and path",
+            &lock(&self.inner.requests),
+        );
+
+        write_header(&mut out, "swiftcast_requests_in_flight", "Requests currently being proxied", "gauge");
+        let _ = writeln!(out, "swiftcast_requests_in_flight {}", self.inner.in_flight.load(Ordering::Relaxed));
+
+        write_histograms(
+            &mut out,
+            "swiftcast_upstream_latency_seconds",
+            "Time from upstream send until response headers",
+            &lock(&self.inner.upstream_latency),
+        );
+        write_histograms(
+            &mut out,
+            "swiftcast_time_to_first_token_seconds",
+            "Time from upstream send until the first content_block_delta",
+            &lock(&self.inner.time_to_first_token),
+        );
+
+        write_counter(
+            &mut out,
+            "swiftcast_tokens_total",
+            "Tokens by account, model and type (input, output, cache_read, cache_creation)",
+            &lock(&self.inner.tokens),
+        );
+
+        write_header(&mut out, "swiftcast_cost_usd_total", "Estimated cost in USD", "counter");
+        for (labels, value) in lock(&self.inner.cost_usd).iter() {
+            let _ = writeln!(out, "swiftcast_cost_usd_total{} {}", format_labels(labels, None), value);
+        }
+
+        write_histograms(
+            &mut out,
+            "swiftcast_hook_duration_seconds",
+            "Hook execution time by hook and event",
+            &lock(&self.inner.hook_duration),
+        );
+        write_counter(
+            &mut out,
+            "swiftcast_hook_failures_total",
+            "Hook invocations that timed out",
+            &lock(&self.inner.hook_failures),
+        );
+        write_counter(
+            &mut out,
+            "swiftcast_webhook_failures_total",
+            "Webhook deliveries that failed or returned a non-2xx status",
+            &lock(&self.inner.webhook_failures),
+        );
+        write_counter(
+            &mut out,
+            "swiftcast_webhook_dead_letters_total",
+            "Webhook deliveries moved to the dead-letter table",
+            &lock(&self.inner.webhook_dead_letters),
+        );
+
+        write_header(
+            &mut out,
+            "swiftcast_usage_log_dropped_total",
+            "Usage rows skipped because too many DB tasks were running",
+            "counter",
+        );
+        let _ = writeln!(
+            out,
+            "swiftcast_usage_log_dropped_total {}",
+            self.inner.usage_log_drops.load(Ordering::Relaxed)
+        );
+
+        out
+    }
+}
+
+/// Lock a metrics map, recovering from poisoning (metrics must never take the proxy down)
+fn lock<T>(m: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn write_header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn write_counter(out: &mut String, name: &str, help: &str, series: &BTreeMap<Labels, u64>) {
+    write_header(out, name, help, "counter");
+    for (labels, value) in series {
+        let _ = writeln!(out, "{}{} {}", name, format_labels(labels, None), value);
+    }
+}
+
+fn write_histograms(out: &mut String, name: &str, help: &str, series: &BTreeMap<Labels, Histogram>) {
+    write_header(out, name, help, "histogram");
+    for (labels, h) in series {
+        for (bound, count) in h.buckets.iter().zip(&h.counts) {
+            let _ = writeln!(out, "{}_bucket{} {}", name, format_labels(labels, Some(&bound.to_string())), count);
+        }
+        let _ = writeln!(out, "{}_bucket{} {}", name, format_labels(labels, Some("+Inf")), h.count);
+        let _ = writeln!(out, "{}_sum{} {}", name, format_labels(labels, None), h.sum);
+        let _ = writeln!(out, "{}_count{} {}", name, format_labels(labels, None), h.count);
+    }
+}
+
+fn format_labels(labels: &Labels, le: Option<&str>) -> String {
+    let mut parts: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+        .collect();
+    if let Some(le) = le {
+        parts.push(format!("le=\"{}\"", le));
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", parts.join(","))
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counters_and_gauge() {
+        let metrics = Metrics::new();
+        metrics.record_request("GLM", "glm-4.6", "200", "/v1/messages");
+        metrics.record_request("GLM", "glm-4.6", "200", "/v1/messages");
+        let guard = metrics.start_request();
+        metrics.record_usage_log_dropped();
+
+        let text = metrics.render();
+        assert!(text.contains(
+            r#"swiftcast_requests_total{account="GLM",model="glm-4.6",path="/v1/messages",status="200"} 2"#
+        ));
+        assert!(text.contains("swiftcast_requests_in_flight 1"));
+        assert!(text.contains("swiftcast_usage_log_dropped_total 1"));
+
+        drop(guard);
+        assert!(metrics.render().contains("swiftcast_requests_in_flight 0"));
+    }
+
+    #[test]
+    fn test_render_histogram_is_cumulative() {
+        let metrics = Metrics::new();
+        metrics.observe_upstream_latency("a", "m", Duration::from_millis(200));
+        metrics.observe_upstream_latency("a", "m", Duration::from_secs(3));
+
+        let text = metrics.render();
+        assert!(text.contains(r#"swiftcast_upstream_latency_seconds_bucket{account="a",model="m",le="0.25"} 1"#));
+        assert!(text.contains(r#"swiftcast_upstream_latency_seconds_bucket{account="a",model="m",le="5"} 2"#));
+        assert!(text.contains(r#"swiftcast_upstream_latency_seconds_bucket{account="a",model="m",le="+Inf"} 2"#));
+        assert!(text.contains(r#"swiftcast_upstream_latency_seconds_count{account="a",model="m"} 2"#));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -1,11 +1,31 @@
+pub mod admin_api;
+pub mod alerts;
+pub mod errors;
+pub mod events;
 pub mod hooks;
+pub mod latency;
+pub mod metrics;
+pub mod pricing;
+pub mod project;
 pub mod question_detector;
+pub mod request_kind;
+pub mod search;
 pub mod server;
+pub mod session_lifecycle;
+pub mod session_resolver;
 pub mod step_tracker;
+pub mod subagent;
+pub mod timeouts;
+pub mod tool_usage;
+pub mod transcript;
 pub mod webhook;
 
 pub use hooks::{FileLoggerHook, Hook, HookRegistry, RequestContext, ResponseBuilder, ResponseContext};
+pub use events::{EventBus, ProxyEvent, ProxyEventKind};
+pub use metrics::Metrics;
 pub use question_detector::QuestionDetector;
+pub use request_kind::{KindRouter, RequestKind};
 pub use server::ProxyServer;
 pub use step_tracker::StepTracker;
+pub use subagent::SubagentTracker;
 pub use webhook::WebhookClient;
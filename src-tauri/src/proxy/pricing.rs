@@ -0,0 +1,82 @@
+/// Per-million-token prices in USD for a model family
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    pub cache_creation: f64,
+    pub cache_read: f64,
+}
+
+/// Token counts needed for cost estimation
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenUsage {
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_creation_tokens: i64,
+    pub cache_read_tokens: i64,
+}
+
+/// Look up list prices by model name (substring match, most specific first)
+pub fn pricing_for_model(model: &str) -> Option<ModelPricing> {
+    let m = model.to_lowercase();
+    let p = |input, output, cache_creation, cache_read| ModelPricing { input, output, cache_creation, cache_read };
+
+    if m.contains("opus-4-5") || m.contains("opus-4.5") {
+        Some(p(5.0, 25.0, 6.25, 0.50))
+    } else if m.contains("opus") {
+        Some(p(15.0, 75.0, 18.75, 1.50))
+    } else if m.contains("sonnet") {
+        Some(p(3.0, 15.0, 3.75, 0.30))
+    } else if m.contains("haiku-4") {
+        Some(p(1.0, 5.0, 1.25, 0.10))
+    } else if m.contains("3-5-haiku") || m.contains("haiku-3-5") {
+        Some(p(0.80, 4.0, 1.0, 0.08))
+    } else if m.contains("haiku") {
+        Some(p(0.25, 1.25, 0.30, 0.03))
+    } else if m.contains("glm") && (m.contains("air") || m.contains("flash")) {
+        Some(p(0.20, 1.10, 0.0, 0.03))
+    } else if m.contains("glm") {
+        Some(p(0.60, 2.20, 0.0, 0.11))
+    } else {
+        None
+    }
+}
+
+/// Estimate request cost in USD (0 for unknown models)
+pub fn estimate_cost_usd(model: &str, usage: &TokenUsage) -> f64 {
+    let Some(price) = pricing_for_model(model) else {
+        return 0.0;
+    };
+    (usage.input_tokens as f64 * price.input
+        + usage.output_tokens as f64 * price.output
+        + usage.cache_creation_tokens as f64 * price.cache_creation
+        + usage.cache_read_tokens as f64 * price.cache_read)
+        / 1_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_cost() {
+        let usage = TokenUsage {
+            input_tokens: 1_000_000,
+            output_tokens: 100_000,
+            cache_creation_tokens: 0,
+            cache_read_tokens: 1_000_000,
+        };
+        let cost = estimate_cost_usd("claude-sonnet-4-20250514", &usage);
+        assert!((cost - (3.0 + 1.5 + 0.3)).abs() < 1e-9);
+
+        assert_eq!(estimate_cost_usd("some-local-model", &usage), 0.0);
+    }
+
+    #[test]
+    fn test_most_specific_match() {
+        assert_eq!(pricing_for_model("claude-opus-4-5-20251101").unwrap().input, 5.0);
+        assert_eq!(pricing_for_model("claude-opus-4-20250514").unwrap().input, 15.0);
+        assert_eq!(pricing_for_model("claude-3-5-haiku-20241022").unwrap().input, 0.80);
+        assert_eq!(pricing_for_model("claude-haiku-4-5-20251001").unwrap().input, 1.0);
+    }
+}
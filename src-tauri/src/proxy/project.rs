@@ -0,0 +1,88 @@
+use super::subagent::{content_text, system_text};
+use crate::models::ProjectInfo;
+use regex::Regex;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+struct Patterns {
+    working_dir: Regex,
+    git_repo: Regex,
+    branch: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        // "Working directory: /path" (<env> 블록) 또는 " - Primary working directory: /path"
+        working_dir: Regex::new(r"(?m)^\s*(?:-\s*)?(?:Primary working directory|Working directory):\s*(\S.*?)\s*$").unwrap(),
+        git_repo: Regex::new(r"(?m)(?:Is directory a git repo|Is a git repository):\s*(\w+)").unwrap(),
+        branch: Regex::new(r"(?m)^\s*Current branch:\s*(\S+)").unwrap(),
+    })
+}
+
+/// Extract the project from the system prompt, falling back to the first user message
+/// (newer Claude Code versions put the environment in a `<system-reminder>` there)
+pub fn detect(json: &Value) -> Option<ProjectInfo> {
+    let first_user_text = json
+        .get("messages")
+        .and_then(|m| m.as_array())
+        .and_then(|m| m.iter().find(|m| m.get("role").and_then(|r| r.as_str()) == Some("user")))
+        .and_then(|m| m.get("content"))
+        .and_then(content_text)
+        .unwrap_or_default();
+
+    [system_text(json), first_user_text].iter().find_map(|text| from_text(text))
+}
+
+fn from_text(text: &str) -> Option<ProjectInfo> {
+    let p = patterns();
+    let path = p.working_dir.captures(text)?.get(1)?.as_str();
+    let trimmed = path.trim_end_matches(['/', '\\']);
+    let project_path = if trimmed.is_empty() { path } else { trimmed }.to_string();
+    let is_git_repo = p
+        .git_repo
+        .captures(text)
+        .and_then(|c| c.get(1))
+        .map(|v| matches!(v.as_str().to_ascii_lowercase().as_str(), "yes" | "true"))
+        .unwrap_or(false);
+    let git_branch = p.branch.captures(text).and_then(|c| c.get(1)).map(|b| b.as_str().to_string());
+
+    Some(ProjectInfo { project_path, is_git_repo, git_branch })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_detect_from_env_block() {
+        let body = json!({
+            "system": [{"type": "text", "text": "You are Claude Code.\n<env>\nWorking directory: /Users/me/code/acme-web\nIs directory a git repo: Yes\nPlatform: darwin\n</env>\ngitStatus: This is the git status at the start of the conversation.\nCurrent branch: feature/billing\n\nMain branch (you will usually use this for PRs): main"}],
+            "messages": [{"role": "user", "content": "hi"}]
+        });
+        let project = detect(&body).unwrap();
+        assert_eq!(project.project_path, "/Users/me/code/acme-web");
+        assert_eq!(project.name(), "acme-web");
+        assert!(project.is_git_repo);
+        assert_eq!(project.git_branch.as_deref(), Some("feature/billing"));
+    }
+
+    #[test]
+    fn test_detect_from_user_reminder() {
+        let body = json!({
+            "system": "You are a helpful assistant",
+            "messages": [{"role": "user", "content": [
+                {"type": "text", "text": "<system-reminder>\n# Environment\n - Primary working directory: /srv/app/\n - Is a git repository: false\n</system-reminder>"},
+                {"type": "text", "text": "fix it"}
+            ]}]
+        });
+        let project = detect(&body).unwrap();
+        assert_eq!(project.project_path, "/srv/app");
+        assert_eq!(project.name(), "app");
+        assert!(!project.is_git_repo);
+        assert_eq!(project.git_branch, None);
+
+        assert_eq!(detect(&json!({"messages": [{"role": "user", "content": "hi"}]})), None);
+    }
+}
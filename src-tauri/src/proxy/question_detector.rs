@@ -1,47 +1,344 @@
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-/// Patterns that indicate an AI is asking a question
-const QUESTION_PATTERNS: &[&str] = &[
-    r"(?i)should i (?:proceed|continue)\??",
-    r"(?i)do you (?:want|approve|confirm)\??",
-    r"(?i)is (?:this|that) (?:okay|correct|right)\??",
-    r"(?i)shall i (?:proceed|continue|go ahead)\??",
-    r"(?i)would you like (?:me to|to)\??",
-    r"(?i)can i (?:proceed|continue|go ahead)\??",
-    r"(?i)are you (?:sure|okay with)\??",
-    r"(?i)please (?:confirm|approve|verify)",
-    r"(?i)\[y(?:es)?/n(?:o)?\]",
-    r"(?i)press.*(?:enter|y|n).*to.*(?:continue|proceed|confirm)",
+/// Claude Code tool that asks the user a multiple-choice question
+pub const ASK_USER_QUESTION_TOOL: &str = "AskUserQuestion";
+
+/// Built-in patterns that indicate an AI is asking a question: (name, language, pattern)
+const BUILTIN_RULES: &[(&str, &str, &str)] = &[
+    ("should_proceed", "en", r"(?i)should i (?:proceed|continue)\??"),
+    ("do_you_want", "en", r"(?i)do you (?:want|approve|confirm)\??"),
+    ("is_this_ok", "en", r"(?i)is (?:this|that) (?:okay|correct|right)\??"),
+    ("shall_i", "en", r"(?i)shall i (?:proceed|continue|go ahead)\??"),
+    ("would_you_like", "en", r"(?i)would you like (?:me to|to)\??"),
+    ("can_i_proceed", "en", r"(?i)can i (?:proceed|continue|go ahead)\??"),
+    ("are_you_sure", "en", r"(?i)are you (?:sure|okay with)\??"),
+    ("please_confirm", "en", r"(?i)please (?:confirm|approve|verify)"),
+    ("yes_no", "en", r"(?i)\[y(?:es)?/n(?:o)?\]"),
+    ("press_to_continue", "en", r"(?i)press.*(?:enter|y|n).*to.*(?:continue|proceed|confirm)"),
+    ("ko_proceed", "ko", r"(?:진행|계속|적용|수정|삭제|실행)(?:할까요|하시겠습니까|해도 (?:될까요|괜찮을까요|되나요))"),
+    ("ko_ok", "ko", r"(?:괜찮을까요|괜찮으신가요|맞을까요|맞나요)\?"),
+    ("ko_want", "ko", r"원하시(?:나요|는지|면 알려)"),
+    ("ko_confirm", "ko", r"(?:확인|승인)(?:해 ?주세요|해 ?주시겠어요|부탁드립니다)"),
+    ("ko_choose", "ko", r"(?:어떤|어느|어떻게)[^.?!\n]{0,40}(?:할까요|좋을까요|선호하시나요|원하시나요)"),
+];
+
+/// Built-in option extraction: (name, mode, pattern, separator)
+const BUILTIN_OPTION_RULES: &[(&str, OptionMode, &str, &str)] = &[
+    ("bracket", OptionMode::Split, r"\[([^\]]+/[^\]]+)\]", "/"),
+    ("paren", OptionMode::Split, r"\(([^()\s]+(?:/[^()\s]+)+)\)", "/"),
+    ("numbered", OptionMode::Each, r"(?m)^\s*\d+[.)]\s+(.+?)\s*$", ""),
 ];
 
+/// Text buffered per response stream
+const MAX_BUFFER_SIZE: usize = 4096; // Keep last 4KB for context
+
+/// Question rules file (`~/.sessioncast/question_rules.toml`), merged over the built-in rules by name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuestionRulesConfig {
+    /// Only run rules of these languages (empty = all)
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// Start from the built-in English and Korean rules (default true)
+    #[serde(default)]
+    pub include_builtin: Option<bool>,
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<QuestionRule>,
+    #[serde(default, rename = "options")]
+    pub option_rules: Vec<OptionRule>,
+    /// Options reported when none could be extracted, per language
+    #[serde(default)]
+    pub default_options: HashMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestionRule {
+    pub name: String,
+    #[serde(default = "default_language")]
+    pub language: String,
+    pub pattern: String,
+    /// Higher runs first (built-in rules are 0)
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionRule {
+    pub name: String,
+    /// Only for questions in this language (any when unset)
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Capture group 1 holds the option (`each`) or the option list (`split`)
+    pub pattern: String,
+    #[serde(default)]
+    pub mode: OptionMode,
+    /// List separator for `split`
+    #[serde(default = "default_separator")]
+    pub separator: String,
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OptionMode {
+    /// One match, its capture split by `separator` (`[Y/n]`)
+    #[default]
+    Split,
+    /// Every match is one option (numbered lists)
+    Each,
+}
+
+fn default_language() -> String {
+    "en".to_string()
+}
+
+fn default_separator() -> String {
+    "/".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
 /// Extracted question information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DetectedQuestion {
     pub question: String,
     pub context: String,
     pub options: Vec<String>,
+    /// Matching rule name, or `AskUserQuestion` for structured questions
+    pub rule: String,
+    pub language: String,
+}
+
+struct CompiledRule {
+    name: String,
+    language: String,
+    regex: Regex,
+}
+
+struct CompiledOptionRule {
+    language: Option<String>,
+    regex: Regex,
+    mode: OptionMode,
+    separator: String,
+}
+
+/// Compiled question and option rules, ordered by priority
+pub struct QuestionRules {
+    rules: Vec<CompiledRule>,
+    option_rules: Vec<CompiledOptionRule>,
+    default_options: HashMap<String, Vec<String>>,
+}
+
+impl QuestionRules {
+    pub fn default_config_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        PathBuf::from(home).join(".sessioncast").join("question_rules.toml")
+    }
+
+    /// Built-in rules only
+    pub fn builtin() -> Self {
+        Self::compile(&QuestionRulesConfig::default()).expect("built-in question rules compile")
+    }
+
+    /// Rules from a TOML file; built-in rules when the file does not exist
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::builtin());
+        }
+        let content = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        let config: QuestionRulesConfig =
+            toml::from_str(&content).map_err(|e| format!("Failed to parse {:?}: {}", path, e))?;
+        Self::compile(&config)
+    }
+
+    pub fn compile(config: &QuestionRulesConfig) -> Result<Self, String> {
+        let mut rules: Vec<QuestionRule> = Vec::new();
+        let mut option_rules: Vec<OptionRule> = Vec::new();
+        let mut default_options = HashMap::from([
+            ("en".to_string(), vec!["Yes".to_string(), "No".to_string()]),
+            ("ko".to_string(), vec!["예".to_string(), "아니오".to_string()]),
+        ]);
+        if config.include_builtin.unwrap_or(true) {
+            rules.extend(BUILTIN_RULES.iter().map(|(name, language, pattern)| QuestionRule {
+                name: name.to_string(),
+                language: language.to_string(),
+                pattern: pattern.to_string(),
+                priority: 0,
+                enabled: true,
+            }));
+            option_rules.extend(BUILTIN_OPTION_RULES.iter().map(|(name, mode, pattern, separator)| OptionRule {
+                name: name.to_string(),
+                language: None,
+                pattern: pattern.to_string(),
+                mode: *mode,
+                separator: separator.to_string(),
+                priority: 0,
+                enabled: true,
+            }));
+        }
+        // 같은 이름의 규칙은 파일 설정으로 교체 (enabled = false로 끄기)
+        for rule in &config.rules {
+            rules.retain(|r| r.name != rule.name);
+            rules.push(rule.clone());
+        }
+        for rule in &config.option_rules {
+            option_rules.retain(|r| r.name != rule.name);
+            option_rules.push(rule.clone());
+        }
+        default_options.extend(config.default_options.clone());
+
+        let language_enabled = |language: &str| config.languages.is_empty() || config.languages.iter().any(|l| l == language);
+        rules.retain(|r| r.enabled && language_enabled(&r.language));
+        option_rules.retain(|r| r.enabled);
+        // 우선순위 높은 순 (같으면 정의 순서)
+        rules.sort_by_key(|r| std::cmp::Reverse(r.priority));
+        option_rules.sort_by_key(|r| std::cmp::Reverse(r.priority));
+
+        let compile = |name: &str, pattern: &str| {
+            Regex::new(pattern).map_err(|e| format!("Invalid pattern in rule {}: {}", name, e))
+        };
+        Ok(Self {
+            rules: rules
+                .into_iter()
+                .map(|r| Ok(CompiledRule { regex: compile(&r.name, &r.pattern)?, name: r.name, language: r.language }))
+                .collect::<Result<_, String>>()?,
+            option_rules: option_rules
+                .into_iter()
+                .map(|r| {
+                    Ok(CompiledOptionRule {
+                        regex: compile(&r.name, &r.pattern)?,
+                        language: r.language,
+                        mode: r.mode,
+                        separator: r.separator,
+                    })
+                })
+                .collect::<Result<_, String>>()?,
+            default_options,
+        })
+    }
+
+    /// (rule name, language) of the active rules, in evaluation order
+    pub fn rule_names(&self) -> Vec<(String, String)> {
+        self.rules.iter().map(|r| (r.name.clone(), r.language.clone())).collect()
+    }
+
+    /// First question found in the text
+    pub fn detect(&self, text: &str) -> Option<DetectedQuestion> {
+        let (rule, m) = self.rules.iter().find_map(|rule| rule.regex.find(text).map(|m| (rule, m)))?;
+        let start = m.start();
+
+        // Extract the question text (sentence containing the match)
+        let question = extract_sentence(text, start, m.end());
+
+        // Extract context (text before the question)
+        let context_start = floor_char_boundary(text, start.saturating_sub(200));
+        let context = text[context_start..start].trim().to_string();
+
+        // Options usually follow the question, otherwise look at the text before it
+        let options = [&text[start..], &text[context_start..start]]
+            .iter()
+            .find_map(|t| self.extract_options(t, &rule.language))
+            .or_else(|| self.default_options.get(&rule.language).cloned())
+            .unwrap_or_default();
+
+        Some(DetectedQuestion {
+            question,
+            context,
+            options,
+            rule: rule.name.clone(),
+            language: rule.language.clone(),
+        })
+    }
+
+    fn extract_options(&self, text: &str, language: &str) -> Option<Vec<String>> {
+        self.option_rules
+            .iter()
+            .filter(|r| r.language.as_deref().is_none_or(|l| l == language))
+            .find_map(|rule| {
+                let options: Vec<String> = match rule.mode {
+                    OptionMode::Split => rule
+                        .regex
+                        .captures(text)
+                        .and_then(|caps| caps.get(1))
+                        .map(|m| m.as_str().split(rule.separator.as_str()).map(|o| o.trim().to_string()).collect())
+                        .unwrap_or_default(),
+                    OptionMode::Each => rule
+                        .regex
+                        .captures_iter(text)
+                        .filter_map(|caps| caps.get(1))
+                        .map(|m| m.as_str().trim().to_string())
+                        .collect(),
+                };
+                let options: Vec<String> = options.into_iter().filter(|o| !o.is_empty()).collect();
+                (!options.is_empty()).then_some(options)
+            })
+    }
+}
+
+/// Questions in an `AskUserQuestion` tool input (`{"questions": [{"question", "header", "options": [{"label"}]}]}`)
+pub fn questions_from_tool_input(input: &serde_json::Value) -> Vec<DetectedQuestion> {
+    let Some(questions) = input.get("questions").and_then(|q| q.as_array()) else {
+        return Vec::new();
+    };
+    questions
+        .iter()
+        .filter_map(|q| {
+            let question = q.get("question").and_then(|v| v.as_str())?;
+            let options = q
+                .get("options")
+                .and_then(|o| o.as_array())
+                .map(|options| {
+                    options
+                        .iter()
+                        .filter_map(|o| o.get("label").and_then(|l| l.as_str()).or_else(|| o.as_str()))
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default();
+            Some(DetectedQuestion {
+                question: question.to_string(),
+                context: q.get("header").and_then(|h| h.as_str()).unwrap_or_default().to_string(),
+                options,
+                rule: ASK_USER_QUESTION_TOOL.to_string(),
+                language: String::new(),
+            })
+        })
+        .collect()
 }
 
 /// Detects AI questions from streaming text
 pub struct QuestionDetector {
-    patterns: Vec<Regex>,
+    rules: Arc<QuestionRules>,
     text_buffer: Arc<Mutex<String>>,
     max_buffer_size: usize,
 }
 
 impl QuestionDetector {
+    /// Rules from `~/.sessioncast/question_rules.toml` (built-in rules if missing or invalid)
     pub fn new() -> Self {
-        let patterns = QUESTION_PATTERNS
-            .iter()
-            .filter_map(|p| Regex::new(p).ok())
-            .collect();
+        let rules = QuestionRules::load(&QuestionRules::default_config_path()).unwrap_or_else(|e| {
+            tracing::warn!("{}; using built-in question rules", e);
+            QuestionRules::builtin()
+        });
+        Self::with_rules(rules)
+    }
 
+    pub fn with_rules(rules: QuestionRules) -> Self {
         Self {
-            patterns,
+            rules: Arc::new(rules),
             text_buffer: Arc::new(Mutex::new(String::new())),
-            max_buffer_size: 4096, // Keep last 4KB for context
+            max_buffer_size: MAX_BUFFER_SIZE,
         }
     }
 
@@ -54,38 +351,14 @@ impl QuestionDetector {
 
         // Trim buffer if too large (keep end)
         if buffer.len() > self.max_buffer_size {
-            let start = buffer.len() - self.max_buffer_size;
+            let start = floor_char_boundary(&buffer, buffer.len() - self.max_buffer_size);
             *buffer = buffer[start..].to_string();
         }
 
-        // Check for question patterns
-        for pattern in &self.patterns {
-            if let Some(m) = pattern.find(&buffer) {
-                let start = m.start();
-                let end = m.end();
-
-                // Extract the question text (sentence containing the match)
-                let question = extract_sentence(&buffer, start, end);
-
-                // Extract context (text before the question)
-                let context_start = start.saturating_sub(200);
-                let context = buffer[context_start..start].trim().to_string();
-
-                // Extract options if present
-                let options = extract_options(&buffer[start..]);
-
-                // Clear buffer after detection to avoid duplicate detections
-                buffer.clear();
-
-                return Some(DetectedQuestion {
-                    question,
-                    context,
-                    options,
-                });
-            }
-        }
-
-        None
+        let detected = self.rules.detect(&buffer)?;
+        // Clear buffer after detection to avoid duplicate detections
+        buffer.clear();
+        Some(detected)
     }
 
     /// Reset the text buffer
@@ -104,70 +377,51 @@ impl Default for QuestionDetector {
 impl Clone for QuestionDetector {
     fn clone(&self) -> Self {
         Self {
-            patterns: self.patterns.clone(),
+            rules: self.rules.clone(),
             text_buffer: Arc::new(Mutex::new(String::new())),
             max_buffer_size: self.max_buffer_size,
         }
     }
 }
 
+/// Largest char boundary at or before `index` (Korean text is multi-byte)
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
 /// Extract the sentence containing the match
 fn extract_sentence(text: &str, start: usize, end: usize) -> String {
     // Find sentence boundaries
     let sentence_start = text[..start]
-        .rfind(|c| c == '.' || c == '!' || c == '?' || c == '\n')
+        .rfind(['.', '!', '?', '\n'])
         .map(|i| i + 1)
         .unwrap_or(0);
 
     let sentence_end = text[end..]
-        .find(|c| c == '.' || c == '!' || c == '?' || c == '\n')
+        .find(['.', '!', '?', '\n'])
         .map(|i| end + i + 1)
         .unwrap_or(text.len());
 
     text[sentence_start..sentence_end].trim().to_string()
 }
 
-/// Extract options from text (e.g., [Y/N], yes/no)
-fn extract_options(text: &str) -> Vec<String> {
-    let mut options = Vec::new();
-
-    // Check for [Y/N] pattern
-    if let Ok(re) = Regex::new(r"\[([^\]]+)\]") {
-        if let Some(caps) = re.captures(text) {
-            if let Some(m) = caps.get(1) {
-                for opt in m.as_str().split('/') {
-                    let opt = opt.trim().to_string();
-                    if !opt.is_empty() {
-                        options.push(opt);
-                    }
-                }
-            }
-        }
-    }
-
-    // Default options if none found
-    if options.is_empty() {
-        options.push("Yes".to_string());
-        options.push("No".to_string());
-    }
-
-    options
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[tokio::test]
     async fn test_detect_should_proceed() {
-        let detector = QuestionDetector::new();
+        let detector = QuestionDetector::with_rules(QuestionRules::builtin());
         let result = detector.process_text("I found the bug. Should I proceed with the fix?").await;
         assert!(result.is_some());
     }
 
     #[tokio::test]
     async fn test_detect_yn_pattern() {
-        let detector = QuestionDetector::new();
+        let detector = QuestionDetector::with_rules(QuestionRules::builtin());
         let result = detector.process_text("Press [Y/N] to continue").await;
         assert!(result.is_some());
         let q = result.unwrap();
@@ -176,8 +430,92 @@ mod tests {
 
     #[tokio::test]
     async fn test_no_question() {
-        let detector = QuestionDetector::new();
+        let detector = QuestionDetector::with_rules(QuestionRules::builtin());
         let result = detector.process_text("I completed the task successfully.").await;
         assert!(result.is_none());
     }
+
+    #[test]
+    fn test_korean_and_numbered_options() {
+        let rules = QuestionRules::builtin();
+        let q = rules.detect("버그를 찾았습니다. 수정을 진행할까요?").unwrap();
+        assert_eq!(q.rule, "ko_proceed");
+        assert_eq!(q.question, "수정을 진행할까요?");
+        assert_eq!(q.options, vec!["예", "아니오"]);
+
+        let q = rules
+            .detect("두 가지 방법이 있습니다.\n1. SQLite 유지\n2. Postgres로 이전\n어떤 방법으로 진행할까요?")
+            .unwrap();
+        assert_eq!(q.options, vec!["SQLite 유지", "Postgres로 이전"]);
+    }
+
+    #[test]
+    fn test_config_rules() {
+        let config: QuestionRulesConfig = toml::from_str(
+            r#"
+            languages = ["ko"]
+
+            [[rule]]
+            name = "ko_proceed"
+            language = "ko"
+            pattern = "진행할까요"
+            enabled = false
+
+            [[rule]]
+            name = "ko_ok_to_go"
+            language = "ko"
+            priority = 10
+            pattern = "가도 될까요"
+
+            [[options]]
+            name = "choices"
+            mode = "split"
+            pattern = "선택: (.+)"
+            separator = ","
+            priority = 5
+
+            [default_options]
+            ko = ["네", "아니요"]
+            "#,
+        )
+        .unwrap();
+        let rules = QuestionRules::compile(&config).unwrap();
+        assert_eq!(rules.rule_names()[0].0, "ko_ok_to_go");
+        assert!(rules.detect("Should I proceed?").is_none());
+        assert!(rules.detect("진행할까요?").is_none());
+        assert_eq!(rules.detect("다음 단계로 가도 될까요? 선택: 예, 나중에").unwrap().options, vec!["예", "나중에"]);
+        assert_eq!(rules.detect("다음 단계로 가도 될까요?").unwrap().options, vec!["네", "아니요"]);
+
+        let bad = QuestionRulesConfig {
+            rules: vec![QuestionRule {
+                name: "broken".to_string(),
+                language: "en".to_string(),
+                pattern: "(".to_string(),
+                priority: 0,
+                enabled: true,
+            }],
+            ..Default::default()
+        };
+        assert!(QuestionRules::compile(&bad).err().unwrap().contains("broken"));
+    }
+
+    #[test]
+    fn test_questions_from_tool_input() {
+        let input = serde_json::json!({
+            "questions": [{
+                "question": "Which database should we use?",
+                "header": "Database",
+                "multiSelect": false,
+                "options": [
+                    {"label": "SQLite", "description": "Embedded"},
+                    {"label": "Postgres", "description": "Server"}
+                ]
+            }]
+        });
+        let questions = questions_from_tool_input(&input);
+        assert_eq!(questions.len(), 1);
+        assert_eq!(questions[0].question, "Which database should we use?");
+        assert_eq!(questions[0].options, vec!["SQLite", "Postgres"]);
+        assert_eq!(questions[0].rule, ASK_USER_QUESTION_TOOL);
+    }
 }
@@ -0,0 +1,189 @@
+use super::hooks::CompactionInjectorHook;
+use super::subagent::system_text;
+use crate::storage::database::KindRoute;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// System prompt fragments of Claude Code's topic/title generation calls
+const TITLE_GENERATION_MARKERS: &[&str] = &[
+    "Analyze if this message indicates a new conversation topic",
+    "isNewTopic",
+    "Summarize this coding conversation in under 50 characters",
+    "Generate a concise, sentence-case title",
+];
+
+/// Prompt fragment of Claude Code's WebFetch summarization call
+const WEB_FETCH_MARKER: &str = "Web page content:";
+
+/// What a proxied request is for, so usage and routing can tell real turns from background calls
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestKind {
+    /// A turn of the main conversation
+    #[default]
+    MainTurn,
+    /// A turn of a Task subagent
+    Subagent,
+    /// Topic / session title generation (usually Haiku)
+    TitleGeneration,
+    /// Conversation summarization for `/compact` or auto-compaction
+    Compaction,
+    /// `max_tokens: 1` quota/connectivity check
+    QuotaProbe,
+    /// WebFetch page summarization
+    WebFetchSummary,
+    /// Anything that isn't a Messages API call (count_tokens, models, ...)
+    Other,
+}
+
+impl RequestKind {
+    pub const ALL: [RequestKind; 7] = [
+        RequestKind::MainTurn,
+        RequestKind::Subagent,
+        RequestKind::TitleGeneration,
+        RequestKind::Compaction,
+        RequestKind::QuotaProbe,
+        RequestKind::WebFetchSummary,
+        RequestKind::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RequestKind::MainTurn => "main_turn",
+            RequestKind::Subagent => "subagent",
+            RequestKind::TitleGeneration => "title_generation",
+            RequestKind::Compaction => "compaction",
+            RequestKind::QuotaProbe => "quota_probe",
+            RequestKind::WebFetchSummary => "web_fetch_summary",
+            RequestKind::Other => "other",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == s)
+    }
+
+    /// Auxiliary calls Claude Code makes on its own (not user-visible turns)
+    pub fn is_background(&self) -> bool {
+        !matches!(self, RequestKind::MainTurn | RequestKind::Subagent)
+    }
+}
+
+/// Classify a request from its path and body; `is_subagent` comes from subagent detection
+pub fn classify(path: &str, body: &str, json: &Value, is_subagent: bool) -> RequestKind {
+    if !path.ends_with("/v1/messages") {
+        return RequestKind::Other;
+    }
+    if CompactionInjectorHook::is_compaction_request(body) {
+        return RequestKind::Compaction;
+    }
+
+    let max_tokens = json.get("max_tokens").and_then(|v| v.as_i64());
+    let has_tools = json
+        .get("tools")
+        .and_then(|v| v.as_array())
+        .map(|t| !t.is_empty())
+        .unwrap_or(false);
+    if max_tokens == Some(1) && !has_tools {
+        return RequestKind::QuotaProbe;
+    }
+
+    let system = system_text(json);
+    if TITLE_GENERATION_MARKERS.iter().any(|m| system.contains(m)) {
+        return RequestKind::TitleGeneration;
+    }
+    if is_subagent {
+        return RequestKind::Subagent;
+    }
+    if !has_tools && body.contains(WEB_FETCH_MARKER) {
+        return RequestKind::WebFetchSummary;
+    }
+
+    RequestKind::MainTurn
+}
+
+/// Request-kind routes of the running proxy, kept in memory so routing doesn't read config per request
+#[derive(Debug, Clone, Default)]
+pub struct KindRouter {
+    routes: Arc<RwLock<HashMap<RequestKind, KindRoute>>>,
+}
+
+impl KindRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the routes (applies to the next request)
+    /// The `subagent` route is skipped: subagent sessions are routed once, when they are detected
+    pub async fn configure(&self, routes: &HashMap<String, KindRoute>) {
+        *self.routes.write().await = routes
+            .iter()
+            .filter_map(|(kind, route)| RequestKind::parse(kind).map(|k| (k, route.clone())))
+            .filter(|(kind, _)| *kind != RequestKind::Subagent)
+            .collect();
+    }
+
+    pub async fn route(&self, kind: RequestKind) -> Option<KindRoute> {
+        self.routes.read().await.get(&kind).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn classify_json(json: Value, is_subagent: bool) -> RequestKind {
+        classify("/v1/messages", &json.to_string(), &json, is_subagent)
+    }
+
+    #[test]
+    fn test_classify() {
+        let main = json!({"tools": [{"name": "Task"}], "messages": [{"role": "user", "content": "hi"}]});
+        assert_eq!(classify_json(main.clone(), false), RequestKind::MainTurn);
+        assert_eq!(classify_json(main.clone(), true), RequestKind::Subagent);
+        assert_eq!(classify("/v1/messages/count_tokens", "{}", &main, false), RequestKind::Other);
+
+        let quota = json!({"max_tokens": 1, "messages": [{"role": "user", "content": "quota"}]});
+        assert_eq!(classify_json(quota, false), RequestKind::QuotaProbe);
+
+        let title = json!({
+            "system": [{"type": "text", "text": "Analyze if this message indicates a new conversation topic."}],
+            "messages": [{"role": "user", "content": "fix the login bug"}]
+        });
+        assert_eq!(classify_json(title, false), RequestKind::TitleGeneration);
+
+        let compaction = json!({"messages": [{"role": "user", "content": "Your task is to create a detailed summary of the conversation so far"}]});
+        assert_eq!(classify_json(compaction, false), RequestKind::Compaction);
+
+        let fetch = json!({"messages": [{"role": "user", "content": "Web page content:\n---\n<html>"}]});
+        assert_eq!(classify_json(fetch, false), RequestKind::WebFetchSummary);
+    }
+
+    #[tokio::test]
+    async fn test_kind_router_skips_subagent() {
+        let route = |id: &str| KindRoute { account_id: id.to_string(), model_override: None };
+        let router = KindRouter::new();
+        router
+            .configure(&HashMap::from([
+                ("title_generation".to_string(), route("glm")),
+                ("subagent".to_string(), route("glm")),
+                ("nope".to_string(), route("glm")),
+            ]))
+            .await;
+        assert_eq!(router.route(RequestKind::TitleGeneration).await.unwrap().account_id, "glm");
+        assert!(router.route(RequestKind::Subagent).await.is_none());
+        assert!(router.route(RequestKind::MainTurn).await.is_none());
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        for kind in RequestKind::ALL {
+            assert_eq!(RequestKind::parse(kind.as_str()), Some(kind));
+        }
+        assert_eq!(RequestKind::parse("nope"), None);
+    }
+}
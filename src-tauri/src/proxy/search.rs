@@ -0,0 +1,92 @@
+use super::FileLoggerHook;
+use crate::storage::database::{SearchHit, SearchQuery};
+use crate::storage::Database;
+use anyhow::{bail, Result};
+use std::path::PathBuf;
+
+/// Search transcripts with user-typed `query.query` and attach FileLoggerHook log files to the hits
+pub async fn search(db: &Database, mut query: SearchQuery) -> Result<Vec<SearchHit>> {
+    query.query = fts_query(&query.query);
+    if query.query.is_empty() {
+        bail!("Search query is empty");
+    }
+    let mut hits = db.search_transcripts(&query).await?;
+
+    let log_dir = db
+        .get_config("hooks_log_dir")
+        .await?
+        .map(PathBuf::from)
+        .unwrap_or_else(FileLoggerHook::default_log_dir);
+    let logger = FileLoggerHook::new(log_dir);
+    for hit in &mut hits {
+        if let Some(request_id) = &hit.request_id {
+            hit.log_file = logger
+                .find_log_file(&hit.session_id, request_id)
+                .await
+                .map(|p| p.to_string_lossy().into_owned());
+        }
+    }
+    Ok(hits)
+}
+
+/// Turn a search box query into an FTS5 match expression.
+///
+/// Words are matched literally (so `migration.rs` or `src/db` don't trip FTS5 syntax), `"..."` is a phrase,
+/// a trailing `*` is a prefix match, `tool:Edit` matches the tool name and `OR` / `NOT` are kept as operators.
+pub fn fts_query(input: &str) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+    let mut terms = Vec::new();
+    let mut rest = input.trim();
+
+    while !rest.is_empty() {
+        let (token, is_phrase, next) = if let Some(stripped) = rest.strip_prefix('"') {
+            match stripped.find('"') {
+                Some(end) => (&stripped[..end], true, &stripped[end + 1..]),
+                None => (stripped, true, ""),
+            }
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            (&rest[..end], false, &rest[end..])
+        };
+        rest = next.trim_start();
+
+        if token.is_empty() {
+            continue;
+        }
+        if is_phrase {
+            terms.push(quote(token));
+        } else if matches!(token, "OR" | "NOT" | "AND") {
+            terms.push(token.to_string());
+        } else if let Some(tool) = token.strip_prefix("tool:").filter(|t| !t.is_empty()) {
+            terms.push(format!("tool_name:{}", quote(tool)));
+        } else if let Some(prefix) = token.strip_suffix('*').filter(|p| !p.is_empty()) {
+            terms.push(format!("{}*", quote(prefix)));
+        } else {
+            terms.push(quote(token));
+        }
+    }
+
+    // 앞뒤에 남은 연산자는 FTS5 문법 오류가 되므로 제거
+    while terms.first().is_some_and(|t| matches!(t.as_str(), "OR" | "NOT" | "AND")) {
+        terms.remove(0);
+    }
+    while terms.last().is_some_and(|t| matches!(t.as_str(), "OR" | "NOT" | "AND")) {
+        terms.pop();
+    }
+    terms.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(fts_query("migration.rs"), r#""migration.rs""#);
+        assert_eq!(fts_query("  edit   tool:Edit  "), r#""edit" tool_name:"Edit""#);
+        assert_eq!(fts_query(r#""create table" OR migrat*"#), r#""create table" OR "migrat"*"#);
+        assert_eq!(fts_query(r#"say "hi"#), r#""say" "hi""#);
+        assert_eq!(fts_query("NOT foo OR"), r#""foo""#);
+        assert_eq!(fts_query("  "), "");
+    }
+}
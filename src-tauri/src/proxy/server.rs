@@ -1,15 +1,36 @@
-use super::hooks::{CompactionConfig, CompactionInjectorHook, CustomTaskHook, FileLoggerHook, HookRegistry, RequestContext, ResponseBuilder};
-use super::question_detector::QuestionDetector;
+use super::admin_api;
+use super::alerts::{AlertEngine, AlertTrigger};
+use super::errors::{parse_error_body, parse_error_from_sse, retry_after_secs};
+use super::events::{EventBus, ProxyEventKind};
+use super::hooks::{CompactionConfig, CompactionInjectorHook, ContentBlock, CustomTaskHook, FileLoggerHook, HookRegistry, RequestContext, ResponseBuilder};
+use super::latency::RequestTimings;
+use super::metrics::Metrics;
+use super::pricing::{estimate_cost_usd, TokenUsage};
+use super::project;
+use super::question_detector::{questions_from_tool_input, DetectedQuestion, QuestionDetector, ASK_USER_QUESTION_TOOL};
+use super::request_kind::{classify, KindRouter, RequestKind};
+use super::session_resolver::{metadata_user_id_from_body, SessionHints, SessionResolverChain, SessionSource};
+use super::session_lifecycle::SessionTracker;
 use super::step_tracker::StepTracker;
+use super::subagent::{child_session_id, RequestShape, SubagentMatch, SubagentTracker, TASK_TOOL_NAMES};
+use super::timeouts::{idle_timeout_sse_event, with_idle_timeout, ClientPool};
+use super::tool_usage;
+use super::transcript::{first_message_hash, message_blocks, plan_append};
 use super::webhook::{AIQuestionData, SessionCompleteData, UsageData, WebhookClient};
+use crate::storage::database::{ErrorEntry, UsageEntry};
+use crate::models::{Account, TranscriptAppend};
 use crate::storage::Database;
 use anyhow::Result;
 use axum::{
     body::Body,
-    extract::{Request, State},
+    extract::{Query, Request, State},
     http::{Method, StatusCode},
-    response::Response,
-    routing::any,
+    middleware,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Response,
+    },
+    routing::{any, get},
     Router,
 };
 use futures::StreamExt;
@@ -21,7 +42,6 @@ use tower_http::cors::CorsLayer;
 // 상수 정의
 const MAX_REQUEST_BODY_SIZE: usize = 100 * 1024 * 1024; // 100MB
 const MAX_CONCURRENT_DB_TASKS: usize = 10; // 동시 DB 작업 제한
-const REQUEST_TIMEOUT_SECS: u64 = 300; // API 요청 타임아웃 (5분)
 
 // 요청에서 모델 및 마지막 메시지 정보 추출
 #[derive(Debug, Clone, Default)]
@@ -35,9 +55,33 @@ struct RequestInfo {
 struct UsageInfo {
     input_tokens: i64,
     output_tokens: i64,
+    cache_creation_tokens: i64,
+    cache_read_tokens: i64,
     stop_reason: Option<String>,
 }
 
+impl UsageInfo {
+    fn from_json(usage: &serde_json::Value, stop_reason: Option<String>) -> Self {
+        let field = |name: &str| usage.get(name).and_then(|v| v.as_i64()).unwrap_or(0);
+        Self {
+            input_tokens: field("input_tokens"),
+            output_tokens: field("output_tokens"),
+            cache_creation_tokens: field("cache_creation_input_tokens"),
+            cache_read_tokens: field("cache_read_input_tokens"),
+            stop_reason,
+        }
+    }
+
+    fn token_usage(&self) -> TokenUsage {
+        TokenUsage {
+            input_tokens: self.input_tokens,
+            output_tokens: self.output_tokens,
+            cache_creation_tokens: self.cache_creation_tokens,
+            cache_read_tokens: self.cache_read_tokens,
+        }
+    }
+}
+
 fn parse_request_info(body: &[u8]) -> RequestInfo {
     if let Ok(json) = serde_json::from_slice::<serde_json::Value>(body) {
         let model = json.get("model").and_then(|v| v.as_str()).unwrap_or("unknown").to_string();
@@ -148,24 +192,10 @@ fn format_error_response(
     status_code: u16,
     error_body: &[u8],
     account_name: &str,
-    target_url: &str,
+    _target_url: &str,
 ) -> bytes::Bytes {
-    let error_text = String::from_utf8_lossy(error_body);
-
     // 원본 에러 메시지 추출 시도
-    let (error_type, error_message) = if let Ok(json) = serde_json::from_slice::<serde_json::Value>(error_body) {
-        let err_type = json.get("error")
-            .and_then(|e| e.get("type"))
-            .and_then(|t| t.as_str())
-            .unwrap_or("unknown_error");
-        let err_msg = json.get("error")
-            .and_then(|e| e.get("message"))
-            .and_then(|m| m.as_str())
-            .unwrap_or(&error_text);
-        (err_type.to_string(), err_msg.to_string())
-    } else {
-        ("unknown_error".to_string(), error_text.to_string())
-    };
+    let (error_type, error_message) = parse_error_body(error_body);
 
     // 에러 타입별 이모지와 설명
     let (emoji, description) = match error_type.as_str() {
@@ -247,8 +277,7 @@ fn parse_usage_from_sse(data: &str) -> Option<UsageInfo> {
     // SSE 이벤트에서 usage 정보 추출
     // event: message_delta 또는 message_stop에 usage가 포함됨
     for line in data.lines() {
-        if line.starts_with("data: ") {
-            let json_str = &line[6..];
+        if let Some(json_str) = line.strip_prefix("data: ") {
             if let Ok(json) = serde_json::from_str::<serde_json::Value>(json_str) {
                 // message_delta 이벤트: stop_reason과 usage 모두 포함
                 if json.get("type").and_then(|v| v.as_str()) == Some("message_delta") {
@@ -258,11 +287,7 @@ fn parse_usage_from_sse(data: &str) -> Option<UsageInfo> {
                         .map(|s| s.to_string());
 
                     if let Some(usage) = json.get("usage") {
-                        return Some(UsageInfo {
-                            input_tokens: usage.get("input_tokens").and_then(|v| v.as_i64()).unwrap_or(0),
-                            output_tokens: usage.get("output_tokens").and_then(|v| v.as_i64()).unwrap_or(0),
-                            stop_reason,
-                        });
+                        return Some(UsageInfo::from_json(usage, stop_reason));
                     }
                 }
                 // message_stop 이벤트의 amazon/anthropic 형식
@@ -273,11 +298,7 @@ fn parse_usage_from_sse(data: &str) -> Option<UsageInfo> {
                             .map(|s| s.to_string());
 
                         if let Some(usage) = message.get("usage") {
-                            return Some(UsageInfo {
-                                input_tokens: usage.get("input_tokens").and_then(|v| v.as_i64()).unwrap_or(0),
-                                output_tokens: usage.get("output_tokens").and_then(|v| v.as_i64()).unwrap_or(0),
-                                stop_reason,
-                            });
+                            return Some(UsageInfo::from_json(usage, stop_reason));
                         }
                     }
                 }
@@ -292,8 +313,7 @@ fn parse_text_from_sse(data: &str) -> Option<String> {
     let mut text = String::new();
 
     for line in data.lines() {
-        if line.starts_with("data: ") {
-            let json_str = &line[6..];
+        if let Some(json_str) = line.strip_prefix("data: ") {
             if let Ok(json) = serde_json::from_str::<serde_json::Value>(json_str) {
                 // content_block_delta 이벤트에서 텍스트 추출
                 if json.get("type").and_then(|v| v.as_str()) == Some("content_block_delta") {
@@ -314,25 +334,34 @@ fn parse_text_from_sse(data: &str) -> Option<String> {
     }
 }
 
+/// Check whether an SSE chunk contains a content_block_delta event (first token marker)
+fn contains_content_delta(data: &str) -> bool {
+    data.lines().any(|line| {
+        line.starts_with("data: ") && line.contains("\"content_block_delta\"")
+    })
+}
+
 /// Tool use info extracted from SSE
 #[derive(Debug, Clone)]
 struct ToolUseInfo {
+    id: Option<String>,
     name: String,
     input: Option<serde_json::Value>,
 }
 
 /// Extract tool_use from SSE streaming response (content_block_start event)
-fn parse_tool_use_from_sse(data: &str) -> Option<ToolUseInfo> {
+fn parse_tool_uses_from_sse(data: &str) -> Vec<ToolUseInfo> {
+    let mut tools = Vec::new();
     for line in data.lines() {
-        if line.starts_with("data: ") {
-            let json_str = &line[6..];
+        if let Some(json_str) = line.strip_prefix("data: ") {
             if let Ok(json) = serde_json::from_str::<serde_json::Value>(json_str) {
                 // content_block_start with type: tool_use
                 if json.get("type").and_then(|v| v.as_str()) == Some("content_block_start") {
                     if let Some(content_block) = json.get("content_block") {
                         if content_block.get("type").and_then(|v| v.as_str()) == Some("tool_use") {
                             if let Some(name) = content_block.get("name").and_then(|v| v.as_str()) {
-                                return Some(ToolUseInfo {
+                                tools.push(ToolUseInfo {
+                                    id: content_block.get("id").and_then(|v| v.as_str()).map(str::to_string),
                                     name: name.to_string(),
                                     input: content_block.get("input").cloned(),
                                 });
@@ -343,74 +372,134 @@ fn parse_tool_use_from_sse(data: &str) -> Option<ToolUseInfo> {
             }
         }
     }
-    None
+    tools
 }
 
 pub struct ProxyServer {
     db: Arc<Database>,
-    client: reqwest::Client,
+    clients: ClientPool,
     webhook: WebhookClient,
     question_detector: QuestionDetector,
     step_tracker: StepTracker,
+    sessions: SessionTracker,
+    subagent_tracker: SubagentTracker,
+    kind_router: KindRouter,
     hook_registry: HookRegistry,
     custom_task_hook: Arc<CustomTaskHook>,
+    metrics: Metrics,
+    events: EventBus,
     shutdown_tx: Option<tokio::sync::oneshot::Sender<()>>,
+    outbox_worker: Option<tokio::task::JoinHandle<()>>,
+    session_sweeper: Option<tokio::task::JoinHandle<()>>,
 }
 
 #[derive(Clone)]
 struct ProxyState {
     db: Arc<Database>,
-    client: reqwest::Client,
+    clients: ClientPool, // connect timeout별 HTTP 클라이언트
     webhook: WebhookClient,
     question_detector: QuestionDetector,
     step_tracker: StepTracker,
+    sessions: SessionTracker,
+    subagent_tracker: SubagentTracker,
+    kind_router: KindRouter,
     hook_registry: HookRegistry,
     custom_task_hook: Arc<CustomTaskHook>,
+    metrics: Metrics,
+    events: EventBus,
+    alerts: AlertEngine,
+    session_resolver: Arc<SessionResolverChain>,
     db_task_semaphore: Arc<Semaphore>, // DB 작업 동시 실행 제한
 }
 
 impl ProxyServer {
     pub fn new(db: Arc<Database>) -> Self {
-        // 타임아웃이 설정된 HTTP 클라이언트 생성
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS))
-            .connect_timeout(std::time::Duration::from_secs(30))
-            .build()
-            .unwrap_or_else(|_| reqwest::Client::new());
-
         // Initialize CustomTaskHook with default config path
         let custom_task_hook = Arc::new(CustomTaskHook::new(CustomTaskHook::default_config_path()));
 
+        let metrics = Metrics::new();
+        let webhook = WebhookClient::new().with_metrics(metrics.clone()).with_outbox(db.clone());
+        let step_tracker = StepTracker::new().with_db(db.clone());
+        let events = EventBus::new();
+        let sessions = SessionTracker::new(db.clone(), events.clone());
+
         Self {
             db,
-            client,
-            webhook: WebhookClient::new(),
+            clients: ClientPool::new(),
+            webhook,
             question_detector: QuestionDetector::new(),
-            step_tracker: StepTracker::new(),
-            hook_registry: HookRegistry::new(),
+            step_tracker,
+            sessions,
+            subagent_tracker: SubagentTracker::new(),
+            kind_router: KindRouter::new(),
+            hook_registry: HookRegistry::new().with_metrics(metrics.clone()),
             custom_task_hook,
+            metrics,
+            events,
             shutdown_tx: None,
+            outbox_worker: None,
+            session_sweeper: None,
         }
     }
 
+    /// Webhook sinks of the running proxy (reconfigure without a restart)
+    pub fn webhook(&self) -> &WebhookClient {
+        &self.webhook
+    }
+
+    /// Step tracker of the running proxy (reconfigure without a restart)
+    pub fn step_tracker(&self) -> &StepTracker {
+        &self.step_tracker
+    }
+
+    /// Request-kind routes of the running proxy (reconfigure without a restart)
+    pub fn kind_router(&self) -> &KindRouter {
+        &self.kind_router
+    }
+
+    /// Session lifecycle tracker of the running proxy
+    pub fn sessions(&self) -> &SessionTracker {
+        &self.sessions
+    }
+
+    /// Share an event bus with the caller (e.g. to mirror events to the Tauri frontend)
+    pub fn with_event_bus(mut self, events: EventBus) -> Self {
+        self.sessions = SessionTracker::new(self.db.clone(), events.clone());
+        self.events = events;
+        self
+    }
+
     pub async fn start(&mut self, port: u16) -> Result<()> {
         let (tx, rx) = tokio::sync::oneshot::channel();
         self.shutdown_tx = Some(tx);
 
-        // Load webhook configuration from DB
-        let webhook_url = self.db.get_config("threadcast_webhook_url").await.ok().flatten();
-        let webhook_enabled = self.db.get_config("threadcast_webhook_enabled").await
-            .ok()
-            .flatten()
-            .map(|v| v == "true")
-            .unwrap_or(false);
-        self.webhook.configure(webhook_url.clone(), webhook_enabled).await;
+        // Load webhook sinks from DB
+        match self.db.get_webhook_sinks().await {
+            Ok(sinks) => self.webhook.configure(sinks).await,
+            Err(e) => tracing::warn!("Failed to load webhook sinks: {}", e),
+        }
+        // 이전 실행에서 남은 이벤트도 이어서 전달
+        self.outbox_worker = self.webhook.start_outbox_worker();
+
+        // Load tool-to-phase mapping from DB
+        match self.db.get_step_config().await {
+            Ok(config) => {
+                if let Err(e) = self.step_tracker.configure(&config).await {
+                    tracing::warn!("Invalid step config, using built-in mapping: {}", e);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to load step config: {}", e),
+        }
+        // Load request-kind routes from DB
+        match self.db.get_request_kind_routes().await {
+            Ok(routes) => self.kind_router.configure(&routes).await,
+            Err(e) => tracing::warn!("Failed to load request kind routes: {}", e),
+        }
 
-        tracing::info!(
-            "Webhook configured: enabled={}, url={:?}",
-            webhook_enabled,
-            webhook_url
-        );
+        // 이전 실행에서 열린 채로 남은 단계 구간 종료
+        if let Err(e) = self.db.close_stale_step_spans().await {
+            tracing::warn!("Failed to close stale step spans: {}", e);
+        }
 
         // Load hook configuration from DB
         let hooks_enabled = self.db.get_config("hooks_enabled").await
@@ -490,19 +579,42 @@ impl ProxyServer {
         let task_count = self.custom_task_hook.list_tasks().await.len();
         tracing::info!("CustomTaskHook loaded {} tasks from {:?}", task_count, CustomTaskHook::default_config_path());
 
+        // 세션 resolver 체인 (config `session_headers`: 추가로 확인할 헤더 목록, 쉼표 구분)
+        let session_headers = self.db.get_config("session_headers").await.ok().flatten();
+        let session_resolver = SessionResolverChain::from_config(session_headers.as_deref());
+
+        // 조용한 세션을 idle/ended로 전환
+        self.session_sweeper = Some(self.sessions.start_sweeper());
+
         let state = ProxyState {
             db: self.db.clone(),
-            client: self.client.clone(),
+            clients: self.clients.clone(),
             webhook: self.webhook.clone(),
             question_detector: self.question_detector.clone(),
             step_tracker: self.step_tracker.clone(),
+            sessions: self.sessions.clone(),
+            subagent_tracker: self.subagent_tracker.clone(),
+            kind_router: self.kind_router.clone(),
             hook_registry: self.hook_registry.clone(),
             custom_task_hook: self.custom_task_hook.clone(),
+            metrics: self.metrics.clone(),
+            events: self.events.clone(),
+            alerts: AlertEngine::new(self.db.clone(), self.events.clone()),
+            session_resolver: Arc::new(session_resolver),
             db_task_semaphore: Arc::new(Semaphore::new(MAX_CONCURRENT_DB_TASKS)),
         };
 
         let app = Router::new()
             .route("/_swiftcast/threadcast/mapping", axum::routing::post(register_threadcast_mapping))
+            .route("/_swiftcast/session", axum::routing::post(register_session))
+            .merge(
+                // 세션 활동이 노출되므로 admin 토큰 필요 (permissive CORS로 임의 웹페이지가 읽지 못하도록)
+                Router::new()
+                    .route("/_swiftcast/metrics", get(metrics_handler))
+                    .route("/_swiftcast/events", get(events_handler))
+                    .route_layer(middleware::from_fn_with_state(self.db.clone(), admin_api::require_token)),
+            )
+            .nest("/_swiftcast/api/v1", admin_api::router(self.db.clone(), self.webhook.clone(), self.step_tracker.clone(), self.sessions.clone(), self.kind_router.clone()))
             .route("/*path", any(proxy_handler))
             .layer(CorsLayer::permissive())
             .with_state(state);
@@ -531,6 +643,12 @@ impl ProxyServer {
         if let Some(tx) = self.shutdown_tx.take() {
             let _ = tx.send(());
         }
+        if let Some(worker) = self.outbox_worker.take() {
+            worker.abort();
+        }
+        if let Some(sweeper) = self.session_sweeper.take() {
+            sweeper.abort();
+        }
         Ok(())
     }
 }
@@ -550,6 +668,10 @@ async fn proxy_handler(
         return handle_threadcast_mapping_internal(state, req).await;
     }
 
+    // 응답 스트림이 끝날 때까지 in-flight로 집계
+    let in_flight = state.metrics.start_request();
+    let received_at = std::time::Instant::now();
+
     let query = req.uri().query().unwrap_or("").to_string();
     let full_path = if query.is_empty() {
         path.clone()
@@ -568,20 +690,86 @@ async fn proxy_handler(
             StatusCode::PAYLOAD_TOO_LARGE
         })?;
 
-    // 세션 ID 추출 (sentry-trace 또는 baggage에서 trace_id 추출)
-    let session_id = original_headers
-        .get("x-session-id")
-        .or_else(|| original_headers.get("x-request-id"))
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string())
-        .or_else(|| {
-            // sentry-trace 헤더에서 trace_id 추출 (형식: trace_id-span_id)
-            original_headers
-                .get("sentry-trace")
-                .and_then(|v| v.to_str().ok())
-                .and_then(|s| s.split('-').next())
-                .map(|s| s.to_string())
+    // 세션 ID 결정 (resolver 체인 → 등록된 별칭 매핑)
+    let metadata_user_id = metadata_user_id_from_body(&body_bytes);
+    let resolved_session = state.session_resolver.resolve(&SessionHints {
+        headers: &original_headers,
+        metadata_user_id: metadata_user_id.as_deref(),
+    });
+    let (session_id, session_source) = match resolved_session {
+        Some(resolved) => match state.db.resolve_session_alias(&resolved.session_id).await {
+            Ok(Some(registered)) => (Some(registered), Some(SessionSource::Registered)),
+            _ => (Some(resolved.session_id), Some(resolved.source)),
+        },
+        None => (None, None),
+    };
+    let session_source = session_source.map(|s| s.as_str().to_string());
+
+    // 서브에이전트 감지: Task 도구로 생성된 대화는 부모 세션의 자식 세션으로 분리
+    let request_json = serde_json::from_slice::<serde_json::Value>(&body_bytes).unwrap_or(serde_json::Value::Null);
+    let request_shape = RequestShape::from_body(&request_json);
+    let subagent = state.subagent_tracker.detect(session_id.as_deref(), &request_shape).await;
+    if let (None, Some(sid)) = (&subagent, &session_id) {
+        track_completed_tasks(&state, sid, &request_shape).await;
+    }
+    let mut new_subagent: Option<SubagentMatch> = None;
+    let (session_id, parent_session_id) = match subagent {
+        Some(m) => {
+            match state
+                .db
+                .link_subagent_session(&m.parent_session_id, &m.child_session_id, m.tool_use_id.as_deref(), m.detection.as_str())
+                .await
+            {
+                Ok(true) => new_subagent = Some(m.clone()),
+                Ok(false) => {}
+                Err(e) => tracing::warn!("Failed to link subagent session: {}", e),
+            }
+            (Some(m.child_session_id), Some(m.parent_session_id))
+        }
+        None => (session_id, None),
+    };
+
+    // 요청 종류 분류 (메인 턴 / 서브에이전트 / 제목 생성 / 컴팩션 / 쿼터 확인 ...)
+    let request_kind = classify(
+        &path,
+        &String::from_utf8_lossy(&body_bytes),
+        &request_json,
+        parent_session_id.is_some(),
+    );
+
+    // 프로젝트 귀속: 환경 정보(작업 디렉토리, git 브랜치)에서 추출, 없으면 세션(또는 부모 세션)의 프로젝트
+    let stored_project = match &session_id {
+        Some(sid) => state.db.get_session_project(sid).await.ok().flatten(),
+        None => None,
+    };
+    let project = match (project::detect(&request_json), &session_id) {
+        (Some(detected), Some(sid)) => {
+            if stored_project.as_ref() != Some(&detected) {
+                if let Err(e) = state.db.set_session_project(sid, &detected).await {
+                    tracing::warn!("Failed to store session project: {}", e);
+                }
+            }
+            Some(detected)
+        }
+        (Some(detected), None) => Some(detected),
+        (None, _) => stored_project,
+    };
+
+    // 이전 턴 도구 호출의 전체 입력과 결과(에러 여부) 기록
+    let (tool_calls, tool_results) = tool_usage::last_turn(&request_json);
+    // 사용자 메시지(tool_result가 없는 요청)는 새 턴을 시작
+    let starts_turn = tool_results.is_empty();
+    if !tool_results.is_empty() {
+        let db = state.db.clone();
+        let sid = session_id.clone();
+        let model = request_json.get("model").and_then(|m| m.as_str()).unwrap_or("unknown").to_string();
+        tokio::spawn(async move {
+            if let Err(e) = db.complete_tool_uses(sid.as_deref(), &model, &tool_calls, &tool_results).await {
+                tracing::warn!("Failed to record tool results: {}", e);
+            }
         });
+    }
+    drop(request_json);
 
     // 세션별 계정 및 모델 오버라이드 결정
     let (account, model_override, is_existing_session) = if let Some(ref sid) = session_id {
@@ -603,6 +791,23 @@ async fn proxy_handler(
             );
 
             (acc, config.model_override, true)
+        } else if let Some(ref parent) = parent_session_id {
+            // 새 서브에이전트 세션: 서브에이전트 라우팅 → 부모 세션 계정 → 활성 계정
+            let (acc, model_override) = subagent_route(&state, parent)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+                .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+            let _ = state.db.upsert_session_config(sid, &acc.id, model_override.as_deref()).await;
+
+            tracing::info!(
+                "NEW SUBAGENT SESSION: {} (parent {}) -> {} ({})",
+                sid,
+                &parent[..std::cmp::min(12, parent.len())],
+                acc.name,
+                model_override.as_deref().unwrap_or("original")
+            );
+
+            (acc, model_override, false)
         } else {
             // 새 세션: 활성 계정으로 자동 등록
             let acc = state
@@ -656,6 +861,20 @@ async fn proxy_handler(
         (acc, None, false)
     };
 
+    // 요청 종류별 라우팅 (설정된 경우 세션 라우팅보다 우선, 서브에이전트는 위의 서브에이전트 라우팅으로 처리)
+    let (account, model_override) = match kind_route(&state, request_kind).await {
+        Some((acc, model)) => {
+            tracing::info!(
+                "KIND ROUTE: {} -> {} ({})",
+                request_kind.as_str(),
+                acc.name,
+                model.as_deref().unwrap_or("original")
+            );
+            (acc, model)
+        }
+        None => (account, model_override),
+    };
+
     // API 키 로드
     let api_key = state
         .db
@@ -673,7 +892,13 @@ async fn proxy_handler(
     // 세션 활동 시간 및 마지막 메시지 업데이트 (기존 세션인 경우)
     if is_existing_session {
         if let Some(ref sid) = session_id {
-            let _ = state.db.update_session_activity(sid, request_info.last_message.as_deref()).await;
+            // 백그라운드 요청(제목 생성 등)의 메시지는 마지막 메시지로 표시하지 않음
+            let last_message = if request_kind.is_background() {
+                None
+            } else {
+                request_info.last_message.as_deref()
+            };
+            let _ = state.db.update_session_activity(sid, last_message).await;
         }
     }
 
@@ -690,7 +915,10 @@ async fn proxy_handler(
         _ => reqwest::Method::POST,
     };
 
-    let mut request_builder = state.client.request(
+    // 계정별 업스트림 타임아웃 (connect / first byte / idle)
+    let timeouts = account.timeouts();
+
+    let mut request_builder = state.clients.get(timeouts.connect).request(
         reqwest_method,
         &target_url,
     );
@@ -733,11 +961,13 @@ async fn proxy_handler(
     // 요청 로깅 (body move 전에)
     let body_len = body_bytes.len();
     tracing::info!(
-        "PROXY: {} {} | Model: {} | Session: {} | BodySize: {}",
+        "PROXY: {} {} | Kind: {} | Model: {} | Session: {} ({}) | BodySize: {}",
         method.as_str(),
         path,
+        request_kind.as_str(),
         request_info.model,
         session_id.as_deref().unwrap_or("none"),
+        session_source.as_deref().unwrap_or("-"),
         body_len
     );
 
@@ -749,7 +979,43 @@ async fn proxy_handler(
         method.as_str().to_string(),
         path.clone(),
         request_body_json,
+    )
+    .with_session_source(session_source.clone())
+    .with_request_kind(request_kind);
+    let request_id = request_context.request_id.clone();
+    state.events.emit(
+        Some(&request_id),
+        session_id.as_deref(),
+        ProxyEventKind::RequestStarted {
+            session_source: session_source.clone(),
+            request_kind: request_kind.as_str().to_string(),
+            account: account.name.clone(),
+            model: request_info.model.clone(),
+            method: method.as_str().to_string(),
+            path: path.clone(),
+        },
     );
+    if let Some(sid) = session_id.as_deref().filter(|_| !request_kind.is_background()) {
+        state.sessions.request_started(sid, starts_turn, &request_id).await;
+    }
+    if let Some(ref m) = new_subagent {
+        tracing::info!(
+            "SUBAGENT DETECTED: {} (parent {}, {}, tool_use {:?})",
+            m.child_session_id,
+            m.parent_session_id,
+            m.detection.as_str(),
+            m.tool_use_id
+        );
+        state.events.emit(
+            Some(&request_id),
+            Some(&m.child_session_id),
+            ProxyEventKind::SubagentDetected {
+                parent_session_id: m.parent_session_id.clone(),
+                tool_use_id: m.tool_use_id.clone(),
+                detection: m.detection.as_str().to_string(),
+            },
+        );
+    }
 
     // Load session-specific hook configuration (if exists)
     let session_hooks = if let Some(ref sid) = session_id {
@@ -771,6 +1037,7 @@ async fn proxy_handler(
             intercepted: false,
             response_text: String::new(),
             task_name: None,
+            pending: None,
         }
     };
     if intercept_result.intercepted {
@@ -780,15 +1047,20 @@ async fn proxy_handler(
             session_id
         );
 
-        // Generate fake SSE response
-        let sse_response = CustomTaskHook::generate_sse_response(&intercept_result.response_text);
+        // Generate fake SSE response (tasks stream their output while they run)
+        let body = match intercept_result.pending {
+            Some(pending) => Body::from_stream(
+                state.custom_task_hook.clone().stream_task(intercept_result.response_text, pending),
+            ),
+            None => Body::from(CustomTaskHook::generate_sse_response(&intercept_result.response_text)),
+        };
 
         // Return SSE response
         return Ok(Response::builder()
             .status(StatusCode::OK)
             .header("content-type", "text/event-stream")
             .header("cache-control", "no-cache")
-            .body(Body::from(sse_response))
+            .body(body)
             .unwrap());
     }
 
@@ -797,6 +1069,16 @@ async fn proxy_handler(
         .map(|h| h.api_logging_enabled)
         .unwrap_or(true); // Default: enabled
 
+    // 대화 기록 저장 (메인 턴/서브에이전트만, 제목 생성 등 백그라운드 요청 제외)
+    let transcript_enabled = session_id.is_some()
+        && !request_kind.is_background()
+        && state.db.get_config("transcripts_enabled").await.ok().flatten().map(|v| v == "true").unwrap_or(true);
+    if transcript_enabled {
+        if let Some(ref sid) = session_id {
+            record_transcript_request(&state, sid, &request_id, &request_context.body).await;
+        }
+    }
+
     // Trigger request_before hook (only if logging enabled for session)
     if api_logging_enabled {
         state.hook_registry.trigger_request_before(&request_context).await;
@@ -835,10 +1117,81 @@ async fn proxy_handler(
             .body(body_vec);
     }
 
+    // 오류 통계 기록용 공통 필드
+    let error_entry = ErrorEntry {
+        account_id: account.id.clone(),
+        model: request_info.model.clone(),
+        phase: "request".to_string(),
+        session_id: session_id.clone(),
+        request_id: Some(request_id.clone()),
+        request_kind: Some(request_kind.as_str().to_string()),
+        ..Default::default()
+    };
+
     // 요청 전송
-    let response = match request_builder.send().await {
-        Ok(resp) => resp,
-        Err(e) => {
+    let upstream_sent_at = std::time::Instant::now();
+    let response = match tokio::time::timeout(timeouts.first_byte, request_builder.send()).await {
+        Ok(Ok(resp)) => resp,
+        Err(_) => {
+            state.metrics.record_request(&account.name, &request_info.model, "504", &path);
+            tracing::error!(
+                "PROXY FIRST BYTE TIMEOUT: {} | Target: {} | Account: {} | Session: {:?} | Timeout: {}s",
+                method.as_str(),
+                target_url,
+                account.name,
+                session_id,
+                timeouts.first_byte.as_secs()
+            );
+            state.events.emit(
+                Some(&request_id),
+                session_id.as_deref(),
+                ProxyEventKind::Error {
+                    account: account.name.clone(),
+                    status_code: 504,
+                    message: format!("first byte timeout ({}s)", timeouts.first_byte.as_secs()),
+                },
+            );
+            log_error(
+                &state.db,
+                &state.alerts,
+                ErrorEntry {
+                    status_code: 504,
+                    error_type: "timeout_error".to_string(),
+                    message: format!("No response headers from upstream within {}s", timeouts.first_byte.as_secs()),
+                    ..error_entry
+                },
+            );
+
+            let error_json = serde_json::json!({
+                "type": "error",
+                "error": {
+                    "type": "timeout_error",
+                    "message": format!("No response headers from upstream within {}s", timeouts.first_byte.as_secs())
+                }
+            });
+            let formatted_error = format_error_response(
+                504,
+                serde_json::to_vec(&error_json).unwrap_or_default().as_slice(),
+                &account.name,
+                &target_url,
+            );
+
+            let builder = Response::builder()
+                .status(StatusCode::GATEWAY_TIMEOUT)
+                .header("content-type", "application/json");
+            return Ok(builder.body(Body::from(formatted_error)).unwrap());
+        }
+        Ok(Err(e)) => {
+            state.metrics.record_request(&account.name, &request_info.model, "502", &path);
+            state.events.emit(
+                Some(&request_id),
+                session_id.as_deref(),
+                ProxyEventKind::Error {
+                    account: account.name.clone(),
+                    status_code: 502,
+                    message: e.to_string(),
+                },
+            );
             tracing::error!(
                 "PROXY REQUEST FAILED: {} | Target: {} | Account: {} | Session: {:?} | Error: {}",
                 method.as_str(),
@@ -847,6 +1200,16 @@ async fn proxy_handler(
                 session_id,
                 e
             );
+            log_error(
+                &state.db,
+                &state.alerts,
+                ErrorEntry {
+                    status_code: 502,
+                    error_type: "connection_error".to_string(),
+                    message: e.to_string(),
+                    ..error_entry
+                },
+            );
 
             // 연결 실패 에러 포맷팅
             let error_json = serde_json::json!({
@@ -871,10 +1234,18 @@ async fn proxy_handler(
     };
 
     let response_status = response.status();
+    let headers_at = std::time::Instant::now();
+    state.metrics.observe_upstream_latency(&account.name, &request_info.model, upstream_sent_at.elapsed());
+    state.metrics.record_request(&account.name, &request_info.model, response_status.as_str(), &path);
 
     // 에러 응답인 경우 상세 로깅 (응답 바디 포함)
     if !response_status.is_success() {
         // 에러 응답은 바디 전체를 읽어서 로깅
+        let retry_after = response
+            .headers()
+            .get("retry-after")
+            .and_then(|v| v.to_str().ok())
+            .and_then(retry_after_secs);
         let error_body = response.bytes().await.unwrap_or_default();
         let error_text = String::from_utf8_lossy(&error_body);
 
@@ -892,6 +1263,29 @@ async fn proxy_handler(
             }
         );
 
+        state.events.emit(
+            Some(&request_id),
+            session_id.as_deref(),
+            ProxyEventKind::Error {
+                account: account.name.clone(),
+                status_code: response_status.as_u16(),
+                message: error_text.chars().take(500).collect(),
+            },
+        );
+
+        let (error_type, message) = parse_error_body(&error_body);
+        log_error(
+            &state.db,
+            &state.alerts,
+            ErrorEntry {
+                status_code: response_status.as_u16() as i64,
+                error_type,
+                message: message.chars().take(2000).collect(),
+                retry_after_secs: retry_after,
+                ..error_entry
+            },
+        );
+
         // 포맷된 에러 응답 반환
         let formatted_error = format_error_response(
             response_status.as_u16(),
@@ -930,41 +1324,177 @@ async fn proxy_handler(
     let account_id = account.id.clone();
     let model = request_info.model.clone();
     let db = state.db.clone();
+    let alerts = state.alerts.clone();
     let session_id_for_log = session_id.clone();
+    let session_source_for_log = session_source.clone();
+    let project_for_log = project.clone();
     let semaphore = state.db_task_semaphore.clone();
     let webhook = state.webhook.clone();
     let question_detector = state.question_detector.clone();
     let step_tracker = state.step_tracker.clone();
+    let sessions = state.sessions.clone();
+    let subagent_tracker = state.subagent_tracker.clone();
     let hook_registry = state.hook_registry.clone();
     let request_context_for_stream = request_context.clone();
     let api_logging_enabled_for_stream = api_logging_enabled;
+    let metrics = state.metrics.clone();
+    let events = state.events.clone();
+    let request_id_for_stream = request_id.clone();
+    let account_name = account.name.clone();
+    let mut timings = RequestTimings { received_at, upstream_sent_at, headers_at, first_token_at: None };
 
     // Create ResponseBuilder for accumulating response data
     let response_builder = ResponseBuilder::new(status.as_u16());
 
-    let body_stream = response.bytes_stream();
+    // chunk 간 idle 타임아웃 (SSE면 error 이벤트로 스트림을 정상 종료)
+    let is_sse = response
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.starts_with("text/event-stream"))
+        .unwrap_or(false);
+    let idle_log = (account.name.clone(), session_id.clone(), timeouts.idle.as_secs());
+    let idle_events = (state.events.clone(), request_id.clone());
+    let idle_error = (state.db.clone(), state.alerts.clone(), ErrorEntry { phase: "stream".to_string(), ..error_entry.clone() });
+    let body_stream = with_idle_timeout(response.bytes_stream(), timeouts.idle, is_sse, move || {
+        tracing::warn!(
+            "PROXY STREAM IDLE TIMEOUT: Account: {} | Session: {:?} | Idle: {}s",
+            idle_log.0,
+            idle_log.1,
+            idle_log.2
+        );
+        idle_events.0.emit(
+            Some(&idle_events.1),
+            idle_log.1.as_deref(),
+            ProxyEventKind::Error {
+                account: idle_log.0.clone(),
+                status_code: 200,
+                message: format!("stream idle timeout ({}s)", idle_log.2),
+            },
+        );
+        log_error(
+            &idle_error.0,
+            &idle_error.1,
+            ErrorEntry {
+                status_code: 200,
+                error_type: "timeout_error".to_string(),
+                message: format!("No data from upstream for {}s", idle_log.2),
+                ..idle_error.2.clone()
+            },
+        );
+    });
+    let stream_error_entry = ErrorEntry { phase: "stream".to_string(), ..error_entry };
+    // idle 타임아웃 시 주입되는 error 이벤트는 on_timeout에서 이미 기록됨
+    let idle_timeout_event = idle_timeout_sse_event(timeouts.idle);
 
     // 스트림을 래핑하여 사용량 정보 및 AI 질문 추출
     let response_builder_for_stream = response_builder.clone();
     let wrapped_stream = body_stream.map(move |chunk_result| {
+        // 스트림이 drop될 때 in-flight 게이지 감소
+        let _ = &in_flight;
+
+        // 스트림 중 연결 오류 (non-SSE idle 타임아웃은 on_timeout에서 기록)
+        if let Err(ref e) = chunk_result {
+            if e.kind() != std::io::ErrorKind::TimedOut {
+                log_error(
+                    &db,
+                    &alerts,
+                    ErrorEntry {
+                        status_code: 200,
+                        error_type: "connection_error".to_string(),
+                        message: e.to_string(),
+                        ..stream_error_entry.clone()
+                    },
+                );
+            }
+        }
+
         if let Ok(ref chunk) = chunk_result {
+            // 응답 블록(text/thinking/tool_use) 누적 - 순서가 중요하므로 동기 처리
+            if is_sse {
+                response_builder_for_stream.append_sse(chunk);
+            }
+
             // SSE 데이터에서 텍스트 및 usage 추출 시도
             if let Ok(text) = std::str::from_utf8(chunk) {
-                // Tool use 감지 및 step tracking
-                if let Some(tool_info) = parse_tool_use_from_sse(text) {
+                if timings.first_token_at.is_none() && contains_content_delta(text) {
+                    timings.first_token_at = Some(std::time::Instant::now());
+                    metrics.observe_time_to_first_token(&account_name, &model, upstream_sent_at.elapsed());
+                    events.emit(
+                        Some(&request_id_for_stream),
+                        session_id_for_log.as_deref(),
+                        ProxyEventKind::FirstToken {
+                            elapsed_ms: upstream_sent_at.elapsed().as_millis() as u64,
+                        },
+                    );
+                }
+
+                // 스트림 중 오류 이벤트 (응답 시작 후 overloaded 등)
+                if let Some((error_type, message)) = parse_error_from_sse(text).filter(|_| *chunk != idle_timeout_event) {
+                    tracing::warn!("PROXY STREAM ERROR: {} | {} | Account: {}", error_type, message, account_name);
+                    log_error(&db, &alerts, ErrorEntry { status_code: 200, error_type, message, ..stream_error_entry.clone() });
+                }
+
+                // Tool use 감지
+                for tool_info in parse_tool_uses_from_sse(text) {
+                    events.emit(
+                        Some(&request_id_for_stream),
+                        session_id_for_log.as_deref(),
+                        ProxyEventKind::ToolUse {
+                            tool_name: tool_info.name.clone(),
+                            input: tool_info.input.clone(),
+                        },
+                    );
+
+                    let subagents_for_tool = subagent_tracker.clone();
+                    let db_for_tool = db.clone();
+                    let session_for_tool = session_id_for_log.clone();
+                    let model_for_tool = model.clone();
+
+                    tokio::spawn(async move {
+                        // 도구 사용 통계용 기록 (입력과 결과는 다음 요청에서 보완)
+                        if let Some(ref tool_use_id) = tool_info.id {
+                            if let Err(e) = db_for_tool
+                                .record_tool_use(tool_use_id, session_for_tool.as_deref(), &model_for_tool, &tool_info.name)
+                                .await
+                            {
+                                tracing::warn!("Failed to record tool use: {}", e);
+                            }
+                        }
+
+                        // Task 호출은 이후 서브에이전트 요청과 연결하기 위해 기록
+                        if let (Some(ref sid), Some(ref tool_use_id)) = (&session_for_tool, &tool_info.id) {
+                            if TASK_TOOL_NAMES.contains(&tool_info.name.as_str()) {
+                                subagents_for_tool.record_task_call(sid, tool_use_id).await;
+                            }
+                        }
+                    });
+                }
+
+                // Step tracking (입력이 완성된 tool_use 기준, Bash 명령 규칙 적용)
+                let finished_tool_uses = response_builder_for_stream.take_finished_tool_uses();
+                if let Some(sid) = session_id_for_log.clone().filter(|_| !finished_tool_uses.is_empty()) {
                     let tracker = step_tracker.clone();
                     let db_for_step = db.clone();
                     let webhook_for_step = webhook.clone();
-                    let session_for_step = session_id_for_log.clone();
+                    let events_for_step = events.clone();
+                    let request_id_for_step = request_id_for_stream.clone();
 
                     tokio::spawn(async move {
-                        if let Some(ref sid) = session_for_step {
-                            let (completed, new_step) = tracker
-                                .process_tool_use(sid, &tool_info.name, tool_info.input.as_ref())
-                                .await;
+                        for block in finished_tool_uses {
+                            let ContentBlock::ToolUse { name, input, .. } = block else { continue };
+                            let (completed, new_step) = tracker.process_tool_use(&sid, &name, Some(&input)).await;
+
+                            for step in completed.iter().chain(new_step.iter()) {
+                                events_for_step.emit(
+                                    Some(&request_id_for_step),
+                                    Some(&sid),
+                                    ProxyEventKind::StepChanged { step: step.clone() },
+                                );
+                            }
 
                             if completed.is_some() || new_step.is_some() {
-                                tracker.send_updates(&db_for_step, &webhook_for_step, sid, completed, new_step).await;
+                                tracker.send_updates(&db_for_step, &webhook_for_step, &sid, completed, new_step).await;
                             }
                         }
                     });
@@ -972,44 +1502,31 @@ async fn proxy_handler(
 
                 // AI 질문 감지를 위한 텍스트 처리
                 if let Some(content_text) = parse_text_from_sse(text) {
-                    // Accumulate response text for hooks
-                    let rb = response_builder_for_stream.clone();
-                    let text_clone = content_text.clone();
-                    tokio::spawn(async move {
-                        rb.append_text(&text_clone).await;
-                    });
-
                     let detector = question_detector.clone();
                     let db_for_question = db.clone();
                     let webhook_for_question = webhook.clone();
                     let session_id_for_question = session_id_for_log.clone();
+                    let events_for_question = events.clone();
+                    let request_id_for_question = request_id_for_stream.clone();
 
                     tokio::spawn(async move {
                         if let Some(detected) = detector.process_text(&content_text).await {
-                            tracing::info!(
-                                "AI QUESTION DETECTED: {}",
-                                detected.question.chars().take(50).collect::<String>()
-                            );
-
-                            // Send webhook if session has ThreadCast mapping
-                            if let Some(ref sid) = session_id_for_question {
-                                if let Ok(Some((todo_id, _))) = db_for_question.get_threadcast_mapping(sid).await {
-                                    let _ = webhook_for_question.send_ai_question(
-                                        Some(todo_id),
-                                        sid,
-                                        AIQuestionData {
-                                            question: detected.question,
-                                            options: detected.options,
-                                            context: detected.context,
-                                        },
-                                    ).await;
-                                }
-                            }
+                            notify_question(
+                                &db_for_question,
+                                &webhook_for_question,
+                                &events_for_question,
+                                &request_id_for_question,
+                                session_id_for_question.as_deref(),
+                                detected,
+                            )
+                            .await;
                         }
                     });
                 }
 
                 if let Some(usage) = parse_usage_from_sse(text) {
+                    // message_delta(usage)는 스트림의 마지막 이벤트 직전
+                    let latency = timings.latency(std::time::Instant::now(), usage.output_tokens);
                     tracing::info!(
                         "USAGE: in={}, out={}, stop_reason={:?}",
                         usage.input_tokens,
@@ -1029,16 +1546,31 @@ async fn proxy_handler(
                         }
                     });
 
+                    let cost_usd = estimate_cost_usd(&model, &usage.token_usage());
+                    metrics.record_tokens(&account_name, &model, "input", usage.input_tokens);
+                    metrics.record_tokens(&account_name, &model, "output", usage.output_tokens);
+                    metrics.record_tokens(&account_name, &model, "cache_read", usage.cache_read_tokens);
+                    metrics.record_tokens(&account_name, &model, "cache_creation", usage.cache_creation_tokens);
+                    metrics.record_cost(&account_name, &model, cost_usd);
+
                     // DB에 사용량 로깅 (비동기로 처리, Semaphore로 동시 실행 제한)
                     let db_clone = db.clone();
+                    let metrics_clone = metrics.clone();
                     let account_id_clone = account_id.clone();
                     let model_clone = model.clone();
                     let session_id_clone = session_id_for_log.clone();
+                    let session_source_clone = session_source_for_log.clone();
+                    let project_clone = project_for_log.clone();
                     let sem = semaphore.clone();
                     let webhook_clone = webhook.clone();
                     let usage_input = usage.input_tokens;
                     let usage_output = usage.output_tokens;
                     let rb_for_webhook = response_builder_for_stream.clone();
+                    let events_for_usage = events.clone();
+                    let alerts_for_usage = alerts.clone();
+                    let request_id_for_usage = request_id_for_stream.clone();
+                    let account_name_for_usage = account_name.clone();
+                    let stop_reason_for_usage = usage.stop_reason.clone();
 
                     // Trigger hooks with final response when we have usage (stream end indicator)
                     // Only if API logging is enabled for this session
@@ -1060,19 +1592,110 @@ async fn proxy_handler(
                         });
                     }
 
+                    // AskUserQuestion tool_use는 입력에 질문과 선택지가 그대로 들어있음
+                    {
+                        let rb_for_question = response_builder_for_stream.clone();
+                        let db_for_question = db.clone();
+                        let webhook_for_question = webhook.clone();
+                        let events_for_question = events.clone();
+                        let session_id_for_question = session_id_for_log.clone();
+                        let request_id_for_question = request_id_for_stream.clone();
+                        tokio::spawn(async move {
+                            // Small delay to ensure all tool input is accumulated
+                            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                            let res_ctx = rb_for_question.build().await;
+                            let questions = res_ctx
+                                .content_blocks
+                                .iter()
+                                .filter_map(|b| match b {
+                                    ContentBlock::ToolUse { name, input, .. } if name == ASK_USER_QUESTION_TOOL => Some(input),
+                                    _ => None,
+                                })
+                                .flat_map(questions_from_tool_input);
+                            for detected in questions {
+                                notify_question(
+                                    &db_for_question,
+                                    &webhook_for_question,
+                                    &events_for_question,
+                                    &request_id_for_question,
+                                    session_id_for_question.as_deref(),
+                                    detected,
+                                )
+                                .await;
+                            }
+                        });
+                    }
+
+                    // 스트리밍된 응답을 대화 기록에 추가 (다음 요청에서 전체 버전으로 교체됨)
+                    if transcript_enabled {
+                        if let Some(sid) = session_id_for_log.clone() {
+                            let db_for_transcript = db.clone();
+                            let rb_for_transcript = response_builder_for_stream.clone();
+                            let request_id_for_transcript = request_id_for_stream.clone();
+                            let usage_tokens = (usage.input_tokens, usage.output_tokens);
+                            let stop_reason_for_transcript = usage.stop_reason.clone();
+                            tokio::spawn(async move {
+                                // Small delay to ensure all text is accumulated
+                                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                                let res_ctx = rb_for_transcript.build().await;
+                                let blocks = message_blocks(
+                                    &serde_json::json!({"role": "assistant", "content": res_ctx.content_blocks}),
+                                );
+                                if let Err(e) = db_for_transcript
+                                    .append_transcript_response(
+                                        &sid,
+                                        &request_id_for_transcript,
+                                        &blocks,
+                                        usage_tokens,
+                                        stop_reason_for_transcript.as_deref(),
+                                    )
+                                    .await
+                                {
+                                    tracing::warn!("Failed to record transcript response: {}", e);
+                                }
+                            });
+                        }
+                    }
+
                     // Complete current step when response ends (usage indicates stream end)
                     let tracker_for_complete = step_tracker.clone();
+                    let sessions_for_complete = sessions.clone();
                     let db_for_complete = db.clone();
                     let webhook_for_complete = webhook.clone();
                     let session_for_complete = session_id_for_log.clone();
                     let stop_reason_for_complete = usage.stop_reason.clone();
+                    let events_for_complete = events.clone();
+                    let request_id_for_complete = request_id_for_stream.clone();
                     let usage_input_for_complete = usage.input_tokens;
                     let usage_output_for_complete = usage.output_tokens;
 
                     tokio::spawn(async move {
-                        // Complete current step first
+                        // Complete current step first (tool_use로 끝난 응답은 같은 턴이 이어짐)
                         if let Some(ref sid) = session_for_complete {
-                            if let Some(step_data) = tracker_for_complete.complete_current_step(sid).await {
+                            let lifecycle = if request_kind.is_background() {
+                                None
+                            } else {
+                                sessions_for_complete
+                                    .request_finished(
+                                        sid,
+                                        latency.duration_ms,
+                                        stop_reason_for_complete.as_deref(),
+                                        &request_id_for_complete,
+                                    )
+                                    .await
+                            };
+                            let turn_continues = stop_reason_for_complete.as_deref() == Some("tool_use");
+                            let completed_step = if turn_continues {
+                                None
+                            } else {
+                                tracker_for_complete.complete_current_step(sid).await
+                            };
+                            if let Some(step_data) = completed_step {
+                                events_for_complete.emit(
+                                    Some(&request_id_for_complete),
+                                    Some(sid),
+                                    ProxyEventKind::StepChanged { step: step_data.clone() },
+                                );
                                 tracker_for_complete.send_single_update(&db_for_complete, &webhook_for_complete, sid, step_data).await;
                             }
 
@@ -1080,6 +1703,12 @@ async fn proxy_handler(
                             if stop_reason_for_complete.as_deref() == Some("end_turn") {
                                 if let Ok(Some((todo_id, _))) = db_for_complete.get_threadcast_mapping(sid).await {
                                     let completed_steps = tracker_for_complete.get_completed_steps(sid).await;
+                                    let turn_duration_ms = db_for_complete
+                                        .get_session_turns(sid)
+                                        .await
+                                        .ok()
+                                        .and_then(|turns| turns.last().map(|t| t.duration_secs as u64 * 1000))
+                                        .unwrap_or(latency.duration_ms as u64);
                                     let _ = webhook_for_complete.send_session_complete(
                                         Some(todo_id),
                                         sid,
@@ -1087,7 +1716,16 @@ async fn proxy_handler(
                                             stop_reason: "end_turn".to_string(),
                                             total_input_tokens: usage_input_for_complete,
                                             total_output_tokens: usage_output_for_complete,
-                                            duration_ms: 0, // TODO: track actual duration
+                                            duration_ms: lifecycle
+                                                .as_ref()
+                                                .map(|l| l.wall_secs() as u64 * 1000)
+                                                .unwrap_or(latency.duration_ms as u64),
+                                            active_ms: lifecycle
+                                                .as_ref()
+                                                .map(|l| l.active_ms as u64)
+                                                .unwrap_or(latency.duration_ms as u64),
+                                            turn_count: lifecycle.as_ref().map(|l| l.turn_count).unwrap_or(1),
+                                            turn_duration_ms,
                                             completed_steps,
                                         },
                                     ).await;
@@ -1104,21 +1742,52 @@ async fn proxy_handler(
                             Err(_) => {
                                 // 동시 DB 작업이 너무 많음 - 로깅 스킵 (서비스 우선)
                                 tracing::debug!("Too many concurrent DB tasks, skipping usage log");
+                                metrics_clone.record_usage_log_dropped();
                                 return;
                             }
                         };
 
-                        if let Err(e) = db_clone
-                            .log_usage(
-                                &account_id_clone,
-                                &model_clone,
-                                usage_input,
-                                usage_output,
-                                session_id_clone.as_deref(),
-                            )
-                            .await
-                        {
+                        let entry = UsageEntry {
+                            account_id: account_id_clone,
+                            model: model_clone.clone(),
+                            input_tokens: usage_input,
+                            output_tokens: usage_output,
+                            cost_usd,
+                            session_id: session_id_clone.clone(),
+                            session_source: session_source_clone,
+                            request_kind: Some(request_kind.as_str().to_string()),
+                            request_id: Some(request_id_for_usage.clone()),
+                            project_path: project_clone.as_ref().map(|p| p.project_path.clone()),
+                            git_branch: project_clone.and_then(|p| p.git_branch),
+                            duration_ms: latency.duration_ms,
+                            queue_ms: Some(latency.queue_ms),
+                            headers_ms: Some(latency.headers_ms),
+                            ttft_ms: latency.ttft_ms,
+                            output_tokens_per_sec: latency.output_tokens_per_sec,
+                        };
+                        if let Err(e) = db_clone.log_usage(&entry).await {
                             tracing::error!("Failed to log usage: {}", e);
+                        } else {
+                            events_for_usage.emit(
+                                Some(&request_id_for_usage),
+                                session_id_clone.as_deref(),
+                                ProxyEventKind::UsageLogged {
+                                    account: account_name_for_usage,
+                                    model: model_clone.clone(),
+                                    input_tokens: usage_input,
+                                    output_tokens: usage_output,
+                                    cost_usd,
+                                    stop_reason: stop_reason_for_usage,
+                                },
+                            );
+                            alerts_for_usage
+                                .evaluate(AlertTrigger {
+                                    account_id: entry.account_id.clone(),
+                                    model: entry.model.clone(),
+                                    session_id: entry.session_id.clone(),
+                                    is_error: false,
+                                })
+                                .await;
                         }
 
                         // Send webhook to ThreadCast unconditionally
@@ -1159,6 +1828,249 @@ async fn proxy_handler(
     Ok(builder.body(body).unwrap())
 }
 
+/// Emit a detected question and send it to the webhook if the session has a ThreadCast mapping
+async fn notify_question(
+    db: &Database,
+    webhook: &WebhookClient,
+    events: &EventBus,
+    request_id: &str,
+    session_id: Option<&str>,
+    detected: DetectedQuestion,
+) {
+    tracing::info!(
+        "AI QUESTION DETECTED ({}): {}",
+        detected.rule,
+        detected.question.chars().take(50).collect::<String>()
+    );
+    events.emit(
+        Some(request_id),
+        session_id,
+        ProxyEventKind::QuestionDetected {
+            question: detected.question.clone(),
+            options: detected.options.clone(),
+            rule: detected.rule.clone(),
+        },
+    );
+
+    if let Some(sid) = session_id {
+        if let Ok(Some((todo_id, _))) = db.get_threadcast_mapping(sid).await {
+            let _ = webhook
+                .send_ai_question(
+                    Some(todo_id),
+                    sid,
+                    AIQuestionData {
+                        question: detected.question,
+                        options: detected.options,
+                        context: detected.context,
+                        rule: detected.rule,
+                    },
+                )
+                .await;
+        }
+    }
+}
+
+/// Persist an upstream/transport error for error statistics and alerts (fire-and-forget)
+fn log_error(db: &Arc<Database>, alerts: &AlertEngine, entry: ErrorEntry) {
+    let db = db.clone();
+    let alerts = alerts.clone();
+    tokio::spawn(async move {
+        if let Err(e) = db.log_error(&entry).await {
+            tracing::warn!("Failed to log error: {}", e);
+            return;
+        }
+        alerts
+            .evaluate(AlertTrigger {
+                account_id: entry.account_id,
+                model: entry.model,
+                session_id: entry.session_id,
+                is_error: true,
+            })
+            .await;
+    });
+}
+
+/// Append the request's new messages to the session transcript, skipping history already stored
+async fn record_transcript_request(state: &ProxyState, session_id: &str, request_id: &str, body: &serde_json::Value) {
+    let Some(messages) = body.get("messages").and_then(|m| m.as_array()) else {
+        return;
+    };
+    if messages.is_empty() {
+        return;
+    }
+
+    let first_hash = first_message_hash(messages);
+    let transcript_state = state.db.get_transcript_state(session_id).await.ok().flatten();
+    let plan = plan_append(transcript_state.as_ref(), messages.len(), &first_hash);
+
+    let indexes = plan.replace_index.into_iter().chain(plan.start..messages.len());
+    let append = TranscriptAppend {
+        session_id: session_id.to_string(),
+        request_id: request_id.to_string(),
+        epoch: plan.epoch,
+        first_hash,
+        message_count: messages.len() as i64,
+        replace_index: plan.replace_index.map(|i| i as i64),
+        messages: indexes.map(|i| (i as i64, message_blocks(&messages[i]))).collect(),
+    };
+
+    if let Err(e) = state.db.append_transcript(&append).await {
+        tracing::warn!("Failed to record transcript: {}", e);
+    }
+}
+
+/// Account and model override configured for a request kind, if any
+async fn kind_route(state: &ProxyState, kind: RequestKind) -> Option<(Account, Option<String>)> {
+    let route = state.kind_router.route(kind).await?;
+    let account = state.db.get_account(&route.account_id).await.ok()??;
+    Some((account, route.model_override.clone()))
+}
+
+/// Account and model override for a new subagent session
+async fn subagent_route(state: &ProxyState, parent_session_id: &str) -> Result<Option<(Account, Option<String>)>> {
+    let routing = state.db.get_subagent_routing().await?;
+
+    let account_id = match routing.account_id {
+        Some(id) => Some(id),
+        None => state.db.get_session_config(parent_session_id).await?.map(|c| c.account_id),
+    };
+    let account = match account_id {
+        Some(id) => state.db.get_account(&id).await?,
+        None => None,
+    };
+    let account = match account {
+        Some(account) => Some(account),
+        None => state.db.get_active_account().await?,
+    };
+
+    Ok(account.map(|a| (a, routing.model_override)))
+}
+
+/// Main-thread request carrying Task results: close the pending calls and record agent type/description
+async fn track_completed_tasks(state: &ProxyState, session_id: &str, shape: &RequestShape) {
+    if shape.tool_result_ids.is_empty() {
+        return;
+    }
+    state.subagent_tracker.complete_task_calls(session_id, &shape.tool_result_ids).await;
+
+    for call in &shape.completed_task_calls {
+        let child = child_session_id(session_id, &call.prompt);
+        if let Err(e) = state
+            .db
+            .annotate_subagent_task(
+                session_id,
+                &child,
+                &call.tool_use_id,
+                call.subagent_type.as_deref(),
+                call.description.as_deref(),
+            )
+            .await
+        {
+            tracing::warn!("Failed to annotate subagent task: {}", e);
+        }
+    }
+}
+
+/// Prometheus scrape endpoint
+async fn metrics_handler(State(state): State<ProxyState>) -> Response {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "text/plain; version=0.0.4; charset=utf-8")
+        .body(Body::from(state.metrics.render()))
+        .unwrap()
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct EventsQuery {
+    /// Only forward events for this session
+    session_id: Option<String>,
+}
+
+/// Live event feed (Server-Sent Events); each event name matches its `type` field
+async fn events_handler(
+    State(state): State<ProxyState>,
+    Query(query): Query<EventsQuery>,
+) -> Sse<impl futures::Stream<Item = Result<Event, std::convert::Infallible>>> {
+    let rx = state.events.subscribe();
+    let session_filter = query.session_id;
+
+    let stream = futures::stream::unfold(rx, move |mut rx| {
+        let session_filter = session_filter.clone();
+        async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => {
+                        if session_filter.is_some() && event.session_id != session_filter {
+                            continue;
+                        }
+                        let sse_event = Event::default()
+                            .event(event.event_name())
+                            .json_data(&event)
+                            .unwrap_or_else(|_| Event::default().comment("serialization failed"));
+                        return Some((Ok(sse_event), rx));
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        // 느린 구독자: 건너뛴 이벤트 수를 알리고 계속
+                        return Some((Ok(Event::default().comment(format!("lagged {}", skipped))), rx));
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Request body for `/_swiftcast/session`
+#[derive(Debug, serde::Deserialize)]
+struct RegisterSessionRequest {
+    /// Session ID to use for matching requests
+    session_id: String,
+    /// Raw session ID as resolved from the request (e.g. Claude Code's session UUID)
+    #[serde(default)]
+    alias: Option<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+}
+
+/// Register aliases so requests resolving to `alias` are attributed to `session_id`
+async fn register_session(
+    State(state): State<ProxyState>,
+    axum::Json(payload): axum::Json<RegisterSessionRequest>,
+) -> Result<axum::Json<serde_json::Value>, StatusCode> {
+    let aliases: Vec<String> = payload
+        .alias
+        .into_iter()
+        .chain(payload.aliases)
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty() && *a != payload.session_id)
+        .collect();
+
+    if payload.session_id.trim().is_empty() || aliases.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    for alias in &aliases {
+        state
+            .db
+            .register_session_alias(alias, &payload.session_id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+
+    tracing::info!(
+        "Registered {} session alias(es) -> {}",
+        aliases.len(),
+        &payload.session_id[..std::cmp::min(12, payload.session_id.len())]
+    );
+
+    Ok(axum::Json(serde_json::json!({
+        "session_id": payload.session_id,
+        "aliases": aliases,
+    })))
+}
+
 /// Request body for ThreadCast mapping registration
 #[derive(Debug, serde::Deserialize)]
 struct ThreadcastMappingRequest {
@@ -1169,10 +2081,12 @@ struct ThreadcastMappingRequest {
     // Alternative format: args string like "--todo-id=XXX"
     #[serde(default)]
     args: Option<String>,
-    // Optional fields from TaskContext format
+    // Optional fields from TaskContext format (accepted but unused)
     #[serde(default)]
+    #[allow(dead_code)]
     path: Option<String>,
     #[serde(default)]
+    #[allow(dead_code)]
     model: Option<String>,
 }
 
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use super::events::{EventBus, ProxyEventKind};
+use crate::models::SessionState;
+use crate::storage::database::SessionLifecycle;
+use crate::storage::Database;
+
+/// Sessions become idle after this long without requests (config `session_idle_secs`)
+pub const DEFAULT_IDLE_AFTER_SECS: i64 = 10 * 60;
+/// Sessions end after this long without requests (config `session_end_secs`)
+pub const DEFAULT_END_AFTER_SECS: i64 = 2 * 60 * 60;
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Moves sessions through active → waiting for user → idle → ended and counts turns and active time
+#[derive(Clone)]
+pub struct SessionTracker {
+    db: Arc<Database>,
+    events: EventBus,
+}
+
+impl SessionTracker {
+    pub fn new(db: Arc<Database>, events: EventBus) -> Self {
+        Self { db, events }
+    }
+
+    /// A main-turn or subagent request arrived; `starts_turn` when it carries a new user message
+    pub async fn request_started(&self, session_id: &str, starts_turn: bool, request_id: &str) {
+        let now = chrono::Utc::now().timestamp();
+        match self.db.session_request_started(session_id, starts_turn, now).await {
+            Ok(previous) if previous != Some(SessionState::Active) => {
+                self.emit(Some(request_id), session_id, SessionState::Active, previous).await;
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to record session activity: {}", e),
+        }
+    }
+
+    /// The response finished; returns the updated lifecycle for the session_complete webhook
+    pub async fn request_finished(
+        &self,
+        session_id: &str,
+        duration_ms: i64,
+        stop_reason: Option<&str>,
+        request_id: &str,
+    ) -> Option<SessionLifecycle> {
+        let now = chrono::Utc::now().timestamp();
+        let lifecycle = match self.db.session_request_finished(session_id, duration_ms, stop_reason, now).await {
+            Ok(lifecycle) => lifecycle?,
+            Err(e) => {
+                tracing::warn!("Failed to record session turn: {}", e);
+                return None;
+            }
+        };
+        if stop_reason != Some("tool_use") {
+            self.emit(Some(request_id), session_id, SessionState::WaitingForUser, Some(SessionState::Active)).await;
+        }
+        Some(lifecycle)
+    }
+
+    /// End a session explicitly; false if it has no recorded activity
+    pub async fn end_session(&self, session_id: &str) -> anyhow::Result<bool> {
+        let previous = self.db.get_session_lifecycle(session_id).await?.and_then(|l| l.session_state());
+        let ended = self
+            .db
+            .set_session_state(session_id, SessionState::Ended, Some("explicit"), chrono::Utc::now().timestamp())
+            .await?;
+        if ended && previous != Some(SessionState::Ended) {
+            self.emit(None, session_id, SessionState::Ended, previous).await;
+        }
+        Ok(ended)
+    }
+
+    /// Mark quiet sessions idle or ended; returns how many changed
+    pub async fn sweep(&self, now: i64, idle_after_secs: i64, end_after_secs: i64) -> anyhow::Result<usize> {
+        let mut changed = 0;
+        for lifecycle in self.db.get_open_session_lifecycles().await? {
+            let Some(state) = lifecycle.session_state() else { continue };
+            let Some(next) = state.after_quiet(now - lifecycle.last_activity_at, idle_after_secs, end_after_secs) else {
+                continue;
+            };
+            // 시간 초과로 끝난 세션은 마지막 활동 시점에 종료된 것으로 기록
+            let (at, reason) = match next {
+                SessionState::Ended => (lifecycle.last_activity_at, Some("timeout")),
+                _ => (now, None),
+            };
+            self.db.set_session_state(&lifecycle.session_id, next, reason, at).await?;
+            self.emit(None, &lifecycle.session_id, next, Some(state)).await;
+            changed += 1;
+        }
+        Ok(changed)
+    }
+
+    /// Periodic idle/timeout sweep (thresholds re-read from config each pass)
+    pub fn start_sweeper(&self) -> JoinHandle<()> {
+        let tracker = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let idle_after = config_secs(&tracker.db, "session_idle_secs", DEFAULT_IDLE_AFTER_SECS).await;
+                let end_after = config_secs(&tracker.db, "session_end_secs", DEFAULT_END_AFTER_SECS).await;
+                if let Err(e) = tracker.sweep(chrono::Utc::now().timestamp(), idle_after, end_after).await {
+                    tracing::warn!("Session sweep failed: {}", e);
+                }
+                tokio::time::sleep(SWEEP_INTERVAL).await;
+            }
+        })
+    }
+
+    async fn emit(&self, request_id: Option<&str>, session_id: &str, state: SessionState, previous: Option<SessionState>) {
+        let turn_count = match self.db.get_session_lifecycle(session_id).await {
+            Ok(Some(lifecycle)) => lifecycle.turn_count,
+            _ => 0,
+        };
+        self.events.emit(
+            request_id,
+            Some(session_id),
+            ProxyEventKind::SessionStateChanged { state, previous, turn_count },
+        );
+    }
+}
+
+async fn config_secs(db: &Database, key: &str, default: i64) -> i64 {
+    db.get_config(key)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::SessionState;
+
+    #[test]
+    fn test_after_quiet() {
+        let (idle, end) = (600, 7200);
+        assert_eq!(SessionState::Active.after_quiet(30, idle, end), None);
+        assert_eq!(SessionState::WaitingForUser.after_quiet(601, idle, end), Some(SessionState::Idle));
+        assert_eq!(SessionState::Active.after_quiet(7200, idle, end), Some(SessionState::Ended));
+        assert_eq!(SessionState::Idle.after_quiet(3600, idle, end), None);
+        assert_eq!(SessionState::Idle.after_quiet(7300, idle, end), Some(SessionState::Ended));
+        assert_eq!(SessionState::Ended.after_quiet(99999, idle, end), None);
+    }
+}
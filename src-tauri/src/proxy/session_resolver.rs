@@ -0,0 +1,257 @@
+use axum::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Header Claude Code sends with its session UUID (newer versions)
+pub const CLAUDE_CODE_SESSION_HEADER: &str = "x-claude-code-session-id";
+/// Explicit override, e.g. via `ANTHROPIC_CUSTOM_HEADERS="x-swiftcast-session-id: my-session"`
+pub const SWIFTCAST_SESSION_HEADER: &str = "x-swiftcast-session-id";
+
+/// Which resolver produced a request's session ID (stored with usage rows)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionSource {
+    /// Raw ID mapped through a `/_swiftcast/session` registration
+    Registered,
+    /// `x-swiftcast-session-id`, `x-claude-code-session-id` or a configured header
+    Header,
+    /// Session UUID embedded in the request body's `metadata.user_id`
+    MetadataUserId,
+    /// `x-session-id` / `x-request-id` (pre-resolver behaviour)
+    LegacyHeader,
+    /// Trace ID from `sentry-trace`
+    SentryTrace,
+}
+
+impl SessionSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionSource::Registered => "registered",
+            SessionSource::Header => "header",
+            SessionSource::MetadataUserId => "metadata_user_id",
+            SessionSource::LegacyHeader => "legacy_header",
+            SessionSource::SentryTrace => "sentry_trace",
+        }
+    }
+}
+
+/// Inputs available to resolvers
+pub struct SessionHints<'a> {
+    pub headers: &'a HeaderMap,
+    pub metadata_user_id: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedSession {
+    pub session_id: String,
+    pub source: SessionSource,
+}
+
+/// One step of the chain; returns a raw session ID if it recognises the request
+pub trait SessionResolver: Send + Sync {
+    fn source(&self) -> SessionSource;
+    fn resolve(&self, hints: &SessionHints) -> Option<String>;
+}
+
+/// First non-empty value among the given headers
+pub struct HeaderResolver {
+    headers: Vec<String>,
+    source: SessionSource,
+}
+
+impl HeaderResolver {
+    pub fn new(headers: Vec<String>, source: SessionSource) -> Self {
+        let headers = headers.into_iter().map(|h| h.trim().to_lowercase()).filter(|h| !h.is_empty()).collect();
+        Self { headers, source }
+    }
+}
+
+impl SessionResolver for HeaderResolver {
+    fn source(&self) -> SessionSource {
+        self.source
+    }
+
+    fn resolve(&self, hints: &SessionHints) -> Option<String> {
+        self.headers.iter().find_map(|name| {
+            hints
+                .headers
+                .get(name.as_str())
+                .and_then(|v| v.to_str().ok())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        })
+    }
+}
+
+/// Claude Code's `metadata.user_id`: `user_<hash>_account_<uuid>_session_<uuid>` or a JSON object with `session_id`
+pub struct MetadataUserIdResolver;
+
+impl SessionResolver for MetadataUserIdResolver {
+    fn source(&self) -> SessionSource {
+        SessionSource::MetadataUserId
+    }
+
+    fn resolve(&self, hints: &SessionHints) -> Option<String> {
+        hints.metadata_user_id.and_then(session_from_user_id)
+    }
+}
+
+/// Trace ID portion of `sentry-trace` (`trace_id-span_id[-sampled]`)
+pub struct SentryTraceResolver;
+
+impl SessionResolver for SentryTraceResolver {
+    fn source(&self) -> SessionSource {
+        SessionSource::SentryTrace
+    }
+
+    fn resolve(&self, hints: &SessionHints) -> Option<String> {
+        hints
+            .headers
+            .get("sentry-trace")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.split('-').next())
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    }
+}
+
+/// Extract the session UUID from a Claude Code `metadata.user_id`
+pub fn session_from_user_id(user_id: &str) -> Option<String> {
+    let user_id = user_id.trim();
+
+    if user_id.starts_with('{') {
+        let json: serde_json::Value = serde_json::from_str(user_id).ok()?;
+        return json
+            .get("session_id")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(str::to_string);
+    }
+
+    let (_, rest) = user_id.rsplit_once("_session_")?;
+    let session: String = rest.chars().take_while(|c| c.is_ascii_hexdigit() || *c == '-').collect();
+    if session.len() >= 8 {
+        Some(session)
+    } else {
+        None
+    }
+}
+
+/// Read only `metadata.user_id` from a request body without building the whole JSON tree
+pub fn metadata_user_id_from_body(body: &[u8]) -> Option<String> {
+    #[derive(Deserialize)]
+    struct Metadata {
+        user_id: Option<String>,
+    }
+    #[derive(Deserialize)]
+    struct Body {
+        metadata: Option<Metadata>,
+    }
+
+    serde_json::from_slice::<Body>(body).ok()?.metadata?.user_id
+}
+
+/// Ordered resolver chain; the first resolver that returns an ID wins
+#[derive(Clone)]
+pub struct SessionResolverChain {
+    resolvers: Vec<Arc<dyn SessionResolver>>,
+}
+
+impl Default for SessionResolverChain {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl SessionResolverChain {
+    /// Default chain: explicit/Claude Code/custom headers → metadata.user_id → legacy headers → sentry-trace
+    pub fn new(custom_headers: Vec<String>) -> Self {
+        let mut headers = vec![SWIFTCAST_SESSION_HEADER.to_string(), CLAUDE_CODE_SESSION_HEADER.to_string()];
+        headers.extend(custom_headers);
+
+        Self {
+            resolvers: vec![
+                Arc::new(HeaderResolver::new(headers, SessionSource::Header)),
+                Arc::new(MetadataUserIdResolver),
+                Arc::new(HeaderResolver::new(
+                    vec!["x-session-id".to_string(), "x-request-id".to_string()],
+                    SessionSource::LegacyHeader,
+                )),
+                Arc::new(SentryTraceResolver),
+            ],
+        }
+    }
+
+    /// Parse the `session_headers` config value (comma separated header names)
+    pub fn from_config(session_headers: Option<&str>) -> Self {
+        let custom = session_headers
+            .unwrap_or("")
+            .split(',')
+            .map(|h| h.trim().to_string())
+            .filter(|h| !h.is_empty())
+            .collect();
+        Self::new(custom)
+    }
+
+    /// Insert a resolver at `index` (clamped to the chain length)
+    pub fn insert(&mut self, index: usize, resolver: Arc<dyn SessionResolver>) {
+        let index = index.min(self.resolvers.len());
+        self.resolvers.insert(index, resolver);
+    }
+
+    pub fn resolve(&self, hints: &SessionHints) -> Option<ResolvedSession> {
+        self.resolvers.iter().find_map(|r| {
+            r.resolve(hints).map(|session_id| ResolvedSession { session_id, source: r.source() })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    const UUID: &str = "0f8e6c1a-2b3c-4d5e-8f90-a1b2c3d4e5f6";
+
+    #[test]
+    fn test_session_from_user_id() {
+        let legacy = format!("user_abc123_account_11111111-2222-3333-4444-555555555555_session_{}", UUID);
+        assert_eq!(session_from_user_id(&legacy).as_deref(), Some(UUID));
+
+        let json = format!(r#"{{"device_id":"d","session_id":"{}"}}"#, UUID);
+        assert_eq!(session_from_user_id(&json).as_deref(), Some(UUID));
+
+        assert_eq!(session_from_user_id("user_abc123"), None);
+    }
+
+    #[test]
+    fn test_metadata_user_id_from_body() {
+        let body = br#"{"model":"m","metadata":{"user_id":"u_session_abc"},"messages":[]}"#;
+        assert_eq!(metadata_user_id_from_body(body).as_deref(), Some("u_session_abc"));
+        assert_eq!(metadata_user_id_from_body(br#"{"model":"m"}"#), None);
+    }
+
+    #[test]
+    fn test_chain_order() {
+        let chain = SessionResolverChain::from_config(Some("x-my-session"));
+        let user_id = format!("user_x_session_{}", UUID);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", HeaderValue::from_static("req-1"));
+        let hints = SessionHints { headers: &headers, metadata_user_id: Some(&user_id) };
+        let resolved = chain.resolve(&hints).unwrap();
+        assert_eq!(resolved.source, SessionSource::MetadataUserId);
+        assert_eq!(resolved.session_id, UUID);
+
+        headers.insert("x-my-session", HeaderValue::from_static("custom"));
+        let hints = SessionHints { headers: &headers, metadata_user_id: Some(&user_id) };
+        let resolved = chain.resolve(&hints).unwrap();
+        assert_eq!(resolved.source, SessionSource::Header);
+        assert_eq!(resolved.session_id, "custom");
+
+        let empty = HeaderMap::new();
+        let hints = SessionHints { headers: &empty, metadata_user_id: None };
+        assert_eq!(chain.resolve(&hints), None);
+    }
+}
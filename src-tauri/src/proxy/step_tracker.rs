@@ -1,67 +1,97 @@
+use regex::Regex;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 use super::webhook::{StepUpdateData, WebhookClient};
+use crate::models::{StepConfig, StepRule};
 use crate::storage::Database;
 
-/// Maps Claude Code tool names to step types
+/// Compiled tool-to-phase rules
 #[derive(Debug)]
+pub struct StepRules {
+    rules: Vec<(StepRule, Option<Regex>)>,
+    idle_timeout_secs: i64,
+}
+
+impl StepRules {
+    pub fn compile(config: &StepConfig) -> Result<Self, String> {
+        config.validate()?;
+        let rules = config
+            .rules
+            .iter()
+            .map(|rule| {
+                let regex = rule
+                    .pattern
+                    .as_deref()
+                    .map(Regex::new)
+                    .transpose()
+                    .map_err(|e| format!("{}: invalid pattern: {}", rule.tool, e))?;
+                Ok((rule.clone(), regex))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self { rules, idle_timeout_secs: config.idle_timeout_secs as i64 })
+    }
+
+    /// Phase for a tool call (first matching rule); None when the tool is not tracked
+    pub fn phase_for(&self, tool_name: &str, tool_input: Option<&serde_json::Value>) -> Option<&str> {
+        let (rule, _) = self.rules.iter().find(|(rule, regex)| {
+            rule.matches_tool(tool_name)
+                && regex.as_ref().is_none_or(|regex| {
+                    let field = rule.field.as_deref().unwrap_or("command");
+                    tool_input
+                        .and_then(|input| input.get(field))
+                        .and_then(|v| v.as_str())
+                        .is_some_and(|value| regex.is_match(value))
+                })
+        })?;
+        rule.phase.as_deref()
+    }
+}
+
+impl Default for StepRules {
+    fn default() -> Self {
+        Self::compile(&StepConfig::default()).expect("built-in step rules compile")
+    }
+}
+
+/// Open phase of a session
+#[derive(Debug, Clone)]
+struct CurrentStep {
+    phase: String,
+    /// Row in `step_spans` (None without a DB)
+    span_id: Option<i64>,
+    last_activity: i64,
+}
+
+/// Maps Claude Code tool calls to phases and records phase spans per session
+#[derive(Clone)]
 pub struct StepTracker {
-    /// Current step for each session
-    current_steps: Arc<RwLock<HashMap<String, String>>>,
-    /// Completed steps for each session (for session_complete webhook)
-    completed_steps: Arc<RwLock<HashMap<String, Vec<String>>>>,
+    rules: Arc<RwLock<StepRules>>,
+    /// Current step for each session (evicted when the turn ends or the session goes idle)
+    current_steps: Arc<RwLock<HashMap<String, CurrentStep>>>,
+    db: Option<Arc<Database>>,
 }
 
 impl StepTracker {
     pub fn new() -> Self {
         Self {
+            rules: Arc::new(RwLock::new(StepRules::default())),
             current_steps: Arc::new(RwLock::new(HashMap::new())),
-            completed_steps: Arc::new(RwLock::new(HashMap::new())),
+            db: None,
         }
     }
 
-    /// Map tool name to step type
-    fn tool_to_step_type(tool_name: &str) -> Option<&'static str> {
-        match tool_name {
-            // Analysis tools
-            "Read" | "Glob" | "Grep" | "WebFetch" | "WebSearch" => Some("ANALYSIS"),
-
-            // Design tools (planning, task management)
-            "EnterPlanMode" | "ExitPlanMode" | "TaskCreate" | "TaskUpdate" | "TaskList" | "TaskGet" => Some("DESIGN"),
-
-            // Implementation tools
-            "Edit" | "Write" | "NotebookEdit" => Some("IMPLEMENTATION"),
-
-            // Verification tools (testing, building)
-            "Bash" => Some("VERIFICATION"), // Could be implementation or verification
-
-            // User interaction
-            "AskUserQuestion" | "Skill" => None, // Don't track these as steps
-
-            // Task/Agent tools
-            "Task" | "TaskOutput" | "TaskStop" => None, // Sub-agent operations
-
-            _ => None,
-        }
+    /// Persist phase spans to `step_spans`
+    pub fn with_db(mut self, db: Arc<Database>) -> Self {
+        self.db = Some(db);
+        self
     }
 
-    /// Check if this is a test-related bash command
-    fn is_test_command(input: Option<&serde_json::Value>) -> bool {
-        if let Some(input) = input {
-            if let Some(command) = input.get("command").and_then(|v| v.as_str()) {
-                let cmd_lower = command.to_lowercase();
-                return cmd_lower.contains("test")
-                    || cmd_lower.contains("jest")
-                    || cmd_lower.contains("pytest")
-                    || cmd_lower.contains("npm run test")
-                    || cmd_lower.contains("./gradlew test")
-                    || cmd_lower.contains("mvn test")
-                    || cmd_lower.contains("cargo test");
-            }
-        }
-        false
+    /// Replace the tool-to-phase mapping (applies to the next tool call)
+    pub async fn configure(&self, config: &StepConfig) -> Result<(), String> {
+        *self.rules.write().await = StepRules::compile(config)?;
+        Ok(())
     }
 
     /// Process a tool_use event and return step updates if needed
@@ -72,31 +102,26 @@ impl StepTracker {
         tool_name: &str,
         tool_input: Option<&serde_json::Value>,
     ) -> (Option<StepUpdateData>, Option<StepUpdateData>) {
-        // Special handling for Bash commands
-        let step_type = if tool_name == "Bash" {
-            if Self::is_test_command(tool_input) {
-                Some("VERIFICATION")
-            } else {
-                // Bash without test keywords could be implementation
-                Some("IMPLEMENTATION")
-            }
-        } else {
-            Self::tool_to_step_type(tool_name)
-        };
+        let now = chrono::Utc::now().timestamp();
+        self.evict_idle(now).await;
 
-        let step_type = match step_type {
-            Some(s) => s,
+        let step_type = match self.rules.read().await.phase_for(tool_name, tool_input) {
+            Some(s) => s.to_string(),
             None => return (None, None),
         };
 
         // Check if we're already in this step
         let mut steps = self.current_steps.write().await;
-        let current = steps.get(session_id);
-
-        if current == Some(&step_type.to_string()) {
+        if let Some(current) = steps.get_mut(session_id).filter(|c| c.phase == step_type) {
+            current.last_activity = now;
+            if let (Some(db), Some(id)) = (&self.db, current.span_id) {
+                if let Err(e) = db.touch_step_span(id, now).await {
+                    tracing::warn!("Failed to update step span: {}", e);
+                }
+            }
             // Already in this step, just update progress
             return (None, Some(StepUpdateData {
-                step_type: step_type.to_string(),
+                step_type,
                 status: "IN_PROGRESS".to_string(),
                 progress: None,
                 message: Some(format!("Using {}", tool_name)),
@@ -105,19 +130,35 @@ impl StepTracker {
         }
 
         // Step changed - complete previous step first
-        let previous = current.cloned();
-        steps.insert(session_id.to_string(), step_type.to_string());
+        let previous = steps.remove(session_id);
+        if let Some(prev) = &previous {
+            self.close_span(prev, now).await;
+        }
+        let span_id = match &self.db {
+            Some(db) => match db.open_step_span(session_id, &step_type, tool_name, now).await {
+                Ok(id) => Some(id),
+                Err(e) => {
+                    tracing::warn!("Failed to record step span: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        steps.insert(
+            session_id.to_string(),
+            CurrentStep { phase: step_type.clone(), span_id, last_activity: now },
+        );
 
         tracing::info!(
             "STEP CHANGE: session={} {} -> {}",
             &session_id[..std::cmp::min(12, session_id.len())],
-            previous.as_deref().unwrap_or("none"),
+            previous.as_ref().map(|p| p.phase.as_str()).unwrap_or("none"),
             step_type
         );
 
         // Create completed update for previous step
         let completed_update = previous.map(|prev_step| StepUpdateData {
-            step_type: prev_step,
+            step_type: prev_step.phase,
             status: "COMPLETED".to_string(),
             progress: Some(100),
             message: Some("Step completed".to_string()),
@@ -126,7 +167,7 @@ impl StepTracker {
 
         // Create in_progress update for new step
         let new_update = Some(StepUpdateData {
-            step_type: step_type.to_string(),
+            step_type: step_type.clone(),
             status: "IN_PROGRESS".to_string(),
             progress: None,
             message: Some(format!("Started {} with {}", step_type.to_lowercase(), tool_name)),
@@ -171,39 +212,34 @@ impl StepTracker {
         }
     }
 
-    /// Complete the current step when session/response ends
+    /// Complete the current step when the turn ends
     pub async fn complete_current_step(&self, session_id: &str) -> Option<StepUpdateData> {
-        let mut steps = self.current_steps.write().await;
-        if let Some(current_step) = steps.remove(session_id) {
-            tracing::info!(
-                "STEP COMPLETE (session end): session={} step={}",
-                &session_id[..std::cmp::min(12, session_id.len())],
-                current_step
-            );
-
-            // Track completed step
-            let mut completed = self.completed_steps.write().await;
-            completed
-                .entry(session_id.to_string())
-                .or_default()
-                .push(current_step.clone());
-
-            Some(StepUpdateData {
-                step_type: current_step,
-                status: "COMPLETED".to_string(),
-                progress: Some(100),
-                message: Some("Step completed".to_string()),
-                tool_name: None,
-            })
-        } else {
-            None
-        }
+        let current_step = self.current_steps.write().await.remove(session_id)?;
+        tracing::info!(
+            "STEP COMPLETE (session end): session={} step={}",
+            &session_id[..std::cmp::min(12, session_id.len())],
+            current_step.phase
+        );
+        self.close_span(&current_step, chrono::Utc::now().timestamp()).await;
+
+        Some(StepUpdateData {
+            step_type: current_step.phase,
+            status: "COMPLETED".to_string(),
+            progress: Some(100),
+            message: Some("Step completed".to_string()),
+            tool_name: None,
+        })
     }
 
     /// Get list of completed steps for a session
     pub async fn get_completed_steps(&self, session_id: &str) -> Vec<String> {
-        let completed = self.completed_steps.read().await;
-        completed.get(session_id).cloned().unwrap_or_default()
+        match &self.db {
+            Some(db) => db.get_completed_phases(session_id).await.unwrap_or_else(|e| {
+                tracing::warn!("Failed to load completed steps: {}", e);
+                Vec::new()
+            }),
+            None => Vec::new(),
+        }
     }
 
     /// Send a single step update
@@ -226,10 +262,35 @@ impl StepTracker {
 
     /// Clear session state
     pub async fn clear_session(&self, session_id: &str) {
-        let mut steps = self.current_steps.write().await;
-        steps.remove(session_id);
-        let mut completed = self.completed_steps.write().await;
-        completed.remove(session_id);
+        if let Some(current) = self.current_steps.write().await.remove(session_id) {
+            self.close_span(&current, current.last_activity).await;
+        }
+    }
+
+    /// Close the phase of sessions without tool calls for longer than the idle timeout
+    async fn evict_idle(&self, now: i64) {
+        let cutoff = now - self.rules.read().await.idle_timeout_secs;
+        let idle: Vec<CurrentStep> = {
+            let mut steps = self.current_steps.write().await;
+            let sessions: Vec<String> = steps
+                .iter()
+                .filter(|(_, step)| step.last_activity < cutoff)
+                .map(|(sid, _)| sid.clone())
+                .collect();
+            sessions.iter().filter_map(|sid| steps.remove(sid)).collect()
+        };
+        // 마지막 도구 호출 시점에 종료된 것으로 기록
+        for step in idle {
+            self.close_span(&step, step.last_activity).await;
+        }
+    }
+
+    async fn close_span(&self, step: &CurrentStep, ended_at: i64) {
+        if let (Some(db), Some(id)) = (&self.db, step.span_id) {
+            if let Err(e) = db.close_step_span(id, ended_at).await {
+                tracing::warn!("Failed to close step span: {}", e);
+            }
+        }
     }
 }
 
@@ -239,11 +300,52 @@ impl Default for StepTracker {
     }
 }
 
-impl Clone for StepTracker {
-    fn clone(&self) -> Self {
-        Self {
-            current_steps: self.current_steps.clone(),
-            completed_steps: self.completed_steps.clone(),
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_default_rules() {
+        let rules = StepRules::default();
+        assert_eq!(rules.phase_for("Read", None), Some("ANALYSIS"));
+        assert_eq!(rules.phase_for("Bash", Some(&json!({"command": "cargo test --workspace"}))), Some("VERIFICATION"));
+        assert_eq!(rules.phase_for("Bash", Some(&json!({"command": "mkdir -p src"}))), Some("IMPLEMENTATION"));
+        assert_eq!(rules.phase_for("AskUserQuestion", None), None);
+    }
+
+    #[test]
+    fn test_custom_phases_and_prefix() {
+        let mut config = StepConfig::default();
+        config.phases.push("REVIEW".to_string());
+        config.rules.insert(0, StepRule::new("mcp__github__*", Some("REVIEW")));
+        config.rules.insert(0, StepRule::new("Bash", Some("REVIEW")).with_pattern(r"^gh pr (?:view|diff)"));
+        config.rules.insert(0, StepRule::new("Skill", None));
+        let rules = StepRules::compile(&config).unwrap();
+        assert_eq!(rules.phase_for("mcp__github__get_pull_request", None), Some("REVIEW"));
+        assert_eq!(rules.phase_for("Bash", Some(&json!({"command": "gh pr diff 12"}))), Some("REVIEW"));
+        assert_eq!(rules.phase_for("Bash", Some(&json!({"command": "gh pr create"}))), Some("IMPLEMENTATION"));
+        assert_eq!(rules.phase_for("Skill", None), None);
+
+        config.rules.push(StepRule::new("Deploy", Some("RELEASE")));
+        assert!(StepRules::compile(&config).unwrap_err().contains("unknown phase RELEASE"));
+    }
+
+    #[tokio::test]
+    async fn test_step_changes() {
+        let tracker = StepTracker::new();
+        let (completed, started) = tracker.process_tool_use("s1", "Read", None).await;
+        assert!(completed.is_none());
+        assert_eq!(started.unwrap().step_type, "ANALYSIS");
+
+        let (completed, _) = tracker.process_tool_use("s1", "Grep", None).await;
+        assert!(completed.is_none());
+
+        let (completed, started) = tracker.process_tool_use("s1", "Edit", None).await;
+        assert_eq!(completed.unwrap().step_type, "ANALYSIS");
+        assert_eq!(started.unwrap().step_type, "IMPLEMENTATION");
+
+        assert_eq!(tracker.complete_current_step("s1").await.unwrap().step_type, "IMPLEMENTATION");
+        assert!(tracker.complete_current_step("s1").await.is_none());
     }
 }
@@ -0,0 +1,397 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// Tool names Claude Code uses to spawn subagents
+pub const TASK_TOOL_NAMES: &[&str] = &["Task", "Agent"];
+
+/// System prompt fragments used by Claude Code's built-in subagents
+const SUBAGENT_PROMPT_MARKERS: &[&str] = &[
+    "You are an agent for Claude Code",
+    "specialist for Claude Code",
+];
+
+/// Outstanding Task calls older than this are no longer used for correlation
+const PENDING_TASK_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// Marker separating a parent session ID from the subagent suffix
+const CHILD_SESSION_MARKER: &str = "/agent-";
+
+/// How a request was identified as a subagent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Detection {
+    /// System prompt matches a subagent prompt
+    SystemPrompt,
+    /// No Task tool while the parent session has an outstanding Task call
+    TaskCorrelation,
+    /// No session ID; attributed to the only session with outstanding Task calls
+    Timing,
+}
+
+impl Detection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Detection::SystemPrompt => "system_prompt",
+            Detection::TaskCorrelation => "task_correlation",
+            Detection::Timing => "timing",
+        }
+    }
+}
+
+/// A Task tool call found in the conversation history
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskCall {
+    pub tool_use_id: String,
+    pub prompt: String,
+    pub description: Option<String>,
+    pub subagent_type: Option<String>,
+}
+
+/// Parts of a Messages API request body relevant to subagent detection
+#[derive(Debug, Clone, Default)]
+pub struct RequestShape {
+    pub has_tools: bool,
+    pub has_task_tool: bool,
+    pub agent_system_prompt: bool,
+    /// Text of the first user message (the Task prompt for subagents)
+    pub first_user_text: Option<String>,
+    /// tool_result IDs in the last user message
+    pub tool_result_ids: Vec<String>,
+    /// Task calls made by the assistant whose results are in the last user message
+    pub completed_task_calls: Vec<TaskCall>,
+}
+
+impl RequestShape {
+    pub fn from_body(json: &Value) -> Self {
+        let tools = json.get("tools").and_then(|v| v.as_array());
+        let has_tools = tools.map(|t| !t.is_empty()).unwrap_or(false);
+        let has_task_tool = tools
+            .map(|t| {
+                t.iter().any(|tool| {
+                    tool.get("name")
+                        .and_then(|n| n.as_str())
+                        .map(|n| TASK_TOOL_NAMES.contains(&n))
+                        .unwrap_or(false)
+                })
+            })
+            .unwrap_or(false);
+
+        let system = system_text(json);
+        let agent_system_prompt = SUBAGENT_PROMPT_MARKERS.iter().any(|m| system.contains(m));
+
+        let messages = json.get("messages").and_then(|v| v.as_array());
+        let first_user_text = messages
+            .and_then(|m| m.first())
+            .filter(|m| m.get("role").and_then(|r| r.as_str()) == Some("user"))
+            .and_then(|m| m.get("content"))
+            .and_then(content_text);
+
+        let tool_result_ids: Vec<String> = messages
+            .and_then(|m| m.last())
+            .filter(|m| m.get("role").and_then(|r| r.as_str()) == Some("user"))
+            .and_then(|m| m.get("content"))
+            .and_then(|c| c.as_array())
+            .map(|blocks| {
+                blocks
+                    .iter()
+                    .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("tool_result"))
+                    .filter_map(|b| b.get("tool_use_id").and_then(|id| id.as_str()).map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let completed_task_calls = if tool_result_ids.is_empty() {
+            Vec::new()
+        } else {
+            messages
+                .map(|m| task_calls(m))
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|call| tool_result_ids.contains(&call.tool_use_id))
+                .collect()
+        };
+
+        Self {
+            has_tools,
+            has_task_tool,
+            agent_system_prompt,
+            first_user_text,
+            tool_result_ids,
+            completed_task_calls,
+        }
+    }
+}
+
+/// System prompt as plain text (string or text blocks joined by newlines)
+pub(crate) fn system_text(json: &Value) -> String {
+    match json.get("system") {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(blocks)) => blocks
+            .iter()
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Plain text of a message `content` (string or text blocks joined by newlines)
+pub(crate) fn content_text(content: &Value) -> Option<String> {
+    let text = match content {
+        Value::String(s) => s.trim().to_string(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string(),
+        _ => return None,
+    };
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// All Task tool_use blocks in assistant messages
+fn task_calls(messages: &[Value]) -> Vec<TaskCall> {
+    messages
+        .iter()
+        .filter(|m| m.get("role").and_then(|r| r.as_str()) == Some("assistant"))
+        .filter_map(|m| m.get("content").and_then(|c| c.as_array()))
+        .flatten()
+        .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("tool_use"))
+        .filter(|b| {
+            b.get("name")
+                .and_then(|n| n.as_str())
+                .map(|n| TASK_TOOL_NAMES.contains(&n))
+                .unwrap_or(false)
+        })
+        .filter_map(|b| {
+            let input = b.get("input")?;
+            let field = |name: &str| input.get(name).and_then(|v| v.as_str()).map(str::to_string);
+            Some(TaskCall {
+                tool_use_id: b.get("id")?.as_str()?.to_string(),
+                prompt: field("prompt")?,
+                description: field("description"),
+                subagent_type: field("subagent_type"),
+            })
+        })
+        .collect()
+}
+
+/// Stable child session ID: `{parent}/agent-{hash8}` where the hash covers the subagent's prompt
+pub fn child_session_id(parent_session_id: &str, prompt: &str) -> String {
+    format!("{}{}{:08x}", parent_session_id, CHILD_SESSION_MARKER, fnv1a(prompt.trim()) as u32)
+}
+
+pub fn is_child_session_id(session_id: &str) -> bool {
+    session_id.contains(CHILD_SESSION_MARKER)
+}
+
+// FNV-1a 64bit (프로세스/버전이 바뀌어도 같은 값이 나와야 하므로 DefaultHasher 대신 사용)
+pub(crate) fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Result of subagent detection for one request
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubagentMatch {
+    pub parent_session_id: String,
+    pub child_session_id: String,
+    /// Task tool_use ID that spawned this subagent, if correlated
+    pub tool_use_id: Option<String>,
+    pub detection: Detection,
+}
+
+#[derive(Debug, Clone)]
+struct PendingTask {
+    tool_use_id: String,
+    started_at: Instant,
+    child_session_id: Option<String>,
+}
+
+/// Tracks outstanding Task calls per parent session to correlate subagent requests
+#[derive(Debug, Clone, Default)]
+pub struct SubagentTracker {
+    pending: Arc<RwLock<HashMap<String, Vec<PendingTask>>>>,
+}
+
+impl SubagentTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A parent response started a Task tool call
+    pub async fn record_task_call(&self, parent_session_id: &str, tool_use_id: &str) {
+        let mut pending = self.pending.write().await;
+        let tasks = pending.entry(parent_session_id.to_string()).or_default();
+        if !tasks.iter().any(|t| t.tool_use_id == tool_use_id) {
+            tasks.push(PendingTask {
+                tool_use_id: tool_use_id.to_string(),
+                started_at: Instant::now(),
+                child_session_id: None,
+            });
+        }
+    }
+
+    /// The parent sent tool results; those Task calls are finished
+    pub async fn complete_task_calls(&self, parent_session_id: &str, tool_result_ids: &[String]) {
+        if tool_result_ids.is_empty() {
+            return;
+        }
+        let mut pending = self.pending.write().await;
+        if let Some(tasks) = pending.get_mut(parent_session_id) {
+            tasks.retain(|t| !tool_result_ids.contains(&t.tool_use_id));
+            if tasks.is_empty() {
+                pending.remove(parent_session_id);
+            }
+        }
+    }
+
+    /// Decide whether a request comes from a subagent and, if so, which session spawned it
+    pub async fn detect(&self, session_id: Option<&str>, shape: &RequestShape) -> Option<SubagentMatch> {
+        if shape.has_task_tool || session_id.map(is_child_session_id).unwrap_or(false) {
+            return None;
+        }
+        let prompt = shape.first_user_text.as_deref()?;
+
+        let mut pending = self.pending.write().await;
+        pending.retain(|_, tasks| {
+            tasks.retain(|t| t.started_at.elapsed() < PENDING_TASK_TTL);
+            !tasks.is_empty()
+        });
+
+        let (parent, detection) = match session_id {
+            Some(sid) if shape.agent_system_prompt => (sid.to_string(), Detection::SystemPrompt),
+            Some(sid) if shape.has_tools && pending.contains_key(sid) => (sid.to_string(), Detection::TaskCorrelation),
+            Some(_) => return None,
+            None if shape.has_tools || shape.agent_system_prompt => {
+                // 여러 세션에 Task가 대기 중이면 어느 세션의 것인지 알 수 없으므로 연결하지 않음
+                if pending.len() != 1 {
+                    return None;
+                }
+                (pending.keys().next()?.clone(), Detection::Timing)
+            }
+            None => return None,
+        };
+
+        let child = child_session_id(&parent, prompt);
+
+        // 이미 연결된 Task가 있으면 재사용, 없으면 가장 오래된 미할당 Task에 연결 (병렬 Task는 순서대로 시작됨)
+        let tool_use_id = pending.get_mut(&parent).and_then(|tasks| {
+            if let Some(task) = tasks.iter().find(|t| t.child_session_id.as_deref() == Some(child.as_str())) {
+                return Some(task.tool_use_id.clone());
+            }
+            let task = tasks.iter_mut().find(|t| t.child_session_id.is_none())?;
+            task.child_session_id = Some(child.clone());
+            Some(task.tool_use_id.clone())
+        });
+
+        Some(SubagentMatch {
+            parent_session_id: parent,
+            child_session_id: child,
+            tool_use_id,
+            detection,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn subagent_body(prompt: &str) -> Value {
+        json!({
+            "model": "claude-haiku",
+            "system": [{"type": "text", "text": "You are an agent for Claude Code, Anthropic's official CLI for Claude."}],
+            "tools": [{"name": "Read"}, {"name": "Grep"}],
+            "messages": [{"role": "user", "content": prompt}]
+        })
+    }
+
+    #[test]
+    fn test_request_shape() {
+        let main = json!({
+            "tools": [{"name": "Task"}, {"name": "Read"}],
+            "messages": [
+                {"role": "user", "content": [{"type": "text", "text": "fix the bug"}]},
+                {"role": "assistant", "content": [{"type": "tool_use", "id": "toolu_1", "name": "Task",
+                    "input": {"description": "Find bug", "prompt": "Search for the bug", "subagent_type": "Explore"}}]},
+                {"role": "user", "content": [{"type": "tool_result", "tool_use_id": "toolu_1", "content": "found"}]}
+            ]
+        });
+        let shape = RequestShape::from_body(&main);
+        assert!(shape.has_task_tool);
+        assert!(!shape.agent_system_prompt);
+        assert_eq!(shape.first_user_text.as_deref(), Some("fix the bug"));
+        assert_eq!(shape.tool_result_ids, vec!["toolu_1"]);
+        assert_eq!(shape.completed_task_calls.len(), 1);
+        assert_eq!(shape.completed_task_calls[0].subagent_type.as_deref(), Some("Explore"));
+
+        let shape = RequestShape::from_body(&subagent_body("Search for the bug"));
+        assert!(!shape.has_task_tool);
+        assert!(shape.agent_system_prompt);
+    }
+
+    #[test]
+    fn test_child_session_id_is_stable() {
+        let a = child_session_id("sess", "Search for the bug");
+        assert_eq!(a, child_session_id("sess", "  Search for the bug\n"));
+        assert!(a.starts_with("sess/agent-"));
+        assert_eq!(a.len(), "sess/agent-".len() + 8);
+        assert!(is_child_session_id(&a));
+        assert_ne!(a, child_session_id("sess", "Another task"));
+    }
+
+    #[tokio::test]
+    async fn test_detect_correlates_pending_task() {
+        let tracker = SubagentTracker::new();
+        tracker.record_task_call("parent", "toolu_1").await;
+        tracker.record_task_call("parent", "toolu_2").await;
+
+        let first = tracker
+            .detect(Some("parent"), &RequestShape::from_body(&subagent_body("task one")))
+            .await
+            .unwrap();
+        assert_eq!(first.detection, Detection::SystemPrompt);
+        assert_eq!(first.tool_use_id.as_deref(), Some("toolu_1"));
+
+        let second = tracker
+            .detect(Some("parent"), &RequestShape::from_body(&subagent_body("task two")))
+            .await
+            .unwrap();
+        assert_eq!(second.tool_use_id.as_deref(), Some("toolu_2"));
+
+        // 같은 subagent의 다음 턴은 같은 Task에 연결
+        let again = tracker
+            .detect(Some("parent"), &RequestShape::from_body(&subagent_body("task one")))
+            .await
+            .unwrap();
+        assert_eq!(again.child_session_id, first.child_session_id);
+        assert_eq!(again.tool_use_id.as_deref(), Some("toolu_1"));
+
+        // 세션 ID 없는 요청은 Task가 대기 중인 유일한 세션의 자식으로
+        let mut shape = RequestShape::from_body(&subagent_body("task three"));
+        shape.agent_system_prompt = false;
+        let timed = tracker.detect(None, &shape).await.unwrap();
+        assert_eq!(timed.parent_session_id, "parent");
+        assert_eq!(timed.detection, Detection::Timing);
+
+        // 다른 세션에도 Task가 대기 중이면 연결하지 않음
+        tracker.record_task_call("other", "toolu_3").await;
+        assert_eq!(tracker.detect(None, &shape).await, None);
+        tracker.complete_task_calls("other", &["toolu_3".to_string()]).await;
+
+        tracker.complete_task_calls("parent", &["toolu_1".to_string(), "toolu_2".to_string()]).await;
+        assert_eq!(tracker.detect(Some("parent"), &shape).await, None);
+    }
+}
@@ -0,0 +1,132 @@
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Upstream HTTP clients keyed by connect timeout.
+///
+/// No total request timeout is set: time-to-first-byte and inter-chunk idle
+/// timeouts are enforced per request in the proxy handler instead.
+#[derive(Clone, Default)]
+pub struct ClientPool {
+    clients: Arc<Mutex<HashMap<Duration, reqwest::Client>>>,
+}
+
+impl ClientPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, connect_timeout: Duration) -> reqwest::Client {
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        clients
+            .entry(connect_timeout)
+            .or_insert_with(|| {
+                reqwest::Client::builder()
+                    .connect_timeout(connect_timeout)
+                    .build()
+                    .unwrap_or_else(|_| reqwest::Client::new())
+            })
+            .clone()
+    }
+}
+
+/// Anthropic-style `error` SSE event sent when the upstream stream stalls
+pub fn idle_timeout_sse_event(idle: Duration) -> Bytes {
+    let data = serde_json::json!({
+        "type": "error",
+        "error": {
+            "type": "api_error",
+            "message": format!("Upstream stream idle for more than {}s (terminated by SwiftCast)", idle.as_secs()),
+        }
+    });
+    Bytes::from(format!("event: error\ndata: {}\n\n", data))
+}
+
+/// End the stream if no chunk arrives within `idle`.
+///
+/// SSE responses get a final `event: error` so the client sees a well-formed
+/// stream and can retry; other bodies are aborted with a `TimedOut` error.
+/// `on_timeout` runs once when the timeout fires.
+pub fn with_idle_timeout<S, E, F>(
+    stream: S,
+    idle: Duration,
+    is_sse: bool,
+    on_timeout: F,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>>
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+    E: std::error::Error + Send + Sync + 'static,
+    F: FnOnce() + Send + 'static,
+{
+    let state = (stream.boxed(), Some(on_timeout));
+    futures::stream::unfold(state, move |(mut stream, mut on_timeout)| async move {
+        // on_timeout가 None이면 이미 타임아웃 처리됨 → 스트림 종료
+        on_timeout.as_ref()?;
+
+        match tokio::time::timeout(idle, stream.next()).await {
+            Ok(Some(item)) => Some((item.map_err(std::io::Error::other), (stream, on_timeout))),
+            Ok(None) => None,
+            Err(_) => {
+                if let Some(f) = on_timeout.take() {
+                    f();
+                }
+                let item = if is_sse {
+                    Ok(idle_timeout_sse_event(idle))
+                } else {
+                    Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "upstream idle timeout"))
+                };
+                Some((item, (stream, None)))
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    type Chunk = Result<Bytes, std::io::Error>;
+
+    #[tokio::test]
+    async fn test_idle_timeout_emits_sse_error() {
+        let upstream = futures::stream::iter(vec![Chunk::Ok(Bytes::from("event: ping\n\n"))])
+            .chain(futures::stream::pending());
+        let fired = Arc::new(AtomicBool::new(false));
+        let fired_clone = fired.clone();
+
+        let chunks: Vec<_> = with_idle_timeout(upstream, Duration::from_millis(50), true, move || {
+            fired_clone.store(true, Ordering::SeqCst);
+        })
+        .collect()
+        .await;
+
+        assert_eq!(chunks.len(), 2);
+        let last = chunks[1].as_ref().unwrap();
+        let text = std::str::from_utf8(last).unwrap();
+        assert!(text.starts_with("event: error\ndata: "));
+        assert!(text.contains(r#""type":"api_error""#));
+        assert!(fired.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout_passes_through_complete_stream() {
+        let upstream = futures::stream::iter(vec![Chunk::Ok(Bytes::from("a")), Chunk::Ok(Bytes::from("b"))]);
+        let chunks: Vec<_> = with_idle_timeout(upstream, Duration::from_millis(50), true, || {})
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout_aborts_non_sse() {
+        let upstream = futures::stream::pending::<Chunk>();
+        let chunks: Vec<_> = with_idle_timeout(upstream, Duration::from_millis(20), false, || {})
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].as_ref().unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+    }
+}
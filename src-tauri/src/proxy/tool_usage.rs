@@ -0,0 +1,125 @@
+use crate::models::{ToolCall, ToolResult};
+use serde_json::Value;
+
+const COMMAND_MAX_CHARS: usize = 500;
+
+/// Tool calls of the previous assistant turn and the results answering them.
+///
+/// Claude Code resends the whole history, but only the last assistant/user pair is new,
+/// so only those two messages are inspected.
+pub fn last_turn(json: &Value) -> (Vec<ToolCall>, Vec<ToolResult>) {
+    let Some(messages) = json.get("messages").and_then(|m| m.as_array()) else {
+        return (Vec::new(), Vec::new());
+    };
+    let blocks = |message: Option<&Value>, role: &str| -> Vec<Value> {
+        message
+            .filter(|m| m.get("role").and_then(|r| r.as_str()) == Some(role))
+            .and_then(|m| m.get("content"))
+            .and_then(|c| c.as_array())
+            .cloned()
+            .unwrap_or_default()
+    };
+    let n = messages.len();
+    let results: Vec<ToolResult> = blocks(messages.last(), "user")
+        .iter()
+        .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("tool_result"))
+        .filter_map(|b| {
+            Some(ToolResult {
+                tool_use_id: b.get("tool_use_id")?.as_str()?.to_string(),
+                is_error: b.get("is_error").and_then(|v| v.as_bool()).unwrap_or(false),
+            })
+        })
+        .collect();
+    if results.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+
+    let calls = blocks(n.checked_sub(2).and_then(|i| messages.get(i)), "assistant")
+        .iter()
+        .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("tool_use"))
+        .filter_map(tool_call)
+        .collect();
+    (calls, results)
+}
+
+fn tool_call(block: &Value) -> Option<ToolCall> {
+    let input = block.get("input").cloned().unwrap_or(Value::Null);
+    let str_input = |name: &str| input.get(name).and_then(|v| v.as_str()).map(str::to_string);
+    let command = str_input("command").filter(|_| block.get("name").and_then(|n| n.as_str()) == Some("Bash"));
+
+    Some(ToolCall {
+        tool_use_id: block.get("id")?.as_str()?.to_string(),
+        tool_name: block.get("name")?.as_str()?.to_string(),
+        input_size: input.to_string().len() as i64,
+        command_head: command.as_deref().map(command_head),
+        command: command.map(|c| c.chars().take(COMMAND_MAX_CHARS).collect()),
+        file_path: str_input("file_path").or_else(|| str_input("notebook_path")),
+    })
+}
+
+/// Program plus subcommand of the first real command in a shell line
+/// (`cd app && FOO=1 cargo test --offline` → `cargo test`)
+pub fn command_head(command: &str) -> String {
+    let segment = command
+        .split(['&', ';', '|', '\n'])
+        .map(str::trim)
+        .find(|s| !s.is_empty() && s.split_whitespace().next() != Some("cd"))
+        .unwrap_or("");
+
+    let mut words = segment.split_whitespace().skip_while(|w| w.contains('=') && !w.starts_with('-'));
+    let Some(program) = words.next() else {
+        return String::new();
+    };
+    let program = program.rsplit('/').next().unwrap_or(program);
+    match words.next() {
+        Some(sub) if sub.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') && !sub.starts_with('-') => {
+            format!("{} {}", program, sub)
+        }
+        _ => program.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_command_head() {
+        assert_eq!(command_head("cargo test --offline"), "cargo test");
+        assert_eq!(command_head("cd src-tauri && RUST_LOG=debug cargo build"), "cargo build");
+        assert_eq!(command_head("ls -la /tmp"), "ls");
+        assert_eq!(command_head("/usr/bin/git status | head"), "git status");
+        assert_eq!(command_head("cat src/main.rs"), "cat");
+        assert_eq!(command_head(""), "");
+    }
+
+    #[test]
+    fn test_last_turn() {
+        let body = json!({"messages": [
+            {"role": "user", "content": "fix it"},
+            {"role": "assistant", "content": [
+                {"type": "text", "text": "ok"},
+                {"type": "tool_use", "id": "t1", "name": "Bash", "input": {"command": "cargo test"}},
+                {"type": "tool_use", "id": "t2", "name": "Edit", "input": {"file_path": "/a.rs", "old_string": "a", "new_string": "b"}}
+            ]},
+            {"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "t1", "is_error": true, "content": "failed"},
+                {"type": "tool_result", "tool_use_id": "t2", "content": "ok"}
+            ]}
+        ]});
+        let (calls, results) = last_turn(&body);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].command_head.as_deref(), Some("cargo test"));
+        assert_eq!(calls[1].file_path.as_deref(), Some("/a.rs"));
+        assert_eq!(calls[1].command, None);
+        assert_eq!(results, vec![
+            ToolResult { tool_use_id: "t1".to_string(), is_error: true },
+            ToolResult { tool_use_id: "t2".to_string(), is_error: false },
+        ]);
+
+        // 새 사용자 메시지만 있는 턴: 도구 결과 없음
+        let (calls, results) = last_turn(&json!({"messages": [{"role": "user", "content": "hi"}]}));
+        assert!(calls.is_empty() && results.is_empty());
+    }
+}
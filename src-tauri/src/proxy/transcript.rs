@@ -0,0 +1,176 @@
+use super::subagent::fnv1a;
+use crate::models::{TranscriptBlock, TranscriptState};
+use serde_json::Value;
+
+/// tool_result content is stored as a summary of at most this many characters
+const TOOL_RESULT_SUMMARY_CHARS: usize = 500;
+/// tool_use inputs (e.g. Write with a whole file) are capped at this many characters
+const TOOL_INPUT_MAX_CHARS: usize = 32 * 1024;
+
+/// Which messages of a request to store, given what is already stored for the session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppendPlan {
+    pub epoch: i64,
+    /// First message index to insert
+    pub start: usize,
+    /// Stream-captured assistant message to replace with its full version from the history
+    pub replace_index: Option<usize>,
+}
+
+/// Decide how to append a request with `message_count` messages whose first message hashes to `first_hash`
+pub fn plan_append(state: Option<&TranscriptState>, message_count: usize, first_hash: &str) -> AppendPlan {
+    let Some(state) = state else {
+        return AppendPlan { epoch: 0, start: 0, replace_index: None };
+    };
+
+    if state.first_hash != first_hash {
+        // 첫 메시지가 바뀜 (compaction / 다른 대화): 새 epoch로 전체 저장
+        return AppendPlan { epoch: state.epoch + 1, start: 0, replace_index: None };
+    }
+    if (message_count as i64) < state.message_count {
+        // 대화가 되감김 (rewind / 재시도): 새 epoch로 마지막 메시지만 저장
+        return AppendPlan {
+            epoch: state.epoch + 1,
+            start: message_count.saturating_sub(1),
+            replace_index: None,
+        };
+    }
+
+    AppendPlan {
+        epoch: state.epoch,
+        start: state.message_count as usize,
+        replace_index: state
+            .pending_response_index
+            .map(|i| i as usize)
+            .filter(|i| *i < message_count),
+    }
+}
+
+/// Hash of the first message's text (cache_control markers move between turns, so raw JSON is not used)
+pub fn first_message_hash(messages: &[Value]) -> String {
+    let text = messages
+        .first()
+        .map(|m| {
+            message_blocks(m)
+                .into_iter()
+                .map(|b| b.content)
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default();
+    format!("{:016x}", fnv1a(&text))
+}
+
+/// Normalize one Messages API message into transcript blocks
+pub fn message_blocks(message: &Value) -> Vec<TranscriptBlock> {
+    let role = message.get("role").and_then(|r| r.as_str()).unwrap_or("user").to_string();
+    let block = |entry_type: &str, content: String, tool_name: Option<String>, tool_use_id: Option<String>| TranscriptBlock {
+        role: role.clone(),
+        entry_type: entry_type.to_string(),
+        content,
+        tool_name,
+        tool_use_id,
+    };
+
+    let blocks = match message.get("content") {
+        Some(Value::String(text)) => return vec![block("text", text.clone(), None, None)],
+        Some(Value::Array(blocks)) => blocks,
+        _ => return Vec::new(),
+    };
+
+    let str_field = |b: &Value, name: &str| b.get(name).and_then(|v| v.as_str()).map(str::to_string);
+
+    blocks
+        .iter()
+        .filter_map(|b| match b.get("type").and_then(|t| t.as_str())? {
+            "text" => Some(block("text", str_field(b, "text")?, None, None)),
+            "thinking" => Some(block("thinking", str_field(b, "thinking")?, None, None)),
+            "tool_use" => {
+                let input = b.get("input").map(|i| i.to_string()).unwrap_or_default();
+                Some(block("tool_use", truncate(&input, TOOL_INPUT_MAX_CHARS), str_field(b, "name"), str_field(b, "id")))
+            }
+            "tool_result" => {
+                let text = match b.get("content") {
+                    Some(Value::String(s)) => s.clone(),
+                    Some(Value::Array(parts)) => parts
+                        .iter()
+                        .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    _ => String::new(),
+                };
+                let is_error = b.get("is_error").and_then(|v| v.as_bool()).unwrap_or(false);
+                let summary = truncate(&text, TOOL_RESULT_SUMMARY_CHARS);
+                let summary = if is_error { format!("[error] {}", summary) } else { summary };
+                Some(block("tool_result", summary, None, str_field(b, "tool_use_id")))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    if s.chars().count() > max_chars {
+        format!("{}...", s.chars().take(max_chars).collect::<String>())
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_message_blocks() {
+        let msg = json!({"role": "assistant", "content": [
+            {"type": "thinking", "thinking": "hmm", "signature": "x"},
+            {"type": "text", "text": "Let me look"},
+            {"type": "tool_use", "id": "toolu_1", "name": "Read", "input": {"file_path": "/a.rs"}}
+        ]});
+        let blocks = message_blocks(&msg);
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].entry_type, "thinking");
+        assert_eq!(blocks[2].tool_name.as_deref(), Some("Read"));
+        assert_eq!(blocks[2].content, r#"{"file_path":"/a.rs"}"#);
+
+        let long = "x".repeat(1000);
+        let msg = json!({"role": "user", "content": [
+            {"type": "tool_result", "tool_use_id": "toolu_1", "is_error": true, "content": [{"type": "text", "text": long}]}
+        ]});
+        let blocks = message_blocks(&msg);
+        assert!(blocks[0].content.starts_with("[error] xxx"));
+        assert_eq!(blocks[0].content.chars().count(), "[error] ".len() + TOOL_RESULT_SUMMARY_CHARS + 3);
+    }
+
+    #[test]
+    fn test_plan_append() {
+        assert_eq!(plan_append(None, 1, "h"), AppendPlan { epoch: 0, start: 0, replace_index: None });
+
+        // 응답이 스트림에서 저장된 뒤의 다음 턴: 새 user 메시지만 추가, 응답은 전체 버전으로 교체
+        let state = TranscriptState {
+            epoch: 0,
+            message_count: 2,
+            first_hash: "h".to_string(),
+            pending_response_index: Some(1),
+        };
+        assert_eq!(plan_append(Some(&state), 3, "h"), AppendPlan { epoch: 0, start: 2, replace_index: Some(1) });
+
+        // 같은 요청 재시도: 추가할 것 없음
+        assert_eq!(plan_append(Some(&state), 2, "h").start, 2);
+
+        assert_eq!(plan_append(Some(&state), 1, "h"), AppendPlan { epoch: 1, start: 0, replace_index: None });
+        assert_eq!(plan_append(Some(&state), 2, "other"), AppendPlan { epoch: 1, start: 0, replace_index: None });
+    }
+
+    #[test]
+    fn test_first_message_hash_ignores_cache_control() {
+        let a = json!([{"role": "user", "content": [{"type": "text", "text": "hi"}]}]);
+        let b = json!([{"role": "user", "content": [{"type": "text", "text": "hi", "cache_control": {"type": "ephemeral"}}]}]);
+        assert_eq!(
+            first_message_hash(a.as_array().unwrap()),
+            first_message_hash(b.as_array().unwrap())
+        );
+    }
+}
@@ -1,10 +1,29 @@
+use hmac::{Hmac, Mac};
 use reqwest::Client;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
+use tokio::sync::{Notify, RwLock};
+use tokio::task::JoinHandle;
 
-#[derive(Debug, Serialize)]
+use super::metrics::Metrics;
+use crate::models::WebhookSink;
+use crate::storage::database::OutboxEntry;
+use crate::storage::Database;
+
+/// Attempts before a delivery is dead-lettered (config `webhook_max_attempts`)
+pub const DEFAULT_MAX_ATTEMPTS: i64 = 10;
+/// Retry delays double from this...
+const RETRY_BASE_SECS: i64 = 5;
+/// ...up to this
+const RETRY_MAX_SECS: i64 = 15 * 60;
+/// Outbox poll interval when nothing is scheduled
+const OUTBOX_IDLE_POLL: Duration = Duration::from_secs(30);
+const OUTBOX_BATCH: i64 = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebhookPayload {
     pub event: String,
     pub todo_id: Option<String>,
@@ -13,6 +32,19 @@ pub struct WebhookPayload {
     pub data: serde_json::Value,
 }
 
+impl WebhookPayload {
+    /// Payload sent by "test webhook"
+    pub fn test() -> Self {
+        Self {
+            event: "test".to_string(),
+            todo_id: None,
+            session_id: "swiftcast-test".to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+            data: serde_json::json!({ "message": "SwiftCast webhook test" }),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct UsageData {
     pub model: String,
@@ -27,6 +59,7 @@ pub struct AIQuestionData {
     pub question: String,
     pub options: Vec<String>,
     pub context: String,
+    pub rule: String,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -43,14 +76,21 @@ pub struct SessionCompleteData {
     pub stop_reason: String,           // end_turn, tool_use, max_tokens
     pub total_input_tokens: i64,
     pub total_output_tokens: i64,
-    pub duration_ms: u64,
+    pub duration_ms: u64,              // Session wall time from the first request
+    pub active_ms: u64,                // Time spent in requests
+    pub turn_count: i64,
+    pub turn_duration_ms: u64,         // Wall time of the turn that just ended
     pub completed_steps: Vec<String>,  // List of completed step types
 }
 
+/// Delivers proxy events to the configured webhook sinks
 pub struct WebhookClient {
     client: Client,
-    base_url: Arc<RwLock<Option<String>>>,
-    enabled: Arc<RwLock<bool>>,
+    sinks: Arc<RwLock<Vec<WebhookSink>>>,
+    metrics: Metrics,
+    /// Durable queue; without it events are sent once, fire-and-forget
+    outbox: Option<Arc<Database>>,
+    wake: Arc<Notify>,
 }
 
 impl WebhookClient {
@@ -63,31 +103,38 @@ impl WebhookClient {
 
         Self {
             client,
-            base_url: Arc::new(RwLock::new(None)),
-            enabled: Arc::new(RwLock::new(false)),
+            sinks: Arc::new(RwLock::new(Vec::new())),
+            metrics: Metrics::new(),
+            outbox: None,
+            wake: Arc::new(Notify::new()),
         }
     }
 
-    /// Update webhook configuration
-    pub async fn configure(&self, base_url: Option<String>, enabled: bool) {
-        let mut url = self.base_url.write().await;
-        *url = base_url;
-        drop(url);
+    /// Queue events in the DB outbox and deliver them from `start_outbox_worker`
+    pub fn with_outbox(mut self, db: Arc<Database>) -> Self {
+        self.outbox = Some(db);
+        self
+    }
 
-        let mut en = self.enabled.write().await;
-        *en = enabled;
+    /// Count delivery failures in the given metrics registry
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
 
-        tracing::info!("Webhook configured: enabled={}, url={:?}", enabled, self.base_url);
+    /// Replace the sink list
+    pub async fn configure(&self, sinks: Vec<WebhookSink>) {
+        let enabled: Vec<&str> = sinks.iter().filter(|s| s.enabled).map(|s| s.name.as_str()).collect();
+        tracing::info!("Webhook sinks configured: {:?}", enabled);
+        *self.sinks.write().await = sinks;
     }
 
-    /// Check if webhook is enabled
+    /// Check if any sink is enabled
     pub async fn is_enabled(&self) -> bool {
-        let enabled = self.enabled.read().await;
-        let url = self.base_url.read().await;
-        *enabled && url.is_some()
+        self.sinks.read().await.iter().any(|s| s.enabled)
     }
 
-    /// Send usage data to ThreadCast
+    /// Send usage data
     pub async fn send_usage(
         &self,
         todo_id: Option<String>,
@@ -109,7 +156,7 @@ impl WebhookClient {
         self.send(&payload).await
     }
 
-    /// Send AI question detection to ThreadCast
+    /// Send AI question detection
     pub async fn send_ai_question(
         &self,
         todo_id: Option<String>,
@@ -131,7 +178,7 @@ impl WebhookClient {
         self.send(&payload).await
     }
 
-    /// Send step update to ThreadCast
+    /// Send step update
     pub async fn send_step_update(
         &self,
         todo_id: Option<String>,
@@ -153,7 +200,7 @@ impl WebhookClient {
         self.send(&payload).await
     }
 
-    /// Send session complete to ThreadCast (when stop_reason is "end_turn")
+    /// Send session complete (when stop_reason is "end_turn")
     pub async fn send_session_complete(
         &self,
         todo_id: Option<String>,
@@ -181,53 +228,248 @@ impl WebhookClient {
         self.send(&payload).await
     }
 
+    /// POST one payload to one sink and wait for the response status
+    pub async fn deliver(&self, sink: &WebhookSink, payload: &WebhookPayload) -> anyhow::Result<reqwest::StatusCode> {
+        self.post(sink, payload, None).await
+    }
+
+    /// `delivery_id` (the outbox row) lets receivers drop duplicates after a retry
+    async fn post(&self, sink: &WebhookSink, payload: &WebhookPayload, delivery_id: Option<i64>) -> anyhow::Result<reqwest::StatusCode> {
+        let payload_json = serde_json::to_value(payload)?;
+        let body = match &sink.template {
+            Some(template) => serde_json::to_vec(&render_template(template, &payload_json))?,
+            None => serde_json::to_vec(&payload_json)?,
+        };
+
+        let mut request = self
+            .client
+            .post(&sink.url)
+            .header("content-type", "application/json")
+            .header("X-SwiftCast-Event", &payload.event);
+        if let Some(id) = delivery_id {
+            request = request.header("X-SwiftCast-Delivery", id.to_string());
+        }
+        if let Some(secret) = sink.secret.as_deref().filter(|s| !s.is_empty()) {
+            request = request.header(sink.signature_header.as_str(), sign(secret, &body));
+        }
+        for (name, value) in &sink.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+
+        let response = request.body(body).send().await?;
+        Ok(response.status())
+    }
+
     async fn send(
         &self,
         payload: &WebhookPayload,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let base_url = self.base_url.read().await;
-        let url = match base_url.as_ref() {
-            Some(url) => format!("{}/api/webhooks/swiftcast", url),
-            None => return Ok(()),
-        };
-        drop(base_url);
+        let sinks: Vec<WebhookSink> = self
+            .sinks
+            .read()
+            .await
+            .iter()
+            .filter(|s| s.subscribes(&payload.event))
+            .cloned()
+            .collect();
 
-        // Non-blocking send - spawn task and don't wait
-        let client = self.client.clone();
-        let payload_json = serde_json::to_value(payload)?;
-        let event = payload.event.clone();
-        let todo_id = payload.todo_id.clone();
-
-        tokio::spawn(async move {
-            match client
-                .post(&url)
-                .json(&payload_json)
-                .send()
+        if let Some(db) = &self.outbox {
+            let payload_json = serde_json::to_string(payload)?;
+            for sink in &sinks {
+                db.enqueue_webhook(&sink.id, &payload.event, &payload.session_id, &payload_json).await?;
+            }
+            if !sinks.is_empty() {
+                self.wake.notify_one();
+            }
+            return Ok(());
+        }
+
+        // Non-blocking send - spawn one task per sink and don't wait
+        for sink in sinks {
+            let webhook = self.clone();
+            let payload = payload.clone();
+            tokio::spawn(async move {
+                match webhook.deliver(&sink, &payload).await {
+                    Ok(status) if status.is_success() => {
+                        tracing::debug!("Webhook sent: sink={}, event={}, todo={:?}", sink.name, payload.event, payload.todo_id);
+                    }
+                    Ok(status) => {
+                        webhook.metrics.record_webhook_failure(&payload.event);
+                        tracing::warn!("Webhook failed: sink={}, status={}, event={}", sink.name, status, payload.event);
+                    }
+                    Err(e) => {
+                        webhook.metrics.record_webhook_failure(&payload.event);
+                        tracing::debug!("Webhook error (ignored): sink={}, {}", sink.name, e);
+                    }
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Wake the outbox worker (e.g. after dead letters were replayed)
+    pub fn notify_outbox(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Deliver queued events until aborted (requires `with_outbox`)
+    pub fn start_outbox_worker(&self) -> Option<JoinHandle<()>> {
+        let db = self.outbox.clone()?;
+        let webhook = self.clone();
+        Some(tokio::spawn(async move {
+            let max_attempts = db
+                .get_config("webhook_max_attempts")
                 .await
-            {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        tracing::debug!(
-                            "Webhook sent: event={}, todo={:?}",
-                            event,
-                            todo_id
-                        );
-                    } else {
-                        tracing::warn!(
-                            "Webhook failed: status={}, event={}",
-                            response.status(),
-                            event
-                        );
+                .ok()
+                .flatten()
+                .and_then(|v| v.parse::<i64>().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(DEFAULT_MAX_ATTEMPTS);
+
+            loop {
+                let delivered = match webhook.deliver_due(&db, max_attempts).await {
+                    Ok(n) => n,
+                    Err(e) => {
+                        tracing::warn!("Webhook outbox error: {}", e);
+                        0
                     }
+                };
+                if delivered > 0 {
+                    // 같은 세션의 다음 이벤트가 바로 전달 가능할 수 있음
+                    continue;
+                }
+
+                let now = chrono::Utc::now().timestamp();
+                let wait = match db.next_webhook_attempt_at().await {
+                    Ok(Some(at)) => Duration::from_secs((at - now).max(1) as u64).min(OUTBOX_IDLE_POLL),
+                    _ => OUTBOX_IDLE_POLL,
+                };
+                tokio::select! {
+                    _ = webhook.wake.notified() => {}
+                    _ = tokio::time::sleep(wait) => {}
                 }
-                Err(e) => {
-                    tracing::debug!("Webhook error (ignored): {}", e);
+            }
+        }))
+    }
+
+    /// One pass over due deliveries; returns how many succeeded
+    async fn deliver_due(&self, db: &Database, max_attempts: i64) -> anyhow::Result<usize> {
+        let entries = db.due_webhook_deliveries(chrono::Utc::now().timestamp(), OUTBOX_BATCH).await?;
+        if entries.is_empty() {
+            return Ok(0);
+        }
+        let sinks = self.sinks.read().await.clone();
+        // 각 항목은 서로 다른 싱크/세션 큐의 선두이므로 동시에 전달해도 순서가 유지됨
+        let results = futures::future::join_all(entries.into_iter().map(|entry| {
+            let sink = sinks.iter().find(|s| s.id == entry.sink_id).cloned();
+            self.deliver_entry(db, entry, sink, max_attempts)
+        }))
+        .await;
+        Ok(results.into_iter().filter(|delivered| *delivered).count())
+    }
+
+    async fn deliver_entry(&self, db: &Database, entry: OutboxEntry, sink: Option<WebhookSink>, max_attempts: i64) -> bool {
+        let result = match (&sink, serde_json::from_str::<WebhookPayload>(&entry.payload)) {
+            (None, _) => Err("sink no longer configured".to_string()),
+            (Some(_), Err(e)) => Err(format!("invalid payload: {}", e)),
+            (Some(sink), Ok(payload)) => match self.post(sink, &payload, Some(entry.id)).await {
+                Ok(status) if status.is_success() => Ok(()),
+                Ok(status) => Err(format!("HTTP {}", status)),
+                Err(e) => Err(e.to_string()),
+            },
+        };
+        let sink_name = sink.as_ref().map(|s| s.name.as_str()).unwrap_or(&entry.sink_id);
+
+        let stored = match result {
+            Ok(()) => {
+                tracing::debug!("Webhook sent: sink={}, event={}, session={}", sink_name, entry.event, entry.session_id);
+                db.complete_webhook_delivery(entry.id).await.map(|_| true)
+            }
+            Err(error) => {
+                self.metrics.record_webhook_failure(&entry.event);
+                let attempts = entry.attempts + 1;
+                if sink.is_none() || attempts >= max_attempts {
+                    self.metrics.record_webhook_dead_letter(&entry.event);
+                    tracing::warn!(
+                        "Webhook dead-lettered after {} attempts: sink={}, event={}, error={}",
+                        attempts, sink_name, entry.event, error
+                    );
+                    db.dead_letter_webhook(entry.id, attempts, &error).await.map(|_| false)
+                } else {
+                    let next = chrono::Utc::now().timestamp() + retry_delay_secs(attempts);
+                    tracing::warn!(
+                        "Webhook failed (attempt {}/{}), retrying in {}s: sink={}, event={}, error={}",
+                        attempts, max_attempts, retry_delay_secs(attempts), sink_name, entry.event, error
+                    );
+                    db.retry_webhook_delivery(entry.id, &error, next).await.map(|_| false)
                 }
             }
-        });
+        };
+        stored.unwrap_or_else(|e| {
+            tracing::warn!("Failed to update webhook outbox: {}", e);
+            false
+        })
+    }
+}
 
-        Ok(())
+/// Delay before the next attempt after `attempts` failures (5s, 10s, 20s, ... up to 15 min)
+fn retry_delay_secs(attempts: i64) -> i64 {
+    let exp = (attempts - 1).clamp(0, 20) as u32;
+    (RETRY_BASE_SECS * 2_i64.pow(exp)).min(RETRY_MAX_SECS)
+}
+
+/// `sha256=<hex HMAC-SHA256 of the body>`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Fill `{{path}}` placeholders (`event`, `session_id`, `data.model`, `data.options.0`, ...) from the payload.
+/// A string that is exactly one placeholder takes the value as-is (numbers, arrays, objects);
+/// placeholders inside longer strings are interpolated as text, missing values as empty.
+pub fn render_template(template: &Value, payload: &Value) -> Value {
+    match template {
+        Value::String(s) => {
+            let trimmed = s.trim();
+            if let Some(path) = trimmed.strip_prefix("{{").and_then(|t| t.strip_suffix("}}")) {
+                if !path.contains("{{") {
+                    return lookup(payload, path.trim()).cloned().unwrap_or(Value::Null);
+                }
+            }
+            Value::String(interpolate(s, payload))
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|v| render_template(v, payload)).collect()),
+        Value::Object(map) => Value::Object(map.iter().map(|(k, v)| (k.clone(), render_template(v, payload))).collect()),
+        other => other.clone(),
+    }
+}
+
+fn interpolate(s: &str, payload: &Value) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        match lookup(payload, rest[start + 2..start + end].trim()) {
+            Some(Value::String(v)) => out.push_str(v),
+            Some(Value::Null) | None => {}
+            Some(v) => out.push_str(&v.to_string()),
+        }
+        rest = &rest[start + end + 2..];
     }
+    out.push_str(rest);
+    out
+}
+
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |v, key| match v {
+        Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+        _ => v.get(key),
+    })
 }
 
 impl Default for WebhookClient {
@@ -240,8 +482,67 @@ impl Clone for WebhookClient {
     fn clone(&self) -> Self {
         Self {
             client: self.client.clone(),
-            base_url: self.base_url.clone(),
-            enabled: self.enabled.clone(),
+            sinks: self.sinks.clone(),
+            metrics: self.metrics.clone(),
+            outbox: self.outbox.clone(),
+            wake: self.wake.clone(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_template() {
+        let payload = json!({
+            "event": "ai_question_detected",
+            "session_id": "s1",
+            "timestamp": 1790000000,
+            "data": {"question": "Which database?", "options": ["SQLite", "Postgres"]}
+        });
+        let template = json!({
+            "text": "*{{event}}* in `{{ session_id }}`: {{data.question}} ({{data.options.0}}, {{data.missing}})",
+            "options": "{{data.options}}",
+            "ts": "{{timestamp}}",
+            "fixed": true
+        });
+        assert_eq!(
+            render_template(&template, &payload),
+            json!({
+                "text": "*ai_question_detected* in `s1`: Which database? (SQLite, )",
+                "options": ["SQLite", "Postgres"],
+                "ts": 1790000000,
+                "fixed": true
+            })
+        );
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay_secs(1), 5);
+        assert_eq!(retry_delay_secs(2), 10);
+        assert_eq!(retry_delay_secs(5), 80);
+        assert_eq!(retry_delay_secs(9), RETRY_MAX_SECS);
+        assert_eq!(retry_delay_secs(100), RETRY_MAX_SECS);
+    }
+
+    #[test]
+    fn test_sign_and_subscribe() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+
+        let mut sink = WebhookSink::new("slack", "https://hooks.slack.com/services/x");
+        assert!(sink.subscribes("step_update"));
+        sink.events = vec!["session_complete".to_string()];
+        assert!(!sink.subscribes("step_update"));
+        assert!(sink.validate().is_ok());
+        sink.events.push("usage".to_string());
+        assert!(sink.validate().is_err());
+    }
+}
@@ -1,6 +1,10 @@
-use crate::models::{Account, SessionConfig, SessionDetail};
+use crate::models::{
+    tool_usage::FILE_EDIT_TOOLS, Account, AlertFiring, AlertRule, AlertScope, ProjectInfo, SessionConfig, SessionDetail, SessionState, StepConfig, ToolCall, ToolResult, TranscriptAppend, TranscriptBlock, TranscriptEntry, TranscriptState, WebhookSink,
+};
+use std::collections::HashMap;
 use anyhow::Result;
-use sqlx::{sqlite::{SqlitePool, SqlitePoolOptions}, Row};
+use sqlx::{sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions}, Row};
+use std::str::FromStr;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -36,11 +40,14 @@ impl Database {
         // Use absolute path with sqlite: prefix
         let db_url = format!("sqlite:{}", db_path.to_str().unwrap());
 
+        // 첫 실행(헤드리스 포함) 시 DB 파일이 없으면 생성
+        let connect_options = SqliteConnectOptions::from_str(&db_url)?.create_if_missing(true);
+
         // 연결 풀 생성 (최대 연결 수 및 타임아웃 설정)
         let pool = SqlitePoolOptions::new()
             .max_connections(DB_MAX_CONNECTIONS)
             .acquire_timeout(Duration::from_secs(DB_ACQUIRE_TIMEOUT_SECS))
-            .connect(&db_url)
+            .connect_with(connect_options)
             .await?;
 
         // 테이블 생성
@@ -80,6 +87,13 @@ impl Database {
         .execute(&pool)
         .await?;
 
+        // 계정별 업스트림 타임아웃 컬럼 추가 (마이그레이션, NULL = 기본값)
+        for column in ["connect_timeout_secs", "first_byte_timeout_secs", "idle_timeout_secs"] {
+            let _ = sqlx::query(&format!("ALTER TABLE accounts ADD COLUMN {} INTEGER", column))
+                .execute(&pool)
+                .await;
+        }
+
         // 기존 테이블에 session_id 컬럼 추가 (마이그레이션)
         let _ = sqlx::query("ALTER TABLE usage_logs ADD COLUMN session_id TEXT")
             .execute(&pool)
@@ -109,7 +123,8 @@ impl Database {
                 ('hooks_retention_days', '30'),
                 ('compaction_injection_enabled', 'false'),
                 ('compaction_summarization_instructions', ''),
-                ('compaction_context_injection', '')
+                ('compaction_context_injection', ''),
+                ('transcripts_enabled', 'true')
             "#,
         )
         .execute(&pool)
@@ -206,6 +221,376 @@ impl Database {
         .execute(&pool)
         .await?;
 
+        // 세션 별칭 테이블 (/_swiftcast/session 등록: 원본 세션 ID → 지정 세션 ID)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS session_aliases (
+                alias TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // 세션 ID를 결정한 resolver 기록 (마이그레이션)
+        let _ = sqlx::query("ALTER TABLE usage_logs ADD COLUMN session_source TEXT")
+            .execute(&pool)
+            .await;
+
+        // 요청 종류 기록 (main_turn, subagent, title_generation, ...) (마이그레이션)
+        let _ = sqlx::query("ALTER TABLE usage_logs ADD COLUMN request_kind TEXT")
+            .execute(&pool)
+            .await;
+
+        // 서브에이전트 세션 관계 테이블 (Task 도구로 생성된 자식 세션 → 부모 세션)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS session_relations (
+                child_session_id TEXT PRIMARY KEY,
+                parent_session_id TEXT NOT NULL,
+                tool_use_id TEXT,
+                agent_type TEXT,
+                description TEXT,
+                detection TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+        let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_session_relations_parent ON session_relations(parent_session_id)")
+            .execute(&pool)
+            .await;
+
+        // 대화 기록 테이블 (정규화된 블록 단위: text, thinking, tool_use, tool_result, usage)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS transcript_entries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL,
+                epoch INTEGER NOT NULL,
+                msg_index INTEGER NOT NULL,
+                request_id TEXT,
+                role TEXT NOT NULL,
+                entry_type TEXT NOT NULL,
+                content TEXT NOT NULL,
+                tool_name TEXT,
+                tool_use_id TEXT,
+                input_tokens INTEGER,
+                output_tokens INTEGER,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+        let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_transcript_entries_session ON transcript_entries(session_id, epoch, msg_index)")
+            .execute(&pool)
+            .await;
+
+        // 세션별 대화 기록 상태 (매 턴 재전송되는 히스토리 중복 저장 방지)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS transcript_sessions (
+                session_id TEXT PRIMARY KEY,
+                epoch INTEGER NOT NULL,
+                message_count INTEGER NOT NULL,
+                first_hash TEXT NOT NULL,
+                pending_response_index INTEGER,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // 요청 ID 기록 (검색 결과 → 계정/모델/종류 연결) (마이그레이션)
+        let _ = sqlx::query("ALTER TABLE usage_logs ADD COLUMN request_id TEXT")
+            .execute(&pool)
+            .await;
+        let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_usage_logs_request_id ON usage_logs(request_id)")
+            .execute(&pool)
+            .await;
+
+        // 내보내기/가져오기용 고유 행 ID와 출처 (다른 머신에서 가져온 행은 origin 기록)
+        let _ = sqlx::query("ALTER TABLE usage_logs ADD COLUMN row_uid TEXT")
+            .execute(&pool)
+            .await;
+        let _ = sqlx::query("ALTER TABLE usage_logs ADD COLUMN origin TEXT")
+            .execute(&pool)
+            .await;
+        sqlx::query("UPDATE usage_logs SET row_uid = lower(hex(randomblob(16))) WHERE row_uid IS NULL")
+            .execute(&pool)
+            .await?;
+        let _ = sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_usage_logs_row_uid ON usage_logs(row_uid)")
+            .execute(&pool)
+            .await;
+
+        // 프로젝트(작업 디렉토리) 및 git 브랜치 기록 (마이그레이션)
+        for column in ["project_path", "git_branch"] {
+            let _ = sqlx::query(&format!("ALTER TABLE usage_logs ADD COLUMN {} TEXT", column))
+                .execute(&pool)
+                .await;
+        }
+        let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_usage_logs_project ON usage_logs(project_path)")
+            .execute(&pool)
+            .await;
+
+        // 지연 시간 (duration_ms는 기존 컬럼, 업스트림 전송 → 스트림 종료) (마이그레이션)
+        for (column, sql_type) in [("queue_ms", "INTEGER"), ("headers_ms", "INTEGER"), ("ttft_ms", "INTEGER"), ("output_tokens_per_sec", "REAL")] {
+            let _ = sqlx::query(&format!("ALTER TABLE usage_logs ADD COLUMN {} {}", column, sql_type))
+                .execute(&pool)
+                .await;
+        }
+
+        // 세션별 프로젝트 (환경 정보가 없는 백그라운드 요청은 세션의 프로젝트를 사용)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS session_projects (
+                session_id TEXT PRIMARY KEY,
+                project_path TEXT NOT NULL,
+                is_git_repo INTEGER NOT NULL DEFAULT 0,
+                git_branch TEXT,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // 도구 호출 기록 (스트림에서 tool_use 시작 시 생성, 다음 요청의 히스토리로 입력/결과 보완)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS tool_uses (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                tool_use_id TEXT NOT NULL UNIQUE,
+                session_id TEXT,
+                model TEXT NOT NULL,
+                tool_name TEXT NOT NULL,
+                input_size INTEGER,
+                command TEXT,
+                command_head TEXT,
+                file_path TEXT,
+                is_error INTEGER,
+                created_at INTEGER NOT NULL,
+                completed_at INTEGER
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+        let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_tool_uses_session ON tool_uses(session_id)")
+            .execute(&pool)
+            .await;
+        let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_tool_uses_created ON tool_uses(created_at)")
+            .execute(&pool)
+            .await;
+
+        // 업스트림/전송 오류 기록 (오류율 통계용)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS error_logs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                account_id TEXT NOT NULL,
+                model TEXT NOT NULL,
+                status_code INTEGER NOT NULL,
+                error_type TEXT NOT NULL,
+                message TEXT NOT NULL,
+                retry_after_secs INTEGER,
+                phase TEXT NOT NULL,
+                session_id TEXT,
+                request_id TEXT,
+                request_kind TEXT
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+        let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_error_logs_timestamp ON error_logs(timestamp)")
+            .execute(&pool)
+            .await;
+        let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_error_logs_account ON error_logs(account_id)")
+            .execute(&pool)
+            .await;
+
+        // 알림 발생 기록 (규칙/대상/기간당 한 번만 발생시키기 위해 사용)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS alert_firings (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                rule_id TEXT NOT NULL,
+                rule_name TEXT NOT NULL,
+                metric TEXT NOT NULL,
+                group_key TEXT NOT NULL,
+                window_key TEXT NOT NULL,
+                value REAL NOT NULL,
+                threshold REAL NOT NULL,
+                message TEXT NOT NULL,
+                fired_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+        let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_alert_firings_rule ON alert_firings(rule_id, group_key, window_key)")
+            .execute(&pool)
+            .await;
+
+        // 웹훅 아웃박스 (싱크/세션별 순서대로 재시도하며 전달, AUTOINCREMENT로 ID 재사용 방지)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS webhook_outbox (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                sink_id TEXT NOT NULL,
+                event TEXT NOT NULL,
+                session_id TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                next_attempt_at INTEGER NOT NULL,
+                last_error TEXT,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+        let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_webhook_outbox_order ON webhook_outbox(sink_id, session_id, id)")
+            .execute(&pool)
+            .await;
+
+        // 최대 시도 횟수를 넘긴 전달 (재전송/삭제 가능)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS webhook_dead_letters (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                outbox_id INTEGER NOT NULL,
+                sink_id TEXT NOT NULL,
+                event TEXT NOT NULL,
+                session_id TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                attempts INTEGER NOT NULL,
+                last_error TEXT,
+                created_at INTEGER NOT NULL,
+                failed_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // 세션 수명 주기 (상태, 턴 수, 요청 처리 시간)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS session_lifecycle (
+                session_id TEXT PRIMARY KEY,
+                state TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                last_activity_at INTEGER NOT NULL,
+                state_changed_at INTEGER NOT NULL,
+                turn_count INTEGER NOT NULL DEFAULT 0,
+                active_ms INTEGER NOT NULL DEFAULT 0,
+                ended_at INTEGER,
+                end_reason TEXT
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+        let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_session_lifecycle_state ON session_lifecycle(state, last_activity_at)")
+            .execute(&pool)
+            .await;
+
+        // 턴 기록 (사용자 메시지부터 end_turn까지)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS session_turns (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL,
+                turn_number INTEGER NOT NULL,
+                started_at INTEGER NOT NULL,
+                ended_at INTEGER,
+                request_count INTEGER NOT NULL DEFAULT 0,
+                active_ms INTEGER NOT NULL DEFAULT 0,
+                stop_reason TEXT
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+        let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_session_turns_session ON session_turns(session_id, turn_number)")
+            .execute(&pool)
+            .await;
+
+        // 단계(phase) 구간 기록 (세션별 분석/설계/구현/검증 시간)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS step_spans (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL,
+                phase TEXT NOT NULL,
+                first_tool TEXT NOT NULL,
+                tool_calls INTEGER NOT NULL DEFAULT 1,
+                started_at INTEGER NOT NULL,
+                last_tool_at INTEGER NOT NULL,
+                ended_at INTEGER
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+        let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_step_spans_session ON step_spans(session_id, started_at)")
+            .execute(&pool)
+            .await;
+        let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_step_spans_started ON step_spans(started_at)")
+            .execute(&pool)
+            .await;
+
+        // 대화 기록 전문 검색 인덱스 (FTS5; 프롬프트, 응답 텍스트, 도구 입력)
+        let fts_exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'transcript_fts')",
+        )
+        .fetch_one(&pool)
+        .await?;
+        if !fts_exists {
+            sqlx::query("CREATE VIRTUAL TABLE transcript_fts USING fts5(content, tool_name)")
+                .execute(&pool)
+                .await?;
+            // 기존 대화 기록 인덱싱 (최초 1회)
+            sqlx::query(
+                r#"
+                INSERT INTO transcript_fts (rowid, content, tool_name)
+                SELECT id, content, COALESCE(tool_name, '') FROM transcript_entries
+                WHERE entry_type IN ('text', 'tool_use')
+                "#,
+            )
+            .execute(&pool)
+            .await?;
+        }
+        sqlx::query(
+            r#"
+            CREATE TRIGGER IF NOT EXISTS transcript_fts_insert AFTER INSERT ON transcript_entries
+            WHEN new.entry_type IN ('text', 'tool_use')
+            BEGIN
+                INSERT INTO transcript_fts (rowid, content, tool_name) VALUES (new.id, new.content, COALESCE(new.tool_name, ''));
+            END
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query(
+            r#"
+            CREATE TRIGGER IF NOT EXISTS transcript_fts_delete AFTER DELETE ON transcript_entries
+            BEGIN
+                DELETE FROM transcript_fts WHERE rowid = old.id;
+            END
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
         // 오래된 데이터 자동 정리
         let db = Self { pool };
         db.cleanup_old_data().await?;
@@ -242,7 +627,7 @@ impl Database {
 
     pub async fn get_accounts(&self) -> Result<Vec<Account>> {
         let accounts = sqlx::query_as::<_, Account>(
-            "SELECT id, name, base_url, created_at, is_active FROM accounts ORDER BY created_at DESC"
+            "SELECT id, name, base_url, created_at, is_active, connect_timeout_secs, first_byte_timeout_secs, idle_timeout_secs FROM accounts ORDER BY created_at DESC"
         )
         .fetch_all(&self.pool)
         .await?;
@@ -252,7 +637,7 @@ impl Database {
 
     pub async fn get_active_account(&self) -> Result<Option<Account>> {
         let account = sqlx::query_as::<_, Account>(
-            "SELECT id, name, base_url, created_at, is_active FROM accounts WHERE is_active = 1"
+            "SELECT id, name, base_url, created_at, is_active, connect_timeout_secs, first_byte_timeout_secs, idle_timeout_secs FROM accounts WHERE is_active = 1"
         )
         .fetch_optional(&self.pool)
         .await?;
@@ -275,6 +660,30 @@ impl Database {
         Ok(())
     }
 
+    // 계정별 타임아웃 설정 (None = 기본값)
+    pub async fn set_account_timeouts(
+        &self,
+        account_id: &str,
+        connect_timeout_secs: Option<u32>,
+        first_byte_timeout_secs: Option<u32>,
+        idle_timeout_secs: Option<u32>,
+    ) -> Result<()> {
+        let result = sqlx::query(
+            "UPDATE accounts SET connect_timeout_secs = ?, first_byte_timeout_secs = ?, idle_timeout_secs = ? WHERE id = ?",
+        )
+        .bind(connect_timeout_secs)
+        .bind(first_byte_timeout_secs)
+        .bind(idle_timeout_secs)
+        .bind(account_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            anyhow::bail!("Account not found: {}", account_id);
+        }
+        Ok(())
+    }
+
     pub async fn delete_account(&self, account_id: &str) -> Result<()> {
         sqlx::query("DELETE FROM accounts WHERE id = ?")
             .bind(account_id)
@@ -333,36 +742,42 @@ impl Database {
     }
 
     // 사용량 로깅
-    pub async fn log_usage(
-        &self,
-        account_id: &str,
-        model: &str,
-        input_tokens: i64,
-        output_tokens: i64,
-        session_id: Option<&str>,
-    ) -> Result<()> {
+    pub async fn log_usage(&self, entry: &UsageEntry) -> Result<()> {
         let timestamp = chrono::Utc::now().timestamp();
 
         sqlx::query(
             r#"
-            INSERT INTO usage_logs (timestamp, account_id, model, input_tokens, output_tokens, cost_usd, duration_ms, status_code, session_id)
-            VALUES (?, ?, ?, ?, ?, 0, 0, 200, ?)
+            INSERT INTO usage_logs (timestamp, account_id, model, input_tokens, output_tokens, cost_usd, duration_ms, status_code, session_id, session_source, request_kind, request_id, row_uid, project_path, git_branch,
+                                    queue_ms, headers_ms, ttft_ms, output_tokens_per_sec)
+            VALUES (?, ?, ?, ?, ?, ?, ?, 200, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(timestamp)
-        .bind(account_id)
-        .bind(model)
-        .bind(input_tokens)
-        .bind(output_tokens)
-        .bind(session_id)
+        .bind(&entry.account_id)
+        .bind(&entry.model)
+        .bind(entry.input_tokens)
+        .bind(entry.output_tokens)
+        .bind(entry.cost_usd)
+        .bind(entry.duration_ms)
+        .bind(&entry.session_id)
+        .bind(&entry.session_source)
+        .bind(&entry.request_kind)
+        .bind(&entry.request_id)
+        .bind(uuid::Uuid::new_v4().simple().to_string())
+        .bind(&entry.project_path)
+        .bind(&entry.git_branch)
+        .bind(entry.queue_ms)
+        .bind(entry.headers_ms)
+        .bind(entry.ttft_ms)
+        .bind(entry.output_tokens_per_sec)
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
-    // 사용량 통계 조회
-    pub async fn get_usage_stats(&self) -> Result<(i64, i64, i64)> {
+    // 사용량 통계 조회 (project: 프로젝트 경로 또는 이름으로 필터)
+    pub async fn get_usage_stats(&self, project: Option<&str>) -> Result<(i64, i64, i64)> {
         let row = sqlx::query(
             r#"
             SELECT
@@ -370,8 +785,12 @@ impl Database {
                 COALESCE(SUM(input_tokens), 0) as total_input_tokens,
                 COALESCE(SUM(output_tokens), 0) as total_output_tokens
             FROM usage_logs
+            WHERE (? IS NULL OR project_path = ? OR project_path LIKE '%/' || ?)
             "#,
         )
+        .bind(project)
+        .bind(project)
+        .bind(project)
         .fetch_one(&self.pool)
         .await?;
 
@@ -415,16 +834,88 @@ impl Database {
         Ok(auto_start == "true")
     }
 
+    // Hook 설정 조회 (config 테이블 키 기반)
+    pub async fn get_hook_config(&self) -> Result<HookConfig> {
+        let hooks_enabled = self.get_config("hooks_enabled").await?
+            .map(|v| v == "true")
+            .unwrap_or(true);
+
+        let hooks_retention_days = self.get_config("hooks_retention_days").await?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30);
+
+        let compaction_injection_enabled = self.get_config("compaction_injection_enabled").await?
+            .map(|v| v == "true")
+            .unwrap_or(false);
+
+        let compaction_summarization_instructions = self.get_config("compaction_summarization_instructions").await?
+            .unwrap_or_default();
+
+        let compaction_context_injection = self.get_config("compaction_context_injection").await?
+            .unwrap_or_default();
+
+        Ok(HookConfig {
+            hooks_enabled,
+            hooks_retention_days,
+            compaction_injection_enabled,
+            compaction_summarization_instructions,
+            compaction_context_injection,
+        })
+    }
+
+    // Hook 설정 저장
+    pub async fn set_hook_config(&self, config: &HookConfig) -> Result<()> {
+        self.set_config("hooks_enabled", if config.hooks_enabled { "true" } else { "false" }).await?;
+        self.set_config("hooks_retention_days", &config.hooks_retention_days.to_string()).await?;
+        self.set_config("compaction_injection_enabled", if config.compaction_injection_enabled { "true" } else { "false" }).await?;
+        self.set_config("compaction_summarization_instructions", &config.compaction_summarization_instructions).await?;
+        self.set_config("compaction_context_injection", &config.compaction_context_injection).await?;
+        Ok(())
+    }
+
+    /// Admin API token; generated and persisted on first use
+    pub async fn get_admin_api_token(&self) -> Result<String> {
+        if let Some(token) = self.get_config("admin_api_token").await?.filter(|t| !t.is_empty()) {
+            return Ok(token);
+        }
+        self.regenerate_admin_api_token().await
+    }
+
+    /// Replace the admin API token and write it to `<app_data_dir>/admin_token` for CLI use
+    pub async fn regenerate_admin_api_token(&self) -> Result<String> {
+        let token = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+        self.set_config("admin_api_token", &token).await?;
+
+        // 권한이 느슨한 기존 파일을 재사용하지 않도록 지우고 처음부터 0600으로 생성
+        let token_path = Self::get_app_data_dir()?.join("admin_token");
+        let _ = std::fs::remove_file(&token_path);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        std::io::Write::write_all(&mut options.open(&token_path)?, token.as_bytes())?;
+
+        Ok(token)
+    }
+
     // 최근 사용량 로그 조회
-    pub async fn get_recent_usage(&self, limit: i64) -> Result<Vec<UsageLog>> {
+    pub async fn get_recent_usage(&self, limit: i64, project: Option<&str>) -> Result<Vec<UsageLog>> {
         let rows = sqlx::query_as::<_, UsageLog>(
             r#"
-            SELECT id, timestamp, account_id, model, input_tokens, output_tokens, cost_usd, duration_ms, status_code, session_id
+            SELECT id, timestamp, account_id, model, input_tokens, output_tokens, cost_usd, duration_ms, status_code, session_id, session_source, request_kind,
+                   project_path, git_branch, queue_ms, headers_ms, ttft_ms, output_tokens_per_sec
             FROM usage_logs
+            WHERE (? IS NULL OR project_path = ? OR project_path LIKE '%/' || ?)
             ORDER BY timestamp DESC
             LIMIT ?
             "#,
         )
+        .bind(project)
+        .bind(project)
+        .bind(project)
         .bind(limit)
         .fetch_all(&self.pool)
         .await?;
@@ -433,7 +924,7 @@ impl Database {
     }
 
     // 계정별 사용량 통계 (최대 100개)
-    pub async fn get_usage_by_account(&self) -> Result<Vec<AccountUsageStats>> {
+    pub async fn get_usage_by_account(&self, project: Option<&str>) -> Result<Vec<AccountUsageStats>> {
         let rows = sqlx::query_as::<_, AccountUsageStats>(
             r#"
             SELECT
@@ -442,11 +933,15 @@ impl Database {
                 COALESCE(SUM(input_tokens), 0) as total_input_tokens,
                 COALESCE(SUM(output_tokens), 0) as total_output_tokens
             FROM usage_logs
+            WHERE (? IS NULL OR project_path = ? OR project_path LIKE '%/' || ?)
             GROUP BY account_id
             ORDER BY request_count DESC
             LIMIT 100
             "#,
         )
+        .bind(project)
+        .bind(project)
+        .bind(project)
         .fetch_all(&self.pool)
         .await?;
 
@@ -454,7 +949,7 @@ impl Database {
     }
 
     // 모델별 사용량 통계 (최대 50개)
-    pub async fn get_usage_by_model(&self) -> Result<Vec<ModelUsageStats>> {
+    pub async fn get_usage_by_model(&self, project: Option<&str>) -> Result<Vec<ModelUsageStats>> {
         let rows = sqlx::query_as::<_, ModelUsageStats>(
             r#"
             SELECT
@@ -463,65 +958,390 @@ impl Database {
                 COALESCE(SUM(input_tokens), 0) as total_input_tokens,
                 COALESCE(SUM(output_tokens), 0) as total_output_tokens
             FROM usage_logs
+            WHERE (? IS NULL OR project_path = ? OR project_path LIKE '%/' || ?)
             GROUP BY model
             ORDER BY request_count DESC
             LIMIT 50
             "#,
         )
+        .bind(project)
+        .bind(project)
+        .bind(project)
         .fetch_all(&self.pool)
         .await?;
 
         Ok(rows)
     }
 
-    // 일별 사용량 통계 (최근 N일)
-    pub async fn get_daily_usage(&self, days: i64) -> Result<Vec<DailyUsageStats>> {
-        let rows = sqlx::query_as::<_, DailyUsageStats>(
+    // 요청 종류별 사용량 통계 (기존 로그는 'unknown')
+    pub async fn get_usage_by_kind(&self, project: Option<&str>) -> Result<Vec<KindUsageStats>> {
+        let rows = sqlx::query_as::<_, KindUsageStats>(
             r#"
             SELECT
-                date(timestamp, 'unixepoch', 'localtime') as date,
+                COALESCE(request_kind, 'unknown') as request_kind,
                 COUNT(*) as request_count,
                 COALESCE(SUM(input_tokens), 0) as total_input_tokens,
-                COALESCE(SUM(output_tokens), 0) as total_output_tokens
+                COALESCE(SUM(output_tokens), 0) as total_output_tokens,
+                COALESCE(SUM(cost_usd), 0.0) as total_cost_usd
             FROM usage_logs
-            WHERE timestamp > unixepoch() - (? * 86400)
-            GROUP BY date
-            ORDER BY date DESC
+            WHERE (? IS NULL OR project_path = ? OR project_path LIKE '%/' || ?)
+            GROUP BY 1
+            ORDER BY request_count DESC
             "#,
         )
-        .bind(days)
+        .bind(project)
+        .bind(project)
+        .bind(project)
         .fetch_all(&self.pool)
         .await?;
 
         Ok(rows)
     }
 
-    // 사용량 로그 초기화
-    pub async fn clear_usage_logs(&self) -> Result<()> {
-        sqlx::query("DELETE FROM usage_logs")
-            .execute(&self.pool)
-            .await?;
-        Ok(())
-    }
-
-    // 세션별 사용량 통계
-    pub async fn get_usage_by_session(&self) -> Result<Vec<SessionUsageStats>> {
-        let rows = sqlx::query_as::<_, SessionUsageStats>(
+    // 지연 시간 통계 (계정/모델/시간대별, 최근 N일) - 지연 시간이 기록된 요청만
+    pub async fn get_latency_stats(&self, by: LatencyGroup, days: i64, project: Option<&str>) -> Result<Vec<LatencyStats>> {
+        let key = match by {
+            LatencyGroup::Account => "COALESCE(a.name, ul.account_id)",
+            LatencyGroup::Model => "ul.model",
+            LatencyGroup::Hour => "strftime('%H', ul.timestamp, 'unixepoch', 'localtime')",
+        };
+        let rows = sqlx::query(&format!(
             r#"
-            SELECT
-                COALESCE(session_id, 'unknown') as session_id,
-                MIN(timestamp) as first_request,
-                MAX(timestamp) as last_request,
-                COUNT(*) as request_count,
-                COALESCE(SUM(input_tokens), 0) as total_input_tokens,
-                COALESCE(SUM(output_tokens), 0) as total_output_tokens
-            FROM usage_logs
-            WHERE session_id IS NOT NULL
-            GROUP BY session_id
-            ORDER BY last_request DESC
+            SELECT {} as key, ul.output_tokens, ul.duration_ms, ul.queue_ms, ul.headers_ms, ul.ttft_ms, ul.output_tokens_per_sec
+            FROM usage_logs ul
+            LEFT JOIN accounts a ON a.id = ul.account_id
+            WHERE ul.headers_ms IS NOT NULL
+              AND ul.timestamp > unixepoch() - ? * 86400
+              AND (? IS NULL OR ul.project_path = ? OR ul.project_path LIKE '%/' || ?)
+            ORDER BY key
+            "#,
+            key
+        ))
+        .bind(days)
+        .bind(project)
+        .bind(project)
+        .bind(project)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut groups: Vec<(String, Vec<sqlx::sqlite::SqliteRow>)> = Vec::new();
+        for row in rows {
+            let key: String = row.get("key");
+            match groups.last_mut() {
+                Some((last, group)) if *last == key => group.push(row),
+                _ => groups.push((key, vec![row])),
+            }
+        }
+
+        let mut stats: Vec<LatencyStats> = groups
+            .into_iter()
+            .map(|(key, rows)| {
+                let avg = |column: &str| {
+                    let values: Vec<i64> = rows.iter().filter_map(|r| r.get::<Option<i64>, _>(column)).collect();
+                    (!values.is_empty()).then(|| values.iter().sum::<i64>() as f64 / values.len() as f64)
+                };
+                let mut ttfts: Vec<i64> = rows.iter().filter_map(|r| r.get::<Option<i64>, _>("ttft_ms")).collect();
+                ttfts.sort_unstable();
+                let percentile = |p: f64| {
+                    (!ttfts.is_empty()).then(|| ttfts[((ttfts.len() - 1) as f64 * p).round() as usize])
+                };
+
+                // 처리량은 요청별 평균이 아니라 (출력 토큰 합 / 생성 시간 합)
+                let (tokens, generation_ms) = rows
+                    .iter()
+                    .filter(|r| r.get::<Option<f64>, _>("output_tokens_per_sec").is_some())
+                    .fold((0i64, 0i64), |(tokens, ms), r| {
+                        let ttft = r.get::<Option<i64>, _>("ttft_ms").unwrap_or(0);
+                        (tokens + r.get::<i64, _>("output_tokens"), ms + (r.get::<i64, _>("duration_ms") - ttft).max(0))
+                    });
+
+                LatencyStats {
+                    key,
+                    request_count: rows.len() as i64,
+                    avg_queue_ms: avg("queue_ms"),
+                    avg_headers_ms: avg("headers_ms"),
+                    avg_ttft_ms: avg("ttft_ms"),
+                    p50_ttft_ms: percentile(0.5),
+                    p95_ttft_ms: percentile(0.95),
+                    avg_duration_ms: avg("duration_ms"),
+                    output_tokens_per_sec: (generation_ms > 0).then(|| tokens as f64 * 1000.0 / generation_ms as f64),
+                }
+            })
+            .collect();
+        if by != LatencyGroup::Hour {
+            stats.sort_by_key(|s| std::cmp::Reverse(s.request_count));
+        }
+        Ok(stats)
+    }
+
+    // 일별 사용량 통계 (최근 N일)
+    pub async fn get_daily_usage(&self, days: i64, project: Option<&str>) -> Result<Vec<DailyUsageStats>> {
+        let rows = sqlx::query_as::<_, DailyUsageStats>(
+            r#"
+            SELECT
+                date(timestamp, 'unixepoch', 'localtime') as date,
+                COUNT(*) as request_count,
+                COALESCE(SUM(input_tokens), 0) as total_input_tokens,
+                COALESCE(SUM(output_tokens), 0) as total_output_tokens
+            FROM usage_logs
+            WHERE timestamp > unixepoch() - (? * 86400)
+              AND (? IS NULL OR project_path = ? OR project_path LIKE '%/' || ?)
+            GROUP BY date
+            ORDER BY date DESC
+            "#,
+        )
+        .bind(days)
+        .bind(project)
+        .bind(project)
+        .bind(project)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    // 프로젝트별 사용량 통계 (프로젝트를 알 수 없는 로그는 'unknown')
+    pub async fn get_usage_by_project(&self) -> Result<Vec<ProjectUsageStats>> {
+        let rows = sqlx::query_as::<_, ProjectUsageStats>(
+            r#"
+            SELECT
+                COALESCE(ul.project_path, 'unknown') as project_path,
+                GROUP_CONCAT(DISTINCT ul.git_branch) as git_branches,
+                COUNT(DISTINCT COALESCE(sr.parent_session_id, ul.session_id)) as session_count,
+                COUNT(*) as request_count,
+                COALESCE(SUM(ul.input_tokens), 0) as total_input_tokens,
+                COALESCE(SUM(ul.output_tokens), 0) as total_output_tokens,
+                COALESCE(SUM(ul.cost_usd), 0.0) as total_cost_usd,
+                MAX(ul.timestamp) as last_request
+            FROM usage_logs ul
+            LEFT JOIN session_relations sr ON sr.child_session_id = ul.session_id
+            GROUP BY 1
+            ORDER BY total_cost_usd DESC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Remember the project a session works in (branch may change between turns)
+    pub async fn set_session_project(&self, session_id: &str, project: &ProjectInfo) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO session_projects (session_id, project_path, is_git_repo, git_branch, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(session_id) DO UPDATE SET
+                project_path = excluded.project_path,
+                is_git_repo = excluded.is_git_repo,
+                git_branch = excluded.git_branch,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(session_id)
+        .bind(&project.project_path)
+        .bind(project.is_git_repo)
+        .bind(&project.git_branch)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Project of a session, or of its parent for subagent sessions
+    pub async fn get_session_project(&self, session_id: &str) -> Result<Option<ProjectInfo>> {
+        let project = sqlx::query_as::<_, ProjectInfo>(
+            r#"
+            SELECT project_path, is_git_repo, git_branch FROM session_projects
+            WHERE session_id = ?
+               OR session_id = (SELECT parent_session_id FROM session_relations WHERE child_session_id = ?)
+            ORDER BY session_id = ? DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(session_id)
+        .bind(session_id)
+        .bind(session_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(project)
+    }
+
+    // 사용량 로그 초기화
+    pub async fn clear_usage_logs(&self) -> Result<()> {
+        sqlx::query("DELETE FROM usage_logs")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("DELETE FROM error_logs")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // 업스트림/전송 오류 기록
+    pub async fn log_error(&self, entry: &ErrorEntry) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO error_logs (timestamp, account_id, model, status_code, error_type, message, retry_after_secs, phase, session_id, request_id, request_kind)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(chrono::Utc::now().timestamp())
+        .bind(&entry.account_id)
+        .bind(&entry.model)
+        .bind(entry.status_code)
+        .bind(&entry.error_type)
+        .bind(&entry.message)
+        .bind(entry.retry_after_secs)
+        .bind(&entry.phase)
+        .bind(&entry.session_id)
+        .bind(&entry.request_id)
+        .bind(&entry.request_kind)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // 최근 오류 (account_id: 계정 필터)
+    pub async fn get_recent_errors(&self, limit: i64, account_id: Option<&str>) -> Result<Vec<ErrorLog>> {
+        let rows = sqlx::query_as::<_, ErrorLog>(
+            r#"
+            SELECT e.id, e.timestamp, e.account_id, a.name as account_name, e.model, e.status_code, e.error_type, e.message,
+                   e.retry_after_secs, e.phase, e.session_id, e.request_id, e.request_kind
+            FROM error_logs e
+            LEFT JOIN accounts a ON a.id = e.account_id
+            WHERE (? IS NULL OR e.account_id = ?)
+            ORDER BY e.timestamp DESC, e.id DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(account_id)
+        .bind(account_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    // 기간별 오류율 (요청 = 사용량 로그 + 오류, hourly면 시간 단위)
+    pub async fn get_error_timeline(&self, days: i64, hourly: bool, account_id: Option<&str>) -> Result<Vec<ErrorBucket>> {
+        let format = if hourly { "%Y-%m-%d %H:00" } else { "%Y-%m-%d" };
+        let rows = sqlx::query_as::<_, ErrorBucket>(
+            r#"
+            WITH events AS (
+                SELECT timestamp, 0 as is_error FROM usage_logs
+                WHERE timestamp > unixepoch() - ? * 86400 AND (? IS NULL OR account_id = ?)
+                UNION ALL
+                SELECT timestamp, 1 as is_error FROM error_logs
+                WHERE timestamp > unixepoch() - ? * 86400 AND (? IS NULL OR account_id = ?)
+            )
+            SELECT strftime(?, timestamp, 'unixepoch', 'localtime') as bucket,
+                   COUNT(*) as request_count,
+                   SUM(is_error) as error_count,
+                   CAST(SUM(is_error) AS REAL) / COUNT(*) as error_rate
+            FROM events
+            GROUP BY bucket
+            ORDER BY bucket DESC
+            "#,
+        )
+        .bind(days)
+        .bind(account_id)
+        .bind(account_id)
+        .bind(days)
+        .bind(account_id)
+        .bind(account_id)
+        .bind(format)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    // 계정별 오류율 (최근 N일)
+    pub async fn get_errors_by_account(&self, days: i64) -> Result<Vec<AccountErrorStats>> {
+        let rows = sqlx::query_as::<_, AccountErrorStats>(
+            r#"
+            WITH events AS (
+                SELECT account_id, timestamp, 0 as is_error FROM usage_logs WHERE timestamp > unixepoch() - ? * 86400
+                UNION ALL
+                SELECT account_id, timestamp, 1 as is_error FROM error_logs WHERE timestamp > unixepoch() - ? * 86400
+            )
+            SELECT ev.account_id,
+                   a.name as account_name,
+                   COUNT(*) as request_count,
+                   SUM(ev.is_error) as error_count,
+                   CAST(SUM(ev.is_error) AS REAL) / COUNT(*) as error_rate,
+                   MAX(CASE WHEN ev.is_error = 1 THEN ev.timestamp END) as last_error_at
+            FROM events ev
+            LEFT JOIN accounts a ON a.id = ev.account_id
+            GROUP BY ev.account_id
+            ORDER BY error_rate DESC, error_count DESC
+            "#,
+        )
+        .bind(days)
+        .bind(days)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    // 오류 종류별 통계 (최근 N일)
+    pub async fn get_errors_by_type(&self, days: i64, account_id: Option<&str>) -> Result<Vec<ErrorTypeStats>> {
+        let rows = sqlx::query_as::<_, ErrorTypeStats>(
+            r#"
+            SELECT e.error_type,
+                   COUNT(*) as error_count,
+                   GROUP_CONCAT(DISTINCT e.status_code) as status_codes,
+                   COUNT(DISTINCT e.account_id) as account_count,
+                   MAX(e.timestamp) as last_error_at,
+                   (SELECT e2.message FROM error_logs e2
+                    WHERE e2.error_type = e.error_type AND (? IS NULL OR e2.account_id = ?)
+                    ORDER BY e2.timestamp DESC, e2.id DESC LIMIT 1) as last_message
+            FROM error_logs e
+            WHERE e.timestamp > unixepoch() - ? * 86400 AND (? IS NULL OR e.account_id = ?)
+            GROUP BY e.error_type
+            ORDER BY error_count DESC
+            "#,
+        )
+        .bind(account_id)
+        .bind(account_id)
+        .bind(days)
+        .bind(account_id)
+        .bind(account_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    // 세션별 사용량 통계 (서브에이전트 사용량은 부모 세션에 합산)
+    pub async fn get_usage_by_session(&self, project: Option<&str>) -> Result<Vec<SessionUsageStats>> {
+        let rows = sqlx::query_as::<_, SessionUsageStats>(
+            r#"
+            SELECT
+                COALESCE(sr.parent_session_id, ul.session_id, 'unknown') as session_id,
+                MIN(ul.timestamp) as first_request,
+                MAX(ul.timestamp) as last_request,
+                COUNT(*) as request_count,
+                COUNT(CASE WHEN ul.request_kind = 'main_turn' THEN 1 END) as main_turn_count,
+                COALESCE(SUM(ul.input_tokens), 0) as total_input_tokens,
+                COALESCE(SUM(ul.output_tokens), 0) as total_output_tokens,
+                COALESCE(SUM(ul.cost_usd), 0.0) as total_cost_usd,
+                COUNT(DISTINCT sr.child_session_id) as subagent_count,
+                COUNT(sr.child_session_id) as subagent_request_count,
+                COALESCE(SUM(CASE WHEN sr.child_session_id IS NOT NULL THEN ul.input_tokens END), 0) as subagent_input_tokens,
+                COALESCE(SUM(CASE WHEN sr.child_session_id IS NOT NULL THEN ul.output_tokens END), 0) as subagent_output_tokens,
+                COALESCE(SUM(CASE WHEN sr.child_session_id IS NOT NULL THEN ul.cost_usd END), 0.0) as subagent_cost_usd
+            FROM usage_logs ul
+            LEFT JOIN session_relations sr ON sr.child_session_id = ul.session_id
+            WHERE ul.session_id IS NOT NULL
+              AND (? IS NULL OR ul.project_path = ? OR ul.project_path LIKE '%/' || ?)
+            GROUP BY 1
+            ORDER BY last_request DESC
             LIMIT 50
             "#,
         )
+        .bind(project)
+        .bind(project)
+        .bind(project)
         .fetch_all(&self.pool)
         .await?;
 
@@ -531,7 +1351,7 @@ impl Database {
     // 특정 계정 조회
     pub async fn get_account(&self, account_id: &str) -> Result<Option<Account>> {
         let account = sqlx::query_as::<_, Account>(
-            "SELECT id, name, base_url, created_at, is_active FROM accounts WHERE id = ?"
+            "SELECT id, name, base_url, created_at, is_active, connect_timeout_secs, first_byte_timeout_secs, idle_timeout_secs FROM accounts WHERE id = ?"
         )
         .bind(account_id)
         .fetch_optional(&self.pool)
@@ -624,19 +1444,35 @@ impl Database {
                 sc.last_activity_at,
                 COALESCE(ul.request_count, 0) as request_count,
                 COALESCE(ul.total_input_tokens, 0) as total_input_tokens,
-                COALESCE(ul.total_output_tokens, 0) as total_output_tokens
+                COALESCE(ul.total_output_tokens, 0) as total_output_tokens,
+                COALESCE(ul.subagent_count, 0) as subagent_count,
+                COALESCE(ul.subagent_input_tokens, 0) as subagent_input_tokens,
+                COALESCE(ul.subagent_output_tokens, 0) as subagent_output_tokens,
+                sp.project_path,
+                sp.git_branch,
+                sl.state,
+                COALESCE(sl.turn_count, 0) as turn_count,
+                COALESCE(sl.active_ms, 0) as active_ms,
+                sl.ended_at
             FROM session_config sc
             LEFT JOIN accounts a ON sc.account_id = a.id
+            LEFT JOIN session_projects sp ON sp.session_id = sc.session_id
+            LEFT JOIN session_lifecycle sl ON sl.session_id = sc.session_id
             LEFT JOIN (
                 SELECT
-                    session_id,
+                    COALESCE(sr.parent_session_id, u.session_id) as session_id,
                     COUNT(*) as request_count,
-                    SUM(input_tokens) as total_input_tokens,
-                    SUM(output_tokens) as total_output_tokens
-                FROM usage_logs
-                GROUP BY session_id
+                    SUM(u.input_tokens) as total_input_tokens,
+                    SUM(u.output_tokens) as total_output_tokens,
+                    COUNT(DISTINCT sr.child_session_id) as subagent_count,
+                    SUM(CASE WHEN sr.child_session_id IS NOT NULL THEN u.input_tokens ELSE 0 END) as subagent_input_tokens,
+                    SUM(CASE WHEN sr.child_session_id IS NOT NULL THEN u.output_tokens ELSE 0 END) as subagent_output_tokens
+                FROM usage_logs u
+                LEFT JOIN session_relations sr ON sr.child_session_id = u.session_id
+                GROUP BY 1
             ) ul ON sc.session_id = ul.session_id
             WHERE sc.last_activity_at > ?
+              AND sc.session_id NOT IN (SELECT child_session_id FROM session_relations)
             ORDER BY sc.last_activity_at DESC
             LIMIT 100
             "#,
@@ -658,6 +1494,19 @@ impl Database {
                 request_count: row.try_get("request_count").unwrap_or(0),
                 total_input_tokens: row.try_get("total_input_tokens").unwrap_or(0),
                 total_output_tokens: row.try_get("total_output_tokens").unwrap_or(0),
+                subagent_count: row.try_get("subagent_count").unwrap_or(0),
+                subagent_input_tokens: row.try_get("subagent_input_tokens").unwrap_or(0),
+                subagent_output_tokens: row.try_get("subagent_output_tokens").unwrap_or(0),
+                project_path: row.try_get("project_path").ok().flatten(),
+                git_branch: row.try_get("git_branch").ok().flatten(),
+                state: row
+                    .try_get::<Option<String>, _>("state")
+                    .ok()
+                    .flatten()
+                    .and_then(|s| SessionState::parse(&s)),
+                turn_count: row.try_get("turn_count").unwrap_or(0),
+                active_ms: row.try_get("active_ms").unwrap_or(0),
+                ended_at: row.try_get("ended_at").ok().flatten(),
             })
             .collect();
 
@@ -695,6 +1544,35 @@ impl Database {
             );
         }
 
+        // 서브에이전트 관계도 세션과 같은 기간 보존
+        let _ = sqlx::query("DELETE FROM session_relations WHERE created_at < ?")
+            .bind(session_cutoff)
+            .execute(&self.pool)
+            .await;
+
+        let _ = sqlx::query("DELETE FROM session_projects WHERE updated_at < ?")
+            .bind(session_cutoff)
+            .execute(&self.pool)
+            .await;
+
+        // 대화 기록도 세션과 같은 기간 보존
+        let _ = sqlx::query("DELETE FROM transcript_entries WHERE created_at < ?")
+            .bind(session_cutoff)
+            .execute(&self.pool)
+            .await;
+        let _ = sqlx::query("DELETE FROM transcript_sessions WHERE updated_at < ?")
+            .bind(session_cutoff)
+            .execute(&self.pool)
+            .await;
+        let _ = sqlx::query("DELETE FROM session_lifecycle WHERE last_activity_at < ?")
+            .bind(session_cutoff)
+            .execute(&self.pool)
+            .await;
+        let _ = sqlx::query("DELETE FROM session_turns WHERE started_at < ?")
+            .bind(session_cutoff)
+            .execute(&self.pool)
+            .await;
+
         // 2. 오래된 사용량 로그 삭제 (1년 이상)
         let usage_cutoff = now - (USAGE_LOG_RETENTION_DAYS * 24 * 60 * 60);
         let deleted_logs = sqlx::query("DELETE FROM usage_logs WHERE timestamp < ?")
@@ -702,6 +1580,28 @@ impl Database {
             .execute(&self.pool)
             .await?;
 
+        // 도구 호출 기록과 오류 기록도 사용량 로그와 같은 기간 보존
+        let _ = sqlx::query("DELETE FROM tool_uses WHERE created_at < ?")
+            .bind(usage_cutoff)
+            .execute(&self.pool)
+            .await;
+        let _ = sqlx::query("DELETE FROM error_logs WHERE timestamp < ?")
+            .bind(usage_cutoff)
+            .execute(&self.pool)
+            .await;
+        let _ = sqlx::query("DELETE FROM alert_firings WHERE fired_at < ?")
+            .bind(usage_cutoff)
+            .execute(&self.pool)
+            .await;
+        let _ = sqlx::query("DELETE FROM webhook_dead_letters WHERE failed_at < ?")
+            .bind(usage_cutoff)
+            .execute(&self.pool)
+            .await;
+        let _ = sqlx::query("DELETE FROM step_spans WHERE started_at < ?")
+            .bind(usage_cutoff)
+            .execute(&self.pool)
+            .await;
+
         if deleted_logs.rows_affected() > 0 {
             tracing::info!(
                 "Cleaned up {} old usage logs (older than {} days)",
@@ -797,81 +1697,1280 @@ impl Database {
         Ok(rows.iter().map(|row| row.get("session_id")).collect())
     }
 
-    // ===== 세션별 Hook 설정 =====
+    // ===== 세션 별칭 =====
 
-    /// 세션별 Hook 설정 조회
-    pub async fn get_session_hooks(&self, session_id: &str) -> Result<Option<SessionHookConfig>> {
-        let config = sqlx::query_as::<_, SessionHookConfig>(
+    // 세션 별칭 등록 (원본 세션 ID를 지정한 세션 ID로 매핑)
+    pub async fn register_session_alias(&self, alias: &str, session_id: &str) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO session_aliases (alias, session_id, created_at) VALUES (?, ?, ?)")
+            .bind(alias)
+            .bind(session_id)
+            .bind(chrono::Utc::now().timestamp())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // 세션 별칭 조회
+    pub async fn resolve_session_alias(&self, alias: &str) -> Result<Option<String>> {
+        let row = sqlx::query("SELECT session_id FROM session_aliases WHERE alias = ?")
+            .bind(alias)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|r| r.get("session_id")))
+    }
+
+    // ===== 서브에이전트 세션 =====
+
+    /// Link a subagent session to its parent; returns true if the link is new
+    pub async fn link_subagent_session(
+        &self,
+        parent_session_id: &str,
+        child_session_id: &str,
+        tool_use_id: Option<&str>,
+        detection: &str,
+    ) -> Result<bool> {
+        let inserted = sqlx::query(
             r#"
-            SELECT session_id, api_logging_enabled, compaction_injection_enabled,
-                   compaction_summarization_instructions, compaction_context_injection,
-                   custom_tasks_enabled, created_at, updated_at
-            FROM session_hooks WHERE session_id = ?
-            "#
+            INSERT OR IGNORE INTO session_relations (child_session_id, parent_session_id, tool_use_id, detection, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
         )
-        .bind(session_id)
-        .fetch_optional(&self.pool)
-        .await?;
+        .bind(child_session_id)
+        .bind(parent_session_id)
+        .bind(tool_use_id)
+        .bind(detection)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&self.pool)
+        .await?
+        .rows_affected()
+            > 0;
+
+        // 처음 감지 시 Task와 연결되지 않았으면 나중에 채움
+        if !inserted && tool_use_id.is_some() {
+            sqlx::query("UPDATE session_relations SET tool_use_id = ? WHERE child_session_id = ? AND tool_use_id IS NULL")
+                .bind(tool_use_id)
+                .bind(child_session_id)
+                .execute(&self.pool)
+                .await?;
+        }
 
-        Ok(config)
+        Ok(inserted)
     }
 
-    /// 세션별 Hook 설정 저장
-    pub async fn set_session_hooks(&self, config: &SessionHookConfig) -> Result<()> {
-        let now = chrono::Utc::now().timestamp();
-
+    /// Fill in agent type/description from the parent's completed Task call
+    pub async fn annotate_subagent_task(
+        &self,
+        parent_session_id: &str,
+        child_session_id: &str,
+        tool_use_id: &str,
+        agent_type: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<()> {
         sqlx::query(
             r#"
-            INSERT INTO session_hooks (
-                session_id, api_logging_enabled, compaction_injection_enabled,
-                compaction_summarization_instructions, compaction_context_injection,
-                custom_tasks_enabled, created_at, updated_at
-            )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-            ON CONFLICT(session_id) DO UPDATE SET
-                api_logging_enabled = excluded.api_logging_enabled,
-                compaction_injection_enabled = excluded.compaction_injection_enabled,
-                compaction_summarization_instructions = excluded.compaction_summarization_instructions,
-                compaction_context_injection = excluded.compaction_context_injection,
-                custom_tasks_enabled = excluded.custom_tasks_enabled,
-                updated_at = excluded.updated_at
+            UPDATE session_relations
+            SET tool_use_id = COALESCE(tool_use_id, ?), agent_type = ?, description = ?
+            WHERE parent_session_id = ? AND (tool_use_id = ? OR child_session_id = ?)
             "#,
         )
-        .bind(&config.session_id)
-        .bind(config.api_logging_enabled)
-        .bind(config.compaction_injection_enabled)
-        .bind(&config.compaction_summarization_instructions)
-        .bind(&config.compaction_context_injection)
-        .bind(config.custom_tasks_enabled)
-        .bind(now)
-        .bind(now)
+        .bind(tool_use_id)
+        .bind(agent_type)
+        .bind(description)
+        .bind(parent_session_id)
+        .bind(tool_use_id)
+        .bind(child_session_id)
         .execute(&self.pool)
         .await?;
-
         Ok(())
     }
 
-    /// 세션별 Hook 설정 삭제 (시스템 기본값 사용으로 복귀)
-    pub async fn delete_session_hooks(&self, session_id: &str) -> Result<()> {
-        sqlx::query("DELETE FROM session_hooks WHERE session_id = ?")
-            .bind(session_id)
-            .execute(&self.pool)
+    // 서브에이전트 세션의 부모 조회
+    pub async fn get_parent_session(&self, child_session_id: &str) -> Result<Option<String>> {
+        let row = sqlx::query("SELECT parent_session_id FROM session_relations WHERE child_session_id = ?")
+            .bind(child_session_id)
+            .fetch_optional(&self.pool)
             .await?;
-
-        Ok(())
+        Ok(row.map(|r| r.get("parent_session_id")))
     }
 
-    /// 모든 세션 Hook 설정 조회
-    pub async fn get_all_session_hooks(&self) -> Result<Vec<SessionHookConfig>> {
-        let configs = sqlx::query_as::<_, SessionHookConfig>(
+    // 부모 세션의 서브에이전트 목록 (사용량 포함)
+    pub async fn get_subagent_sessions(&self, parent_session_id: &str) -> Result<Vec<SubagentSession>> {
+        let rows = sqlx::query_as::<_, SubagentSession>(
             r#"
-            SELECT session_id, api_logging_enabled, compaction_injection_enabled,
-                   compaction_summarization_instructions, compaction_context_injection,
-                   custom_tasks_enabled, created_at, updated_at
-            FROM session_hooks
-            ORDER BY updated_at DESC
-            "#
-        )
+            SELECT
+                sr.child_session_id,
+                sr.parent_session_id,
+                sr.tool_use_id,
+                sr.agent_type,
+                sr.description,
+                sr.detection,
+                sr.created_at,
+                COUNT(ul.id) as request_count,
+                COALESCE(SUM(ul.input_tokens), 0) as total_input_tokens,
+                COALESCE(SUM(ul.output_tokens), 0) as total_output_tokens,
+                COALESCE(SUM(ul.cost_usd), 0.0) as total_cost_usd
+            FROM session_relations sr
+            LEFT JOIN usage_logs ul ON ul.session_id = sr.child_session_id
+            WHERE sr.parent_session_id = ?
+            GROUP BY sr.child_session_id
+            ORDER BY sr.created_at
+            "#,
+        )
+        .bind(parent_session_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    // 요청 종류별 라우팅 (config `request_kind_routes`: {"title_generation": {"account_id": ..., "model_override": ...}})
+    // `subagent`는 별도 키가 아니라 서브에이전트 라우팅(`subagent_*`)으로 저장
+    pub async fn get_request_kind_routes(&self) -> Result<HashMap<String, KindRoute>> {
+        let mut routes: HashMap<String, KindRoute> = match self.get_config("request_kind_routes").await?.filter(|v| !v.is_empty()) {
+            Some(json) => serde_json::from_str(&json)?,
+            None => HashMap::new(),
+        };
+        let subagent = self.get_subagent_routing().await?;
+        if let Some(account_id) = subagent.account_id {
+            routes.insert("subagent".to_string(), KindRoute { account_id, model_override: subagent.model_override });
+        }
+        Ok(routes)
+    }
+
+    pub async fn set_request_kind_routes(&self, routes: &HashMap<String, KindRoute>) -> Result<()> {
+        let mut routes = routes.clone();
+        match routes.remove("subagent") {
+            Some(route) => {
+                self.set_subagent_routing(&SubagentRouting {
+                    account_id: Some(route.account_id),
+                    model_override: route.model_override,
+                })
+                .await?
+            }
+            // 계정 없이 모델만 지정한 서브에이전트 라우팅은 kind route로 표현되지 않으므로 유지
+            None if self.get_subagent_routing().await?.account_id.is_some() => {
+                self.set_subagent_routing(&SubagentRouting::default()).await?
+            }
+            None => {}
+        }
+        self.set_config("request_kind_routes", &serde_json::to_string(&routes)?).await
+    }
+
+    // 서브에이전트 라우팅 설정 (None = 부모 세션 계정 / 원래 모델 사용)
+    pub async fn get_subagent_routing(&self) -> Result<SubagentRouting> {
+        let non_empty = |v: Option<String>| v.filter(|s| !s.is_empty());
+        Ok(SubagentRouting {
+            account_id: non_empty(self.get_config("subagent_account_id").await?),
+            model_override: non_empty(self.get_config("subagent_model_override").await?),
+        })
+    }
+
+    pub async fn set_subagent_routing(&self, routing: &SubagentRouting) -> Result<()> {
+        self.set_config("subagent_account_id", routing.account_id.as_deref().unwrap_or("")).await?;
+        self.set_config("subagent_model_override", routing.model_override.as_deref().unwrap_or("")).await?;
+        Ok(())
+    }
+
+    // ===== 웹훅 =====
+
+    /// Configured sinks; without any, the legacy ThreadCast URL acts as a single sink when enabled
+    pub async fn get_webhook_sinks(&self) -> Result<Vec<WebhookSink>> {
+        if let Some(json) = self.get_config("webhook_sinks").await? {
+            return Ok(serde_json::from_str(&json)?);
+        }
+        let enabled = self.get_config("threadcast_webhook_enabled").await?.as_deref() == Some("true");
+        match self.get_config("threadcast_webhook_url").await?.filter(|url| enabled && !url.is_empty()) {
+            Some(url) => {
+                let mut sink = WebhookSink::new("threadcast", format!("{}/api/webhooks/swiftcast", url.trim_end_matches('/')));
+                sink.id = "threadcast".to_string();
+                Ok(vec![sink])
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Save sinks, generating IDs for new ones
+    pub async fn set_webhook_sinks(&self, sinks: &[WebhookSink]) -> Result<Vec<WebhookSink>> {
+        let sinks: Vec<WebhookSink> = sinks
+            .iter()
+            .cloned()
+            .map(|mut sink| {
+                if sink.id.is_empty() {
+                    sink.id = uuid::Uuid::new_v4().to_string();
+                }
+                sink
+            })
+            .collect();
+        self.set_config("webhook_sinks", &serde_json::to_string(&sinks)?).await?;
+        Ok(sinks)
+    }
+
+    /// Queue one event for one sink
+    pub async fn enqueue_webhook(&self, sink_id: &str, event: &str, session_id: &str, payload: &str) -> Result<i64> {
+        let now = chrono::Utc::now().timestamp();
+        let result = sqlx::query(
+            "INSERT INTO webhook_outbox (sink_id, event, session_id, payload, next_attempt_at, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(sink_id)
+        .bind(event)
+        .bind(session_id)
+        .bind(payload)
+        .bind(now)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Oldest queued delivery of each sink/session pair that is due (later ones wait behind it)
+    pub async fn due_webhook_deliveries(&self, now: i64, limit: i64) -> Result<Vec<OutboxEntry>> {
+        let rows = sqlx::query_as::<_, OutboxEntry>(&format!(
+            "SELECT * FROM webhook_outbox WHERE id IN ({}) AND next_attempt_at <= ? ORDER BY id LIMIT ?",
+            OUTBOX_HEADS
+        ))
+        .bind(now)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// When the next queued delivery becomes due
+    pub async fn next_webhook_attempt_at(&self) -> Result<Option<i64>> {
+        let next: Option<i64> = sqlx::query_scalar(&format!(
+            "SELECT MIN(next_attempt_at) FROM webhook_outbox WHERE id IN ({})",
+            OUTBOX_HEADS
+        ))
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(next)
+    }
+
+    pub async fn complete_webhook_delivery(&self, id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM webhook_outbox WHERE id = ?").bind(id).execute(&self.pool).await?;
+        Ok(())
+    }
+
+    pub async fn retry_webhook_delivery(&self, id: i64, error: &str, next_attempt_at: i64) -> Result<()> {
+        sqlx::query("UPDATE webhook_outbox SET attempts = attempts + 1, last_error = ?, next_attempt_at = ? WHERE id = ?")
+            .bind(error)
+            .bind(next_attempt_at)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Give up on a delivery: move it to the dead-letter table
+    pub async fn dead_letter_webhook(&self, id: i64, attempts: i64, error: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_dead_letters (outbox_id, sink_id, event, session_id, payload, attempts, last_error, created_at, failed_at)
+            SELECT id, sink_id, event, session_id, payload, ?, ?, created_at, ? FROM webhook_outbox WHERE id = ?
+            "#,
+        )
+        .bind(attempts)
+        .bind(error)
+        .bind(chrono::Utc::now().timestamp())
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM webhook_outbox WHERE id = ?").bind(id).execute(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Queued deliveries, oldest first
+    pub async fn get_webhook_outbox(&self, limit: i64) -> Result<Vec<OutboxEntry>> {
+        let rows = sqlx::query_as::<_, OutboxEntry>("SELECT * FROM webhook_outbox ORDER BY id LIMIT ?")
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows)
+    }
+
+    /// Failed deliveries, most recent first
+    pub async fn get_webhook_dead_letters(&self, limit: i64, sink_id: Option<&str>) -> Result<Vec<DeadLetter>> {
+        let rows = sqlx::query_as::<_, DeadLetter>(
+            "SELECT * FROM webhook_dead_letters WHERE (? IS NULL OR sink_id = ?) ORDER BY failed_at DESC, id DESC LIMIT ?",
+        )
+        .bind(sink_id)
+        .bind(sink_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Queue dead letters again (all when `ids` is empty) under their original outbox IDs,
+    /// so they keep their place in the per-session order; returns how many were queued
+    pub async fn replay_webhook_dead_letters(&self, ids: &[i64], sink_id: Option<&str>) -> Result<u64> {
+        let now = chrono::Utc::now().timestamp();
+        let mut tx = self.pool.begin().await?;
+        let letters = sqlx::query_as::<_, DeadLetter>(
+            "SELECT * FROM webhook_dead_letters WHERE (? IS NULL OR sink_id = ?) ORDER BY outbox_id",
+        )
+        .bind(sink_id)
+        .bind(sink_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut replayed = 0;
+        for letter in letters.into_iter().filter(|l| ids.is_empty() || ids.contains(&l.id)) {
+            sqlx::query(
+                r#"
+                INSERT INTO webhook_outbox (id, sink_id, event, session_id, payload, attempts, next_attempt_at, last_error, created_at)
+                VALUES (?, ?, ?, ?, ?, 0, ?, ?, ?)
+                "#,
+            )
+            .bind(letter.outbox_id)
+            .bind(&letter.sink_id)
+            .bind(&letter.event)
+            .bind(&letter.session_id)
+            .bind(&letter.payload)
+            .bind(now)
+            .bind(&letter.last_error)
+            .bind(letter.created_at)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query("DELETE FROM webhook_dead_letters WHERE id = ?").bind(letter.id).execute(&mut *tx).await?;
+            replayed += 1;
+        }
+        tx.commit().await?;
+        Ok(replayed)
+    }
+
+    /// Delete dead letters (all when `ids` is empty); returns how many were deleted
+    pub async fn purge_webhook_dead_letters(&self, ids: &[i64], sink_id: Option<&str>) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let letters: Vec<i64> = sqlx::query_scalar("SELECT id FROM webhook_dead_letters WHERE (? IS NULL OR sink_id = ?)")
+            .bind(sink_id)
+            .bind(sink_id)
+            .fetch_all(&mut *tx)
+            .await?;
+
+        let mut purged = 0;
+        for id in letters.into_iter().filter(|id| ids.is_empty() || ids.contains(id)) {
+            sqlx::query("DELETE FROM webhook_dead_letters WHERE id = ?").bind(id).execute(&mut *tx).await?;
+            purged += 1;
+        }
+        tx.commit().await?;
+        Ok(purged)
+    }
+
+    // ===== 알림 =====
+
+    pub async fn get_alert_rules(&self) -> Result<Vec<AlertRule>> {
+        match self.get_config("alert_rules").await?.filter(|v| !v.is_empty()) {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Save rules, generating IDs for new ones
+    pub async fn set_alert_rules(&self, rules: &[AlertRule]) -> Result<Vec<AlertRule>> {
+        let rules: Vec<AlertRule> = rules
+            .iter()
+            .cloned()
+            .map(|mut rule| {
+                if rule.id.is_empty() {
+                    rule.id = uuid::Uuid::new_v4().to_string();
+                }
+                rule
+            })
+            .collect();
+        self.set_config("alert_rules", &serde_json::to_string(&rules)?).await?;
+        Ok(rules)
+    }
+
+    /// Successful requests, tokens and cost since `query.since`
+    pub async fn alert_usage_totals(&self, query: &AlertQuery<'_>) -> Result<(i64, i64, f64)> {
+        let sql = format!(
+            "SELECT COUNT(*), COALESCE(SUM(input_tokens + output_tokens), 0), COALESCE(SUM(cost_usd), 0.0) FROM usage_logs WHERE {}",
+            query.where_clause()
+        );
+        let row = query.bind(sqlx::query(&sql)).fetch_one(&self.pool).await?;
+        Ok((row.get(0), row.get(1), row.get(2)))
+    }
+
+    /// Errors since `query.since`, optionally of one type
+    pub async fn alert_error_count(&self, query: &AlertQuery<'_>, error_type: Option<&str>) -> Result<i64> {
+        let sql = format!(
+            "SELECT COUNT(*) FROM error_logs WHERE {} AND (?5 IS NULL OR error_type = ?5)",
+            query.where_clause()
+        );
+        let row = query
+            .bind(sqlx::query(&sql))
+            .bind(error_type)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(row.get(0))
+    }
+
+    /// Parent session of a subagent session (the session itself otherwise)
+    pub async fn root_session_id(&self, session_id: &str) -> Result<String> {
+        let parent: Option<String> =
+            sqlx::query_scalar("SELECT parent_session_id FROM session_relations WHERE child_session_id = ?")
+                .bind(session_id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(parent.unwrap_or_else(|| session_id.to_string()))
+    }
+
+    pub async fn last_alert_firing(&self, rule_id: &str, group_key: &str, window_key: &str) -> Result<Option<i64>> {
+        let fired_at = sqlx::query_scalar(
+            "SELECT MAX(fired_at) FROM alert_firings WHERE rule_id = ? AND group_key = ? AND window_key = ?",
+        )
+        .bind(rule_id)
+        .bind(group_key)
+        .bind(window_key)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(fired_at)
+    }
+
+    pub async fn record_alert_firing(&self, firing: &AlertFiring) -> Result<i64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO alert_firings (rule_id, rule_name, metric, group_key, window_key, value, threshold, message, fired_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&firing.rule_id)
+        .bind(&firing.rule_name)
+        .bind(&firing.metric)
+        .bind(&firing.group_key)
+        .bind(&firing.window_key)
+        .bind(firing.value)
+        .bind(firing.threshold)
+        .bind(&firing.message)
+        .bind(firing.fired_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    pub async fn get_alert_firings(&self, limit: i64) -> Result<Vec<AlertFiring>> {
+        let rows = sqlx::query_as::<_, AlertFiring>("SELECT * FROM alert_firings ORDER BY fired_at DESC, id DESC LIMIT ?")
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows)
+    }
+
+    // ===== 세션 수명 주기 =====
+
+    /// Record a request of a session; `starts_turn` when it carries a new user message.
+    /// Returns the state before the request (None for a new session)
+    pub async fn session_request_started(&self, session_id: &str, starts_turn: bool, now: i64) -> Result<Option<SessionState>> {
+        let mut tx = self.pool.begin().await?;
+        let row = sqlx::query("SELECT state, turn_count FROM session_lifecycle WHERE session_id = ?")
+            .bind(session_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let previous = row
+            .as_ref()
+            .and_then(|r| r.try_get::<String, _>("state").ok())
+            .and_then(|s| SessionState::parse(&s));
+        let turn_count: i64 = row.as_ref().and_then(|r| r.try_get("turn_count").ok()).unwrap_or(0);
+        let open_turn: Option<i64> =
+            sqlx::query_scalar("SELECT id FROM session_turns WHERE session_id = ? AND ended_at IS NULL ORDER BY id DESC LIMIT 1")
+                .bind(session_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+
+        // 새 사용자 메시지이거나 열린 턴이 없으면 새 턴 시작 (이전 턴은 중단된 것으로 종료)
+        let new_turn = starts_turn || open_turn.is_none();
+        if new_turn {
+            if let Some(id) = open_turn {
+                sqlx::query("UPDATE session_turns SET ended_at = ?, stop_reason = 'interrupted' WHERE id = ?")
+                    .bind(now)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            sqlx::query("INSERT INTO session_turns (session_id, turn_number, started_at) VALUES (?, ?, ?)")
+                .bind(session_id)
+                .bind(turn_count + 1)
+                .bind(now)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO session_lifecycle (session_id, state, started_at, last_activity_at, state_changed_at, turn_count)
+            VALUES (?1, 'active', ?2, ?2, ?2, ?3)
+            ON CONFLICT(session_id) DO UPDATE SET
+                state = 'active',
+                last_activity_at = ?2,
+                state_changed_at = CASE WHEN state = 'active' THEN state_changed_at ELSE ?2 END,
+                turn_count = ?3,
+                ended_at = NULL,
+                end_reason = NULL
+            "#,
+        )
+        .bind(session_id)
+        .bind(now)
+        .bind(if new_turn { turn_count + 1 } else { turn_count })
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(previous)
+    }
+
+    /// Add a finished request to the session and its turn; any stop reason but `tool_use` ends the turn
+    pub async fn session_request_finished(
+        &self,
+        session_id: &str,
+        duration_ms: i64,
+        stop_reason: Option<&str>,
+        now: i64,
+    ) -> Result<Option<SessionLifecycle>> {
+        let ends_turn = stop_reason != Some("tool_use");
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            UPDATE session_turns SET
+                request_count = request_count + 1,
+                active_ms = active_ms + ?1,
+                ended_at = CASE WHEN ?2 THEN ?3 ELSE ended_at END,
+                stop_reason = CASE WHEN ?2 THEN ?4 ELSE stop_reason END
+            WHERE id = (SELECT id FROM session_turns WHERE session_id = ?5 AND ended_at IS NULL ORDER BY id DESC LIMIT 1)
+            "#,
+        )
+        .bind(duration_ms)
+        .bind(ends_turn)
+        .bind(now)
+        .bind(stop_reason)
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+            UPDATE session_lifecycle SET
+                active_ms = active_ms + ?1,
+                last_activity_at = ?2,
+                state = CASE WHEN ?3 THEN 'waiting_for_user' ELSE state END,
+                state_changed_at = CASE WHEN ?3 THEN ?2 ELSE state_changed_at END
+            WHERE session_id = ?4
+            "#,
+        )
+        .bind(duration_ms)
+        .bind(now)
+        .bind(ends_turn)
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        self.get_session_lifecycle(session_id).await
+    }
+
+    pub async fn get_session_lifecycle(&self, session_id: &str) -> Result<Option<SessionLifecycle>> {
+        let lifecycle = sqlx::query_as::<_, SessionLifecycle>(&format!("{} WHERE session_id = ?", SESSION_LIFECYCLE_SELECT))
+            .bind(session_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(lifecycle)
+    }
+
+    /// Sessions that are not ended, for the idle/timeout sweep
+    pub async fn get_open_session_lifecycles(&self) -> Result<Vec<SessionLifecycle>> {
+        let lifecycles = sqlx::query_as::<_, SessionLifecycle>(&format!(
+            "{} WHERE state != 'ended' ORDER BY last_activity_at",
+            SESSION_LIFECYCLE_SELECT
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(lifecycles)
+    }
+
+    /// Move a session to `state`; ending closes its open turn. Returns false for unknown sessions
+    pub async fn set_session_state(&self, session_id: &str, state: SessionState, end_reason: Option<&str>, at: i64) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(
+            r#"
+            UPDATE session_lifecycle SET
+                state = ?1,
+                state_changed_at = ?2,
+                ended_at = CASE WHEN ?1 = 'ended' THEN ?2 ELSE ended_at END,
+                end_reason = CASE WHEN ?1 = 'ended' THEN ?3 ELSE end_reason END
+            WHERE session_id = ?4
+            "#,
+        )
+        .bind(state.as_str())
+        .bind(at)
+        .bind(end_reason)
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await?;
+        if state == SessionState::Ended {
+            sqlx::query("UPDATE session_turns SET ended_at = ?, stop_reason = ? WHERE session_id = ? AND ended_at IS NULL")
+                .bind(at)
+                .bind(end_reason)
+                .bind(session_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_session_turns(&self, session_id: &str) -> Result<Vec<SessionTurn>> {
+        let now = chrono::Utc::now().timestamp();
+        let turns = sqlx::query_as::<_, SessionTurn>(
+            r#"
+            SELECT turn_number, started_at, ended_at, COALESCE(ended_at, ?) - started_at as duration_secs,
+                   request_count, active_ms, stop_reason
+            FROM session_turns WHERE session_id = ?
+            ORDER BY turn_number, id
+            "#,
+        )
+        .bind(now)
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(turns)
+    }
+
+    // ===== 단계 추적 =====
+
+    /// Tool-to-phase mapping (built-in mapping when unset)
+    pub async fn get_step_config(&self) -> Result<StepConfig> {
+        match self.get_config("step_config").await?.filter(|v| !v.is_empty()) {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Ok(StepConfig::default()),
+        }
+    }
+
+    pub async fn set_step_config(&self, config: &StepConfig) -> Result<()> {
+        self.set_config("step_config", &serde_json::to_string(config)?).await
+    }
+
+    /// Start a phase span, returns its ID
+    pub async fn open_step_span(&self, session_id: &str, phase: &str, tool_name: &str, now: i64) -> Result<i64> {
+        let result = sqlx::query(
+            "INSERT INTO step_spans (session_id, phase, first_tool, tool_calls, started_at, last_tool_at) VALUES (?, ?, ?, 1, ?, ?)",
+        )
+        .bind(session_id)
+        .bind(phase)
+        .bind(tool_name)
+        .bind(now)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Another tool call in the open span
+    pub async fn touch_step_span(&self, id: i64, now: i64) -> Result<()> {
+        sqlx::query("UPDATE step_spans SET tool_calls = tool_calls + 1, last_tool_at = ? WHERE id = ?")
+            .bind(now)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn close_step_span(&self, id: i64, ended_at: i64) -> Result<()> {
+        sqlx::query("UPDATE step_spans SET ended_at = ? WHERE id = ? AND ended_at IS NULL")
+            .bind(ended_at)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Close spans left open by a previous run at their last tool call
+    pub async fn close_stale_step_spans(&self) -> Result<u64> {
+        let result = sqlx::query("UPDATE step_spans SET ended_at = last_tool_at WHERE ended_at IS NULL")
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Phases of a session's closed spans, in order (for session_complete)
+    pub async fn get_completed_phases(&self, session_id: &str) -> Result<Vec<String>> {
+        let phases = sqlx::query_scalar(
+            "SELECT phase FROM step_spans WHERE session_id = ? AND ended_at IS NOT NULL ORDER BY started_at, id",
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(phases)
+    }
+
+    /// Phase spans of a session in order, with time per phase
+    pub async fn get_step_timeline(&self, session_id: &str) -> Result<StepTimeline> {
+        let now = chrono::Utc::now().timestamp();
+        let spans = sqlx::query_as::<_, StepSpan>(
+            r#"
+            SELECT id, phase, first_tool, tool_calls, started_at, ended_at,
+                   COALESCE(ended_at, ?) - started_at as duration_secs
+            FROM step_spans WHERE session_id = ?
+            ORDER BY started_at, id
+            "#,
+        )
+        .bind(now)
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let phases = sqlx::query_as::<_, PhaseStats>(&format!(
+            "{} WHERE session_id = ?2 GROUP BY phase ORDER BY duration_secs DESC",
+            PHASE_STATS_SELECT
+        ))
+        .bind(now)
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(StepTimeline { session_id: session_id.to_string(), spans, phases })
+    }
+
+    /// Time per phase across all sessions in the last `days` days
+    pub async fn get_phase_stats(&self, days: i64) -> Result<Vec<PhaseStats>> {
+        let now = chrono::Utc::now().timestamp();
+        let stats = sqlx::query_as::<_, PhaseStats>(&format!(
+            "{} WHERE started_at >= ?2 GROUP BY phase ORDER BY duration_secs DESC",
+            PHASE_STATS_SELECT
+        ))
+        .bind(now)
+        .bind(now - days * 24 * 60 * 60)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(stats)
+    }
+
+    // ===== 대화 기록 =====
+
+    pub async fn get_transcript_state(&self, session_id: &str) -> Result<Option<TranscriptState>> {
+        let state = sqlx::query_as::<_, TranscriptState>(
+            "SELECT epoch, message_count, first_hash, pending_response_index FROM transcript_sessions WHERE session_id = ?",
+        )
+        .bind(session_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(state)
+    }
+
+    /// Store new request messages (see `proxy::transcript::plan_append`)
+    pub async fn append_transcript(&self, append: &TranscriptAppend) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let mut tx = self.pool.begin().await?;
+
+        // 스트림에서 저장한 응답은 히스토리의 전체 버전(thinking, tool_use 입력 포함)으로 교체 (usage는 유지)
+        if let Some(index) = append.replace_index {
+            sqlx::query("DELETE FROM transcript_entries WHERE session_id = ? AND epoch = ? AND msg_index = ? AND entry_type != 'usage'")
+                .bind(&append.session_id)
+                .bind(append.epoch)
+                .bind(index)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        for (index, blocks) in &append.messages {
+            for block in blocks {
+                sqlx::query(
+                    r#"
+                    INSERT INTO transcript_entries (session_id, epoch, msg_index, request_id, role, entry_type, content, tool_name, tool_use_id, created_at)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                    "#,
+                )
+                .bind(&append.session_id)
+                .bind(append.epoch)
+                .bind(index)
+                .bind(&append.request_id)
+                .bind(&block.role)
+                .bind(&block.entry_type)
+                .bind(&block.content)
+                .bind(&block.tool_name)
+                .bind(&block.tool_use_id)
+                .bind(now)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO transcript_sessions (session_id, epoch, message_count, first_hash, pending_response_index, updated_at)
+            VALUES (?, ?, ?, ?, NULL, ?)
+            ON CONFLICT(session_id) DO UPDATE SET
+                epoch = excluded.epoch,
+                message_count = excluded.message_count,
+                first_hash = excluded.first_hash,
+                pending_response_index = NULL,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&append.session_id)
+        .bind(append.epoch)
+        .bind(append.message_count)
+        .bind(&append.first_hash)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Store the streamed assistant reply and its usage after the request's messages
+    pub async fn append_transcript_response(
+        &self,
+        session_id: &str,
+        request_id: &str,
+        blocks: &[TranscriptBlock],
+        usage: (i64, i64),
+        stop_reason: Option<&str>,
+    ) -> Result<()> {
+        let Some(state) = self.get_transcript_state(session_id).await? else {
+            return Ok(());
+        };
+        let now = chrono::Utc::now().timestamp();
+        let index = state.message_count;
+        let mut tx = self.pool.begin().await?;
+
+        let insert = r#"
+            INSERT INTO transcript_entries (session_id, epoch, msg_index, request_id, role, entry_type, content, tool_name, tool_use_id, input_tokens, output_tokens, created_at)
+            VALUES (?, ?, ?, ?, 'assistant', ?, ?, ?, ?, ?, ?, ?)
+        "#;
+        for block in blocks {
+            sqlx::query(insert)
+                .bind(session_id)
+                .bind(state.epoch)
+                .bind(index)
+                .bind(request_id)
+                .bind(&block.entry_type)
+                .bind(&block.content)
+                .bind(&block.tool_name)
+                .bind(&block.tool_use_id)
+                .bind(None::<i64>)
+                .bind(None::<i64>)
+                .bind(now)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query(insert)
+            .bind(session_id)
+            .bind(state.epoch)
+            .bind(index)
+            .bind(request_id)
+            .bind("usage")
+            .bind(stop_reason.unwrap_or(""))
+            .bind(None::<String>)
+            .bind(None::<String>)
+            .bind(usage.0)
+            .bind(usage.1)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE transcript_sessions SET message_count = ?, pending_response_index = ?, updated_at = ? WHERE session_id = ?")
+            .bind(index + 1)
+            .bind(index)
+            .bind(now)
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Session timeline in insertion order; `after_id` allows incremental polling
+    pub async fn get_session_timeline(
+        &self,
+        session_id: &str,
+        include_subagents: bool,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<TranscriptEntry>> {
+        let entries = sqlx::query_as::<_, TranscriptEntry>(
+            r#"
+            SELECT id, session_id, epoch, msg_index, request_id, role, entry_type, content, tool_name, tool_use_id,
+                   input_tokens, output_tokens, created_at
+            FROM transcript_entries
+            WHERE (session_id = ?
+                   OR (? AND session_id IN (SELECT child_session_id FROM session_relations WHERE parent_session_id = ?)))
+              AND id > ?
+            ORDER BY id
+            LIMIT ?
+            "#,
+        )
+        .bind(session_id)
+        .bind(include_subagents)
+        .bind(session_id)
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(entries)
+    }
+
+    /// Usage rows joined with account names and parent sessions, oldest first (`until` exclusive)
+    pub async fn get_usage_export_rows(&self, since: Option<i64>, until: Option<i64>) -> Result<Vec<UsageExportRow>> {
+        let rows = sqlx::query_as::<_, UsageExportRow>(
+            r#"
+            SELECT u.row_uid AS row_id, u.timestamp, u.origin, u.account_id, a.name AS account_name, u.model,
+                   u.session_id, r.parent_session_id, u.session_source, u.request_kind, u.request_id,
+                   u.project_path, u.git_branch,
+                   u.input_tokens, u.output_tokens, u.cost_usd, u.duration_ms, u.status_code
+            FROM usage_logs u
+            LEFT JOIN accounts a ON a.id = u.account_id
+            LEFT JOIN session_relations r ON r.child_session_id = u.session_id
+            WHERE (? IS NULL OR u.timestamp >= ?)
+              AND (? IS NULL OR u.timestamp < ?)
+            ORDER BY u.timestamp, u.id
+            "#,
+        )
+        .bind(since)
+        .bind(since)
+        .bind(until)
+        .bind(until)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Merge exported usage rows, skipping row IDs already present.
+    ///
+    /// Accounts are matched by id, then by name, then `fallback_account_id`; rows with no match are skipped.
+    pub async fn import_usage_rows(
+        &self,
+        rows: &[UsageExportRow],
+        fallback_account_id: Option<&str>,
+    ) -> Result<UsageImportReport> {
+        let accounts = self.get_accounts().await?;
+        let resolve = |row: &UsageExportRow| {
+            accounts
+                .iter()
+                .find(|a| a.id == row.account_id)
+                .or_else(|| {
+                    let name = row.account_name.as_deref()?;
+                    accounts.iter().find(|a| a.name.eq_ignore_ascii_case(name))
+                })
+                .map(|a| a.id.clone())
+                .or_else(|| fallback_account_id.map(str::to_string))
+        };
+
+        let mut report = UsageImportReport::default();
+        let mut tx = self.pool.begin().await?;
+        for row in rows {
+            let Some(account_id) = resolve(row) else {
+                report.skipped_unknown_account += 1;
+                continue;
+            };
+            let result = sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO usage_logs (row_uid, timestamp, origin, account_id, model, session_id, session_source, request_kind, request_id,
+                                                  project_path, git_branch, input_tokens, output_tokens, cost_usd, duration_ms, status_code)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&row.row_id)
+            .bind(row.timestamp)
+            .bind(&row.origin)
+            .bind(&account_id)
+            .bind(&row.model)
+            .bind(&row.session_id)
+            .bind(&row.session_source)
+            .bind(&row.request_kind)
+            .bind(&row.request_id)
+            .bind(&row.project_path)
+            .bind(&row.git_branch)
+            .bind(row.input_tokens)
+            .bind(row.output_tokens)
+            .bind(row.cost_usd)
+            .bind(row.duration_ms)
+            .bind(row.status_code)
+            .execute(&mut *tx)
+            .await?;
+            if result.rows_affected() == 0 {
+                report.duplicates += 1;
+                continue;
+            }
+            report.imported += 1;
+
+            if let (Some(child), Some(parent)) = (&row.session_id, &row.parent_session_id) {
+                sqlx::query(
+                    "INSERT OR IGNORE INTO session_relations (child_session_id, parent_session_id, detection, created_at) VALUES (?, ?, 'imported', ?)",
+                )
+                .bind(child)
+                .bind(parent)
+                .bind(row.timestamp)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(report)
+    }
+
+    // ===== 도구 사용 통계 =====
+
+    /// Record a tool_use seen in a response stream (input and outcome are filled in later)
+    pub async fn record_tool_use(&self, tool_use_id: &str, session_id: Option<&str>, model: &str, tool_name: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT OR IGNORE INTO tool_uses (tool_use_id, session_id, model, tool_name, created_at) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(tool_use_id)
+        .bind(session_id)
+        .bind(model)
+        .bind(tool_name)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Fill in full inputs and results of the previous turn's tool calls (creating rows the stream missed)
+    pub async fn complete_tool_uses(
+        &self,
+        session_id: Option<&str>,
+        model: &str,
+        calls: &[ToolCall],
+        results: &[ToolResult],
+    ) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let mut tx = self.pool.begin().await?;
+        for call in calls {
+            sqlx::query(
+                r#"
+                INSERT INTO tool_uses (tool_use_id, session_id, model, tool_name, input_size, command, command_head, file_path, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT(tool_use_id) DO UPDATE SET
+                    input_size = excluded.input_size,
+                    command = excluded.command,
+                    command_head = excluded.command_head,
+                    file_path = excluded.file_path
+                "#,
+            )
+            .bind(&call.tool_use_id)
+            .bind(session_id)
+            .bind(model)
+            .bind(&call.tool_name)
+            .bind(call.input_size)
+            .bind(&call.command)
+            .bind(&call.command_head)
+            .bind(&call.file_path)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+        for result in results {
+            sqlx::query("UPDATE tool_uses SET is_error = ?, completed_at = ? WHERE tool_use_id = ? AND is_error IS NULL")
+                .bind(result.is_error)
+                .bind(now)
+                .bind(&result.tool_use_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Most-used tools, per-model tool behaviour, Bash commands and edited files
+    pub async fn get_tool_usage_report(&self, filter: &ToolStatsFilter) -> Result<ToolUsageReport> {
+        let tools = bind_tool_filter(
+            sqlx::query_as::<_, ToolStats>(&format!(
+                r#"
+                SELECT tool_name,
+                       COUNT(*) as call_count,
+                       COALESCE(SUM(is_error = 1), 0) as error_count,
+                       CAST(COALESCE(SUM(is_error = 1), 0) AS REAL) / MAX(COUNT(is_error), 1) as error_rate,
+                       COALESCE(AVG(input_size), 0.0) as avg_input_size,
+                       COUNT(DISTINCT session_id) as session_count
+                FROM tool_uses WHERE {}
+                GROUP BY tool_name
+                ORDER BY call_count DESC
+                "#,
+                TOOL_FILTER
+            )),
+            filter,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let models = bind_tool_filter(
+            sqlx::query_as::<_, ModelToolStats>(&format!(
+                r#"
+                SELECT model,
+                       COUNT(*) as call_count,
+                       COALESCE(SUM(is_error = 1), 0) as error_count,
+                       CAST(COALESCE(SUM(is_error = 1), 0) AS REAL) / MAX(COUNT(is_error), 1) as error_rate,
+                       COUNT(DISTINCT session_id) as session_count,
+                       CAST(COUNT(*) AS REAL) / MAX(COUNT(DISTINCT session_id), 1) as calls_per_session,
+                       COUNT(DISTINCT tool_name) as distinct_tools
+                FROM tool_uses WHERE {}
+                GROUP BY model
+                ORDER BY call_count DESC
+                "#,
+                TOOL_FILTER
+            )),
+            filter,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let bash_commands = bind_tool_filter(
+            sqlx::query_as::<_, CommandStats>(&format!(
+                r#"
+                SELECT command_head,
+                       COUNT(*) as call_count,
+                       COALESCE(SUM(is_error = 1), 0) as error_count
+                FROM tool_uses WHERE {} AND command_head IS NOT NULL AND command_head != ''
+                GROUP BY command_head
+                ORDER BY call_count DESC
+                LIMIT 50
+                "#,
+                TOOL_FILTER
+            )),
+            filter,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let edit_tools = FILE_EDIT_TOOLS.map(|t| format!("'{}'", t)).join(", ");
+        let file_edits = bind_tool_filter(
+            sqlx::query_as::<_, FileEditStats>(&format!(
+                r#"
+                SELECT file_path,
+                       COUNT(*) as edit_count,
+                       COALESCE(SUM(is_error = 1), 0) as error_count,
+                       COUNT(DISTINCT session_id) as session_count
+                FROM tool_uses WHERE {} AND file_path IS NOT NULL AND tool_name IN ({})
+                GROUP BY file_path
+                ORDER BY edit_count DESC
+                LIMIT 50
+                "#,
+                TOOL_FILTER, edit_tools
+            )),
+            filter,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ToolUsageReport { tools, models, bash_commands, file_edits })
+    }
+
+    /// Full-text search over transcripts; `query.query` must already be an FTS5 match expression
+    pub async fn search_transcripts(&self, query: &SearchQuery) -> Result<Vec<SearchHit>> {
+        let hits = sqlx::query_as::<_, SearchHit>(
+            r#"
+            SELECT e.id AS entry_id, e.session_id, e.request_id, e.role, e.entry_type, e.tool_name,
+                   snippet(transcript_fts, 0, '[', ']', '...', 16) AS snippet, e.created_at,
+                   u.account_id, a.name AS account_name, u.model, u.request_kind
+            FROM transcript_fts
+            JOIN transcript_entries e ON e.id = transcript_fts.rowid
+            LEFT JOIN usage_logs u ON u.request_id = e.request_id
+            LEFT JOIN accounts a ON a.id = u.account_id
+            WHERE transcript_fts MATCH ?
+              AND (? IS NULL OR e.session_id = ?
+                   OR e.session_id IN (SELECT child_session_id FROM session_relations WHERE parent_session_id = ?))
+              AND (? IS NULL OR u.account_id = ?)
+              AND (? IS NULL OR u.model = ?)
+              AND (? IS NULL OR u.request_kind = ?)
+              AND (? IS NULL OR e.created_at >= ?)
+              AND (? IS NULL OR e.created_at < ?)
+            ORDER BY transcript_fts.rank
+            LIMIT ?
+            "#,
+        )
+        .bind(&query.query)
+        .bind(&query.session_id)
+        .bind(&query.session_id)
+        .bind(&query.session_id)
+        .bind(&query.account_id)
+        .bind(&query.account_id)
+        .bind(&query.model)
+        .bind(&query.model)
+        .bind(&query.request_kind)
+        .bind(&query.request_kind)
+        .bind(query.since)
+        .bind(query.since)
+        .bind(query.until)
+        .bind(query.until)
+        .bind(query.limit.unwrap_or(50).clamp(1, 500))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(hits)
+    }
+
+    // ===== 세션별 Hook 설정 =====
+
+    /// 세션별 Hook 설정 조회
+    pub async fn get_session_hooks(&self, session_id: &str) -> Result<Option<SessionHookConfig>> {
+        let config = sqlx::query_as::<_, SessionHookConfig>(
+            r#"
+            SELECT session_id, api_logging_enabled, compaction_injection_enabled,
+                   compaction_summarization_instructions, compaction_context_injection,
+                   custom_tasks_enabled, created_at, updated_at
+            FROM session_hooks WHERE session_id = ?
+            "#
+        )
+        .bind(session_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(config)
+    }
+
+    /// 세션별 Hook 설정 저장
+    pub async fn set_session_hooks(&self, config: &SessionHookConfig) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+
+        sqlx::query(
+            r#"
+            INSERT INTO session_hooks (
+                session_id, api_logging_enabled, compaction_injection_enabled,
+                compaction_summarization_instructions, compaction_context_injection,
+                custom_tasks_enabled, created_at, updated_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(session_id) DO UPDATE SET
+                api_logging_enabled = excluded.api_logging_enabled,
+                compaction_injection_enabled = excluded.compaction_injection_enabled,
+                compaction_summarization_instructions = excluded.compaction_summarization_instructions,
+                compaction_context_injection = excluded.compaction_context_injection,
+                custom_tasks_enabled = excluded.custom_tasks_enabled,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&config.session_id)
+        .bind(config.api_logging_enabled)
+        .bind(config.compaction_injection_enabled)
+        .bind(&config.compaction_summarization_instructions)
+        .bind(&config.compaction_context_injection)
+        .bind(config.custom_tasks_enabled)
+        .bind(now)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 세션별 Hook 설정 삭제 (시스템 기본값 사용으로 복귀)
+    pub async fn delete_session_hooks(&self, session_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM session_hooks WHERE session_id = ?")
+            .bind(session_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 모든 세션 Hook 설정 조회
+    pub async fn get_all_session_hooks(&self) -> Result<Vec<SessionHookConfig>> {
+        let configs = sqlx::query_as::<_, SessionHookConfig>(
+            r#"
+            SELECT session_id, api_logging_enabled, compaction_injection_enabled,
+                   compaction_summarization_instructions, compaction_context_injection,
+                   custom_tasks_enabled, created_at, updated_at
+            FROM session_hooks
+            ORDER BY updated_at DESC
+            "#
+        )
         .fetch_all(&self.pool)
         .await?;
 
@@ -892,6 +2991,400 @@ pub struct UsageLog {
     pub duration_ms: i64,
     pub status_code: i64,
     pub session_id: Option<String>,
+    #[sqlx(default)]
+    pub session_source: Option<String>,
+    #[sqlx(default)]
+    pub request_kind: Option<String>,
+    #[sqlx(default)]
+    pub project_path: Option<String>,
+    #[sqlx(default)]
+    pub git_branch: Option<String>,
+    #[sqlx(default)]
+    pub queue_ms: Option<i64>,
+    #[sqlx(default)]
+    pub headers_ms: Option<i64>,
+    #[sqlx(default)]
+    pub ttft_ms: Option<i64>,
+    #[sqlx(default)]
+    pub output_tokens_per_sec: Option<f64>,
+}
+
+/// 사용량 로그 한 건 (log_usage 입력)
+#[derive(Debug, Clone, Default)]
+pub struct UsageEntry {
+    pub account_id: String,
+    pub model: String,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost_usd: f64,
+    pub session_id: Option<String>,
+    /// Resolver that produced `session_id` (see `SessionSource`)
+    pub session_source: Option<String>,
+    /// See `RequestKind`
+    pub request_kind: Option<String>,
+    pub request_id: Option<String>,
+    pub project_path: Option<String>,
+    pub git_branch: Option<String>,
+    /// Upstream send to end of stream
+    pub duration_ms: i64,
+    /// Time spent in the proxy before the request went upstream
+    pub queue_ms: Option<i64>,
+    /// Upstream send to response headers
+    pub headers_ms: Option<i64>,
+    /// Upstream send to first content delta
+    pub ttft_ms: Option<i64>,
+    pub output_tokens_per_sec: Option<f64>,
+}
+
+/// One `usage_logs` row as exported (flat, self-describing so it can be imported on another machine)
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, serde::Serialize, serde::Deserialize)]
+pub struct UsageExportRow {
+    /// Stable row ID used to deduplicate imports
+    pub row_id: String,
+    pub timestamp: i64,
+    /// Machine/person the row came from (NULL = this machine)
+    pub origin: Option<String>,
+    pub account_id: String,
+    pub account_name: Option<String>,
+    pub model: String,
+    pub session_id: Option<String>,
+    pub parent_session_id: Option<String>,
+    pub session_source: Option<String>,
+    pub request_kind: Option<String>,
+    pub request_id: Option<String>,
+    pub project_path: Option<String>,
+    pub git_branch: Option<String>,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost_usd: f64,
+    pub duration_ms: i64,
+    pub status_code: i64,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct UsageImportReport {
+    pub imported: u64,
+    /// Rows whose row ID already exists
+    pub duplicates: u64,
+    pub skipped_unknown_account: u64,
+}
+
+/// WHERE clause for `ToolStatsFilter`, bound by `bind_tool_filter`
+const TOOL_FILTER: &str = "(? IS NULL OR session_id = ? OR session_id IN (SELECT child_session_id FROM session_relations WHERE parent_session_id = ?)) \
+     AND (? IS NULL OR model = ?) \
+     AND (? IS NULL OR created_at > unixepoch() - ? * 86400)";
+
+fn bind_tool_filter<'q, O>(
+    query: sqlx::query::QueryAs<'q, sqlx::Sqlite, O, sqlx::sqlite::SqliteArguments<'q>>,
+    filter: &'q ToolStatsFilter,
+) -> sqlx::query::QueryAs<'q, sqlx::Sqlite, O, sqlx::sqlite::SqliteArguments<'q>> {
+    query
+        .bind(&filter.session_id)
+        .bind(&filter.session_id)
+        .bind(&filter.session_id)
+        .bind(&filter.model)
+        .bind(&filter.model)
+        .bind(filter.days)
+        .bind(filter.days)
+}
+
+/// Tool statistics filters (session includes its subagents)
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ToolStatsFilter {
+    pub session_id: Option<String>,
+    pub model: Option<String>,
+    /// Only the last N days
+    pub days: Option<i64>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ToolUsageReport {
+    pub tools: Vec<ToolStats>,
+    pub models: Vec<ModelToolStats>,
+    pub bash_commands: Vec<CommandStats>,
+    pub file_edits: Vec<FileEditStats>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct ToolStats {
+    pub tool_name: String,
+    pub call_count: i64,
+    pub error_count: i64,
+    /// Errors among calls whose result has been seen
+    pub error_rate: f64,
+    pub avg_input_size: f64,
+    pub session_count: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct ModelToolStats {
+    pub model: String,
+    pub call_count: i64,
+    pub error_count: i64,
+    pub error_rate: f64,
+    pub session_count: i64,
+    pub calls_per_session: f64,
+    pub distinct_tools: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct CommandStats {
+    /// Program and subcommand (`cargo test`)
+    pub command_head: String,
+    pub call_count: i64,
+    pub error_count: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct FileEditStats {
+    pub file_path: String,
+    pub edit_count: i64,
+    pub error_count: i64,
+    pub session_count: i64,
+}
+
+/// Transcript search filters (all optional except `query`)
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SearchQuery {
+    pub query: String,
+    /// Also matches the session's subagents
+    pub session_id: Option<String>,
+    pub account_id: Option<String>,
+    pub model: Option<String>,
+    /// See `RequestKind`
+    pub request_kind: Option<String>,
+    /// Unix timestamps, `until` exclusive
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct SearchHit {
+    /// `transcript_entries.id` (use as `after_id - 1` to open the timeline here)
+    pub entry_id: i64,
+    pub session_id: String,
+    pub request_id: Option<String>,
+    pub role: String,
+    pub entry_type: String,
+    pub tool_name: Option<String>,
+    /// Matched text with terms in [brackets]
+    pub snippet: String,
+    pub created_at: i64,
+    pub account_id: Option<String>,
+    pub account_name: Option<String>,
+    pub model: Option<String>,
+    pub request_kind: Option<String>,
+    /// FileLoggerHook log file of the request, if API logging was on
+    #[sqlx(skip)]
+    pub log_file: Option<String>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct ProjectUsageStats {
+    pub project_path: String,
+    /// Comma-separated branches seen in the project
+    pub git_branches: Option<String>,
+    pub session_count: i64,
+    pub request_count: i64,
+    pub total_input_tokens: i64,
+    pub total_output_tokens: i64,
+    pub total_cost_usd: f64,
+    pub last_request: i64,
+}
+
+/// Rows an alert metric is computed over (`usage_logs` / `error_logs` share these columns)
+#[derive(Debug, Clone, Copy)]
+pub struct AlertQuery<'a> {
+    pub since: i64,
+    pub scope: AlertScope,
+    /// Account id, model or root session id for scoped rules
+    pub scope_value: Option<&'a str>,
+    /// Rule filters
+    pub account_id: Option<&'a str>,
+    pub model: Option<&'a str>,
+}
+
+impl<'a> AlertQuery<'a> {
+    fn where_clause(&self) -> String {
+        let scope = match self.scope {
+            AlertScope::Global => "?1 IS NULL OR 1",
+            AlertScope::Account => "account_id = ?1",
+            AlertScope::Model => "model = ?1",
+            AlertScope::Session => {
+                "session_id = ?1 OR session_id IN (SELECT child_session_id FROM session_relations WHERE parent_session_id = ?1)"
+            }
+        };
+        format!(
+            "timestamp >= ?2 AND ({}) AND (?3 IS NULL OR account_id = ?3) AND (?4 IS NULL OR model = ?4)",
+            scope
+        )
+    }
+
+    fn bind<'q>(
+        &self,
+        query: sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>>,
+    ) -> sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>>
+    where
+        'a: 'q,
+    {
+        query.bind(self.scope_value).bind(self.since).bind(self.account_id).bind(self.model)
+    }
+}
+
+/// 오류 한 건 (log_error 입력)
+#[derive(Debug, Clone, Default)]
+pub struct ErrorEntry {
+    pub account_id: String,
+    pub model: String,
+    /// HTTP status returned to the client (502 / 504 for transport errors)
+    pub status_code: i64,
+    /// Anthropic error type (`overloaded_error`, `rate_limit_error`, ...; `connection_error` / `timeout_error` for transport errors)
+    pub error_type: String,
+    pub message: String,
+    /// Parsed `retry-after` header
+    pub retry_after_secs: Option<i64>,
+    /// `request` (before the response started) or `stream` (error event or idle timeout mid-stream)
+    pub phase: String,
+    pub session_id: Option<String>,
+    pub request_id: Option<String>,
+    pub request_kind: Option<String>,
+}
+
+/// Head of each sink/session queue in `webhook_outbox`
+const OUTBOX_HEADS: &str = "SELECT MIN(id) FROM webhook_outbox GROUP BY sink_id, session_id";
+
+/// Queued webhook delivery (one row per event and sink)
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct OutboxEntry {
+    pub id: i64,
+    pub sink_id: String,
+    pub event: String,
+    pub session_id: String,
+    /// `WebhookPayload` JSON
+    pub payload: String,
+    /// Failed attempts so far
+    pub attempts: i64,
+    pub next_attempt_at: i64,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+}
+
+/// Webhook delivery that failed too many times
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct DeadLetter {
+    pub id: i64,
+    /// Original `webhook_outbox` ID (reused on replay to keep the order)
+    pub outbox_id: i64,
+    pub sink_id: String,
+    pub event: String,
+    pub session_id: String,
+    pub payload: String,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+    pub failed_at: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct ErrorLog {
+    pub id: i64,
+    pub timestamp: i64,
+    pub account_id: String,
+    pub account_name: Option<String>,
+    pub model: String,
+    pub status_code: i64,
+    pub error_type: String,
+    pub message: String,
+    pub retry_after_secs: Option<i64>,
+    pub phase: String,
+    pub session_id: Option<String>,
+    pub request_id: Option<String>,
+    pub request_kind: Option<String>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct ErrorBucket {
+    /// Local day (`YYYY-MM-DD`) or hour (`YYYY-MM-DD HH:00`)
+    pub bucket: String,
+    /// Successful requests plus errors
+    pub request_count: i64,
+    pub error_count: i64,
+    pub error_rate: f64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct AccountErrorStats {
+    pub account_id: String,
+    pub account_name: Option<String>,
+    pub request_count: i64,
+    pub error_count: i64,
+    pub error_rate: f64,
+    pub last_error_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct ErrorTypeStats {
+    pub error_type: String,
+    pub error_count: i64,
+    /// Comma-separated
+    pub status_codes: String,
+    pub account_count: i64,
+    pub last_error_at: i64,
+    pub last_message: Option<String>,
+}
+
+/// Grouping for `get_latency_stats`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyGroup {
+    Account,
+    Model,
+    /// Local hour of day (00-23)
+    Hour,
+}
+
+impl LatencyGroup {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "account" => Some(Self::Account),
+            "model" => Some(Self::Model),
+            "hour" => Some(Self::Hour),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Account => "account",
+            Self::Model => "model",
+            Self::Hour => "hour",
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LatencyStats {
+    /// Account name, model or hour of day
+    pub key: String,
+    pub request_count: i64,
+    pub avg_queue_ms: Option<f64>,
+    pub avg_headers_ms: Option<f64>,
+    pub avg_ttft_ms: Option<f64>,
+    pub p50_ttft_ms: Option<i64>,
+    pub p95_ttft_ms: Option<i64>,
+    pub avg_duration_ms: Option<f64>,
+    /// Output tokens over total generation time (first token to end of stream)
+    pub output_tokens_per_sec: Option<f64>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct KindUsageStats {
+    pub request_kind: String,
+    pub request_count: i64,
+    pub total_input_tokens: i64,
+    pub total_output_tokens: i64,
+    pub total_cost_usd: f64,
 }
 
 #[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
@@ -918,14 +3411,67 @@ pub struct DailyUsageStats {
     pub total_output_tokens: i64,
 }
 
+/// 세션별 사용량 (서브에이전트 포함 합계 + 서브에이전트 분량)
 #[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
 pub struct SessionUsageStats {
     pub session_id: String,
     pub first_request: i64,
     pub last_request: i64,
     pub request_count: i64,
+    /// 메인 대화 턴 수 (제목 생성/쿼터 확인 등 백그라운드 요청 제외)
+    pub main_turn_count: i64,
     pub total_input_tokens: i64,
     pub total_output_tokens: i64,
+    pub total_cost_usd: f64,
+    pub subagent_count: i64,
+    pub subagent_request_count: i64,
+    pub subagent_input_tokens: i64,
+    pub subagent_output_tokens: i64,
+    pub subagent_cost_usd: f64,
+}
+
+/// Subagent session linked to a parent, with its own usage
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct SubagentSession {
+    pub child_session_id: String,
+    pub parent_session_id: String,
+    pub tool_use_id: Option<String>,
+    pub agent_type: Option<String>,
+    pub description: Option<String>,
+    pub detection: String,
+    pub created_at: i64,
+    pub request_count: i64,
+    pub total_input_tokens: i64,
+    pub total_output_tokens: i64,
+    pub total_cost_usd: f64,
+}
+
+/// Account (and optional model) for every request of one `RequestKind`; takes precedence over session routing
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct KindRoute {
+    pub account_id: String,
+    /// `None` keeps the model the request asked for
+    #[serde(default)]
+    pub model_override: Option<String>,
+}
+
+/// Routing applied to newly detected subagent sessions (config keys `subagent_*`)
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SubagentRouting {
+    /// Account for subagents; `None` inherits the parent session's account
+    pub account_id: Option<String>,
+    /// Model for subagents; `None` keeps the model the subagent requested
+    pub model_override: Option<String>,
+}
+
+/// 전역 Hook 설정 (config 테이블에 키별로 저장)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct HookConfig {
+    pub hooks_enabled: bool,
+    pub hooks_retention_days: u64,
+    pub compaction_injection_enabled: bool,
+    pub compaction_summarization_instructions: String,
+    pub compaction_context_injection: String,
 }
 
 #[derive(Debug, Clone, sqlx::FromRow, serde::Serialize, serde::Deserialize)]
@@ -955,3 +3501,86 @@ impl SessionHookConfig {
         }
     }
 }
+
+/// Per-phase totals; `?1` is the current time for spans still open
+const PHASE_STATS_SELECT: &str = r#"
+    SELECT phase,
+           COUNT(*) as span_count,
+           COALESCE(SUM(tool_calls), 0) as tool_calls,
+           COALESCE(SUM(COALESCE(ended_at, ?1) - started_at), 0) as duration_secs,
+           COUNT(DISTINCT session_id) as session_count
+    FROM step_spans"#;
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct StepSpan {
+    pub id: i64,
+    pub phase: String,
+    pub first_tool: String,
+    pub tool_calls: i64,
+    pub started_at: i64,
+    /// None while the phase is still open
+    pub ended_at: Option<i64>,
+    pub duration_secs: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct PhaseStats {
+    pub phase: String,
+    pub span_count: i64,
+    pub tool_calls: i64,
+    pub duration_secs: i64,
+    pub session_count: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StepTimeline {
+    pub session_id: String,
+    pub spans: Vec<StepSpan>,
+    pub phases: Vec<PhaseStats>,
+}
+
+const SESSION_LIFECYCLE_SELECT: &str = r#"
+    SELECT session_id, state, started_at, last_activity_at, state_changed_at, turn_count, active_ms, ended_at, end_reason
+    FROM session_lifecycle"#;
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct SessionLifecycle {
+    pub session_id: String,
+    /// See `SessionState`
+    pub state: String,
+    pub started_at: i64,
+    pub last_activity_at: i64,
+    pub state_changed_at: i64,
+    pub turn_count: i64,
+    /// Time spent in requests (model streaming), summed
+    pub active_ms: i64,
+    pub ended_at: Option<i64>,
+    /// `timeout` or `explicit`
+    pub end_reason: Option<String>,
+}
+
+impl SessionLifecycle {
+    pub fn session_state(&self) -> Option<SessionState> {
+        SessionState::parse(&self.state)
+    }
+
+    /// Wall time from the first request to the end (or the last activity while open)
+    pub fn wall_secs(&self) -> i64 {
+        self.ended_at.unwrap_or(self.last_activity_at) - self.started_at
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct SessionTurn {
+    pub turn_number: i64,
+    pub started_at: i64,
+    /// None while the turn is in progress
+    pub ended_at: Option<i64>,
+    /// Wall time from the user's message to the end of the turn
+    pub duration_secs: i64,
+    pub request_count: i64,
+    /// Time spent in requests during the turn
+    pub active_ms: i64,
+    /// `end_turn`, `max_tokens`, ..., `interrupted` or the session end reason
+    pub stop_reason: Option<String>,
+}
@@ -1,3 +1,4 @@
 pub mod database;
+pub mod usage_export;
 
 pub use database::Database;
@@ -0,0 +1,467 @@
+//! Usage export (CSV / JSON Lines, per request or grouped) and import for merging machines.
+
+use super::database::{UsageExportRow, UsageImportReport};
+use super::Database;
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Columns of a per-request export, in file order (`date` is derived and ignored on import)
+const ROW_COLUMNS: &[&str] = &[
+    "row_id",
+    "date",
+    "timestamp",
+    "origin",
+    "account_id",
+    "account_name",
+    "model",
+    "session_id",
+    "parent_session_id",
+    "session_source",
+    "request_kind",
+    "request_id",
+    "project_path",
+    "git_branch",
+    "input_tokens",
+    "output_tokens",
+    "cost_usd",
+    "duration_ms",
+    "status_code",
+];
+
+const NUMERIC_COLUMNS: &[&str] = &["timestamp", "input_tokens", "output_tokens", "cost_usd", "duration_ms", "status_code"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Jsonl,
+}
+
+impl ExportFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "jsonl" | "ndjson" | "json" => Some(Self::Jsonl),
+            _ => None,
+        }
+    }
+
+    /// Format implied by a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension().and_then(|e| e.to_str()).and_then(Self::parse)
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Jsonl => "application/x-ndjson",
+        }
+    }
+}
+
+/// Time bucket of a grouped export (local time, like the daily usage report)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    Day,
+    Month,
+}
+
+impl Period {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "day" => Some(Self::Day),
+            "month" => Some(Self::Month),
+            _ => None,
+        }
+    }
+
+    fn bucket(&self, timestamp: i64) -> String {
+        let format = match self {
+            Self::Day => "%Y-%m-%d",
+            Self::Month => "%Y-%m",
+        };
+        chrono::DateTime::from_timestamp(timestamp, 0)
+            .map(|t| t.with_timezone(&chrono::Local).format(format).to_string())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupKey {
+    /// Machine/person (`origin`)
+    Origin,
+    Account,
+    Model,
+    /// Session, with subagents rolled up into their parent
+    Session,
+    Kind,
+    /// Working directory
+    Project,
+    Branch,
+}
+
+impl GroupKey {
+    pub const ALL: [GroupKey; 7] = [
+        GroupKey::Origin,
+        GroupKey::Account,
+        GroupKey::Model,
+        GroupKey::Session,
+        GroupKey::Kind,
+        GroupKey::Project,
+        GroupKey::Branch,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Origin => "origin",
+            Self::Account => "account",
+            Self::Model => "model",
+            Self::Session => "session",
+            Self::Kind => "kind",
+            Self::Project => "project",
+            Self::Branch => "branch",
+        }
+    }
+
+    /// Comma-separated list, e.g. `origin,account`
+    pub fn parse_list(s: &str) -> Result<Vec<Self>> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .map(|k| {
+                Self::ALL
+                    .into_iter()
+                    .find(|g| g.as_str() == k)
+                    .ok_or_else(|| anyhow!("unknown group key: {} (expected origin, account, model, session, kind, project, branch)", k))
+            })
+            .collect()
+    }
+
+    fn value(&self, row: &UsageExportRow, origin: &str) -> String {
+        match self {
+            Self::Origin => row.origin.clone().unwrap_or_else(|| origin.to_string()),
+            Self::Account => row.account_name.clone().unwrap_or_else(|| row.account_id.clone()),
+            Self::Model => row.model.clone(),
+            Self::Session => row
+                .parent_session_id
+                .clone()
+                .or_else(|| row.session_id.clone())
+                .unwrap_or_default(),
+            Self::Kind => row.request_kind.clone().unwrap_or_else(|| "unknown".to_string()),
+            Self::Project => row.project_path.clone().unwrap_or_else(|| "unknown".to_string()),
+            Self::Branch => row.git_branch.clone().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportOptions {
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// Unix timestamps, `until` exclusive
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    /// Label written for this machine's rows (default: `default_origin()`)
+    pub origin: Option<String>,
+    /// Aggregate per day/month; with neither `period` nor `group_by` every request is a line
+    pub period: Option<Period>,
+    #[serde(default)]
+    pub group_by: Vec<GroupKey>,
+}
+
+impl ExportOptions {
+    fn is_grouped(&self) -> bool {
+        self.period.is_some() || !self.group_by.is_empty()
+    }
+}
+
+/// Origin label for rows logged on this machine: the OS user name
+pub fn default_origin() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "local".to_string())
+}
+
+/// Render usage as CSV / JSONL; returns the file content and the number of records
+pub async fn export_usage(db: &Database, options: &ExportOptions) -> Result<(String, usize)> {
+    let rows = db.get_usage_export_rows(options.since, options.until).await?;
+    let origin = options.origin.clone().unwrap_or_else(default_origin);
+    let table = if options.is_grouped() {
+        grouped_table(&rows, options.period, &options.group_by, &origin)
+    } else {
+        row_table(&rows, &origin)
+    };
+    let count = table.rows.len();
+    Ok((table.render(options.format), count))
+}
+
+/// Import a per-request export (CSV or JSONL; detected when `format` is None)
+pub async fn import_usage(
+    db: &Database,
+    content: &str,
+    format: Option<ExportFormat>,
+    fallback_account_id: Option<&str>,
+) -> Result<UsageImportReport> {
+    let rows = parse_rows(content, format)?;
+    db.import_usage_rows(&rows, fallback_account_id).await
+}
+
+struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    fn render(&self, format: ExportFormat) -> String {
+        let mut out = String::new();
+        match format {
+            ExportFormat::Csv => {
+                out.push_str(&self.headers.iter().map(|h| csv_field(h)).collect::<Vec<_>>().join(","));
+                out.push('\n');
+                for row in &self.rows {
+                    let fields: Vec<String> = row
+                        .iter()
+                        .map(|v| match v {
+                            Value::Null => String::new(),
+                            Value::String(s) => csv_field(s),
+                            other => other.to_string(),
+                        })
+                        .collect();
+                    out.push_str(&fields.join(","));
+                    out.push('\n');
+                }
+            }
+            ExportFormat::Jsonl => {
+                for row in &self.rows {
+                    let object: Map<String, Value> = self.headers.iter().cloned().zip(row.iter().cloned()).collect();
+                    out.push_str(&Value::Object(object).to_string());
+                    out.push('\n');
+                }
+            }
+        }
+        out
+    }
+}
+
+fn row_table(rows: &[UsageExportRow], origin: &str) -> Table {
+    let rows = rows
+        .iter()
+        .map(|row| {
+            let mut object = match serde_json::to_value(row) {
+                Ok(Value::Object(object)) => object,
+                _ => Map::new(),
+            };
+            object.insert(
+                "origin".to_string(),
+                Value::String(row.origin.clone().unwrap_or_else(|| origin.to_string())),
+            );
+            let date = chrono::DateTime::from_timestamp(row.timestamp, 0)
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default();
+            object.insert("date".to_string(), Value::String(date));
+            ROW_COLUMNS
+                .iter()
+                .map(|c| object.remove(*c).unwrap_or(Value::Null))
+                .collect()
+        })
+        .collect();
+    Table {
+        headers: ROW_COLUMNS.iter().map(|c| c.to_string()).collect(),
+        rows,
+    }
+}
+
+fn grouped_table(rows: &[UsageExportRow], period: Option<Period>, keys: &[GroupKey], origin: &str) -> Table {
+    // (요청 수, 입력 토큰, 출력 토큰, 비용)
+    let mut groups: BTreeMap<Vec<String>, (i64, i64, i64, f64)> = BTreeMap::new();
+    for row in rows {
+        let mut key: Vec<String> = period.iter().map(|p| p.bucket(row.timestamp)).collect();
+        key.extend(keys.iter().map(|k| k.value(row, origin)));
+        let totals = groups.entry(key).or_default();
+        totals.0 += 1;
+        totals.1 += row.input_tokens;
+        totals.2 += row.output_tokens;
+        totals.3 += row.cost_usd;
+    }
+
+    let mut headers: Vec<String> = period.iter().map(|_| "period".to_string()).collect();
+    headers.extend(keys.iter().map(|k| k.as_str().to_string()));
+    headers.extend(["request_count", "input_tokens", "output_tokens", "cost_usd"].map(String::from));
+
+    let rows = groups
+        .into_iter()
+        .map(|(key, (count, input, output, cost))| {
+            let mut values: Vec<Value> = key.into_iter().map(Value::String).collect();
+            values.extend([
+                Value::from(count),
+                Value::from(input),
+                Value::from(output),
+                Value::from((cost * 1_000_000.0).round() / 1_000_000.0),
+            ]);
+            values
+        })
+        .collect();
+    Table { headers, rows }
+}
+
+fn parse_rows(content: &str, format: Option<ExportFormat>) -> Result<Vec<UsageExportRow>> {
+    let format = format.unwrap_or(if content.trim_start().starts_with('{') {
+        ExportFormat::Jsonl
+    } else {
+        ExportFormat::Csv
+    });
+
+    match format {
+        ExportFormat::Jsonl => content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line).map_err(|e| anyhow!("line {}: {} (only per-request exports can be imported)", i + 1, e))
+            })
+            .collect(),
+        ExportFormat::Csv => {
+            let mut records = parse_csv(content).into_iter();
+            let headers = records.next().unwrap_or_default();
+            if !headers.iter().any(|h| h == "row_id") {
+                bail!("row_id column missing: only per-request exports can be imported");
+            }
+            records
+                .enumerate()
+                .map(|(i, record)| {
+                    let object: Map<String, Value> = headers
+                        .iter()
+                        .zip(record)
+                        .map(|(header, field)| {
+                            let value = if field.is_empty() {
+                                Value::Null
+                            } else if NUMERIC_COLUMNS.contains(&header.as_str()) {
+                                field.parse::<i64>().map(Value::from).or_else(|_| field.parse::<f64>().map(Value::from)).unwrap_or(Value::String(field))
+                            } else {
+                                Value::String(field)
+                            };
+                            (header.clone(), value)
+                        })
+                        .collect();
+                    serde_json::from_value(Value::Object(object)).map_err(|e| anyhow!("record {}: {}", i + 1, e))
+                })
+                .collect()
+        }
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Minimal RFC 4180 reader (quoted fields, doubled quotes, CRLF)
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(row_id: &str, timestamp: i64, account: &str, cost_usd: f64) -> UsageExportRow {
+        UsageExportRow {
+            row_id: row_id.to_string(),
+            timestamp,
+            origin: None,
+            account_id: format!("id-{}", account),
+            account_name: Some(account.to_string()),
+            model: "claude-sonnet-4".to_string(),
+            session_id: Some("s1/agent-1".to_string()),
+            parent_session_id: Some("s1".to_string()),
+            session_source: None,
+            request_kind: Some("subagent".to_string()),
+            request_id: None,
+            project_path: Some("/work/acme".to_string()),
+            git_branch: None,
+            input_tokens: 100,
+            output_tokens: 10,
+            cost_usd,
+            duration_ms: 0,
+            status_code: 200,
+        }
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let mut a = row("r1", 1_760_000_000, "Work, \"main\"", 0.5);
+        a.model = "multi\nline".to_string();
+        let b = row("r2", 1_760_000_100, "GLM", 0.25);
+        let csv = row_table(&[a.clone(), b.clone()], "alice").render(ExportFormat::Csv);
+
+        let parsed = parse_rows(&csv, None).unwrap();
+        a.origin = Some("alice".to_string());
+        assert_eq!(parsed[0], a);
+        assert_eq!(parsed[1].account_name.as_deref(), Some("GLM"));
+
+        let jsonl = row_table(&[b], "alice").render(ExportFormat::Jsonl);
+        assert_eq!(parse_rows(&jsonl, None).unwrap()[0].row_id, "r2");
+    }
+
+    #[test]
+    fn test_grouped_table() {
+        let mut other = row("r3", 1_760_000_000, "GLM", 1.0);
+        other.origin = Some("bob".to_string());
+        let rows = [row("r1", 1_760_000_000, "Work", 0.5), row("r2", 1_760_000_100, "Work", 0.25), other];
+
+        let table = grouped_table(&rows, None, &[GroupKey::Origin, GroupKey::Session], "alice");
+        assert_eq!(table.headers, ["origin", "session", "request_count", "input_tokens", "output_tokens", "cost_usd"]);
+        assert_eq!(table.rows[0][..4], [json_str("alice"), json_str("s1"), Value::from(2), Value::from(200)]);
+        assert_eq!(table.rows[1][0], json_str("bob"));
+
+        // 그룹 결과는 가져오기 불가
+        let csv = table.render(ExportFormat::Csv);
+        assert!(parse_rows(&csv, None).is_err());
+    }
+
+    fn json_str(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+
+    #[test]
+    fn test_parse_group_keys() {
+        assert_eq!(GroupKey::parse_list("origin, account").unwrap(), [GroupKey::Origin, GroupKey::Account]);
+        assert!(GroupKey::parse_list("person").is_err());
+    }
+}